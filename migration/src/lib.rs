@@ -10,6 +10,27 @@ mod m20250417_035316_app_versions;
 mod m20250417_035727_create_fk_apps_icon_file_id_to_files;
 mod m20250417_035735_create_fk_apps_current_version_id_to_app_versions;
 mod m20250417_035743_create_fk_app_versions_apk_file_id_to_files;
+mod m20250422_081530_add_manifest_fields_to_files;
+mod m20250424_023118_add_signing_fields;
+mod m20250426_061204_add_icon_fields_to_files;
+mod m20250429_090512_app_version_artifacts;
+mod m20250502_024417_add_artifact_type_to_files;
+mod m20250506_031542_version_patches;
+mod m20250508_072214_add_update_policy_fields;
+mod m20250512_094531_add_rollout_fields;
+mod m20250514_030812_channels;
+mod m20250516_083027_version_code_to_big_int;
+mod m20250519_041156_add_rollback_fields;
+mod m20250521_062340_add_yank_fields;
+mod m20250523_015934_add_localized_texts;
+mod m20250526_040318_add_targeting;
+mod m20250528_063917_add_compatibility_fields_to_files;
+mod m20250530_020645_add_publish_schedule;
+mod m20250603_072841_update_checks;
+mod m20250605_031426_downloads;
+mod m20250607_054210_webhooks;
+mod m20250607_054236_webhook_deliveries;
+mod m20250609_021547_subscriptions;
 pub struct Migrator;
 
 #[async_trait::async_trait]
@@ -24,6 +45,27 @@ impl MigratorTrait for Migrator {
             Box::new(m20250417_035727_create_fk_apps_icon_file_id_to_files::Migration),
             Box::new(m20250417_035735_create_fk_apps_current_version_id_to_app_versions::Migration),
             Box::new(m20250417_035743_create_fk_app_versions_apk_file_id_to_files::Migration),
+            Box::new(m20250422_081530_add_manifest_fields_to_files::Migration),
+            Box::new(m20250424_023118_add_signing_fields::Migration),
+            Box::new(m20250426_061204_add_icon_fields_to_files::Migration),
+            Box::new(m20250429_090512_app_version_artifacts::Migration),
+            Box::new(m20250502_024417_add_artifact_type_to_files::Migration),
+            Box::new(m20250506_031542_version_patches::Migration),
+            Box::new(m20250508_072214_add_update_policy_fields::Migration),
+            Box::new(m20250512_094531_add_rollout_fields::Migration),
+            Box::new(m20250514_030812_channels::Migration),
+            Box::new(m20250516_083027_version_code_to_big_int::Migration),
+            Box::new(m20250519_041156_add_rollback_fields::Migration),
+            Box::new(m20250521_062340_add_yank_fields::Migration),
+            Box::new(m20250523_015934_add_localized_texts::Migration),
+            Box::new(m20250526_040318_add_targeting::Migration),
+            Box::new(m20250528_063917_add_compatibility_fields_to_files::Migration),
+            Box::new(m20250530_020645_add_publish_schedule::Migration),
+            Box::new(m20250603_072841_update_checks::Migration),
+            Box::new(m20250605_031426_downloads::Migration),
+            Box::new(m20250607_054210_webhooks::Migration),
+            Box::new(m20250607_054236_webhook_deliveries::Migration),
+            Box::new(m20250609_021547_subscriptions::Migration),
             // inject-above (do not remove this comment)
         ]
     }
@@ -0,0 +1,28 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        add_column(m, "files", "package_name", ColType::StringNull).await?;
+        add_column(m, "files", "version_code", ColType::BigIntegerNull).await?;
+        add_column(m, "files", "version_name", ColType::StringNull).await?;
+        add_column(m, "files", "min_sdk_version", ColType::IntegerNull).await?;
+        add_column(m, "files", "target_sdk_version", ColType::IntegerNull).await?;
+        add_column(m, "files", "permissions", ColType::JsonBinaryNull).await?;
+        Ok(())
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        remove_column(m, "files", "permissions").await?;
+        remove_column(m, "files", "target_sdk_version").await?;
+        remove_column(m, "files", "min_sdk_version").await?;
+        remove_column(m, "files", "version_name").await?;
+        remove_column(m, "files", "version_code").await?;
+        remove_column(m, "files", "package_name").await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,22 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        add_column(m, "files", "signer_fingerprints", ColType::JsonBinaryNull).await?;
+        add_column(m, "files", "signing_lineage", ColType::JsonBinaryNull).await?;
+        add_column(m, "apps", "signer_fingerprints", ColType::JsonBinaryNull).await?;
+        Ok(())
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        remove_column(m, "apps", "signer_fingerprints").await?;
+        remove_column(m, "files", "signing_lineage").await?;
+        remove_column(m, "files", "signer_fingerprints").await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,37 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        add_column(m, "files", "label", ColType::StringNull).await?;
+        add_column(m, "files", "icon_file_id", ColType::IntegerNull).await?;
+        m.create_foreign_key(
+            ForeignKey::create()
+                .name("fk-files-icon_file_id-to-files")
+                .from(Alias::new("files"), Alias::new("icon_file_id"))
+                .to(Alias::new("files"), Alias::new("id"))
+                .on_delete(ForeignKeyAction::SetNull)
+                .on_update(ForeignKeyAction::Cascade)
+                .to_owned(),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        m.drop_foreign_key(
+            ForeignKey::drop()
+                .name("fk-files-icon_file_id-to-files")
+                .table(Alias::new("files"))
+                .to_owned(),
+        )
+        .await?;
+        remove_column(m, "files", "icon_file_id").await?;
+        remove_column(m, "files", "label").await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,29 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        create_table(m, "app_version_artifacts",
+            &[
+            
+            ("id", ColType::PkAuto),
+            
+            ("kind", ColType::String),
+            ("abi", ColType::StringNull),
+            ("density", ColType::IntegerNull),
+            ],
+            &[
+            ("app_version", ""),
+            ("file", ""),
+            ]
+        ).await
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        drop_table(m, "app_version_artifacts").await
+    }
+}
@@ -0,0 +1,39 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        create_table(m, "version_patches",
+            &[
+            
+            ("id", ColType::PkAuto),
+            
+            ("algorithm", ColType::String),
+            ],
+            &[
+            ("app_version", ""),
+            ("app_version", "from_version_id"),
+            ("file", ""),
+            ]
+        ).await?;
+        m.create_index(
+            Index::create()
+                .name("idx-version_patches-from_version_id-app_version_id")
+                .table(Alias::new("version_patches"))
+                .col(Alias::new("from_version_id"))
+                .col(Alias::new("app_version_id"))
+                .unique()
+                .to_owned(),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        drop_table(m, "version_patches").await
+    }
+}
@@ -0,0 +1,22 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        add_column(m, "apps", "min_supported_version_code", ColType::BigIntegerNull).await?;
+        add_column(m, "app_versions", "min_supported_version_code", ColType::BigIntegerNull).await?;
+        add_column(m, "app_versions", "mandatory", ColType::BooleanWithDefault(false)).await?;
+        Ok(())
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        remove_column(m, "app_versions", "mandatory").await?;
+        remove_column(m, "app_versions", "min_supported_version_code").await?;
+        remove_column(m, "apps", "min_supported_version_code").await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,20 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        add_column(m, "app_versions", "rollout_percentage", ColType::SmallIntegerWithDefault(100)).await?;
+        add_column(m, "app_versions", "rollout_state", ColType::StringNull).await?;
+        Ok(())
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        remove_column(m, "app_versions", "rollout_state").await?;
+        remove_column(m, "app_versions", "rollout_percentage").await?;
+        Ok(())
+    }
+}
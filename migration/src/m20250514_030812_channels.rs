@@ -0,0 +1,49 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        create_table(m, "channels",
+            &[
+            
+            ("id", ColType::PkAuto),
+            
+            ("name", ColType::String),
+            ("position", ColType::Integer),
+            ("current_version_id", ColType::IntegerNull),
+            ],
+            &[
+            ("app", ""),
+            ]
+        ).await?;
+        m.create_foreign_key(
+            ForeignKey::create()
+                .name("fk-channels-current_version_id-to-app_versions")
+                .from(Alias::new("channels"), Alias::new("current_version_id"))
+                .to(Alias::new("app_versions"), Alias::new("id"))
+                .on_delete(ForeignKeyAction::SetNull)
+                .on_update(ForeignKeyAction::Cascade)
+                .to_owned(),
+        )
+        .await?;
+        m.create_index(
+            Index::create()
+                .name("idx-channels-app_id-name")
+                .table(Alias::new("channels"))
+                .col(Alias::new("app_id"))
+                .col(Alias::new("name"))
+                .unique()
+                .to_owned(),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        drop_table(m, "channels").await
+    }
+}
@@ -0,0 +1,45 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        // postgres only casts text to bigint when told how
+        m.get_connection()
+            .execute_unprepared(
+                "ALTER TABLE app_versions ALTER COLUMN version_code TYPE bigint USING version_code::bigint",
+            )
+            .await?;
+        m.create_index(
+            Index::create()
+                .name("idx-app_versions-app_id-version_code")
+                .table(Alias::new("app_versions"))
+                .col(Alias::new("app_id"))
+                .col(Alias::new("version_code"))
+                .unique()
+                .to_owned(),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        m.drop_index(
+            Index::drop()
+                .name("idx-app_versions-app_id-version_code")
+                .table(Alias::new("app_versions"))
+                .to_owned(),
+        )
+        .await?;
+        m.alter_table(
+            Table::alter()
+                .table(Alias::new("app_versions"))
+                .modify_column(ColumnDef::new(Alias::new("version_code")).string().not_null())
+                .to_owned(),
+        )
+        .await?;
+        Ok(())
+    }
+}
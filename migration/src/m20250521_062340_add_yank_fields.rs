@@ -0,0 +1,22 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        add_column(m, "app_versions", "yanked_at", ColType::TimestampWithTimeZoneNull).await?;
+        add_column(m, "app_versions", "yank_reason", ColType::TextNull).await?;
+        add_column(m, "app_versions", "yank_forced", ColType::BooleanWithDefault(false)).await?;
+        Ok(())
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        remove_column(m, "app_versions", "yank_forced").await?;
+        remove_column(m, "app_versions", "yank_reason").await?;
+        remove_column(m, "app_versions", "yanked_at").await?;
+        Ok(())
+    }
+}
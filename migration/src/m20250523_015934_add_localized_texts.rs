@@ -0,0 +1,20 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        add_column(m, "apps", "localized_descriptions", ColType::JsonBinaryNull).await?;
+        add_column(m, "app_versions", "localized_release_notes", ColType::JsonBinaryNull).await?;
+        Ok(())
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        remove_column(m, "app_versions", "localized_release_notes").await?;
+        remove_column(m, "apps", "localized_descriptions").await?;
+        Ok(())
+    }
+}
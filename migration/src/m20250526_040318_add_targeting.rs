@@ -0,0 +1,20 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        add_column(m, "app_versions", "targeting", ColType::JsonBinaryNull).await?;
+        add_column(m, "channels", "targeting", ColType::JsonBinaryNull).await?;
+        Ok(())
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        remove_column(m, "channels", "targeting").await?;
+        remove_column(m, "app_versions", "targeting").await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,22 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        add_column(m, "files", "max_sdk_version", ColType::IntegerNull).await?;
+        add_column(m, "files", "native_abis", ColType::JsonBinaryNull).await?;
+        add_column(m, "files", "required_features", ColType::JsonBinaryNull).await?;
+        Ok(())
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        remove_column(m, "files", "required_features").await?;
+        remove_column(m, "files", "native_abis").await?;
+        remove_column(m, "files", "max_sdk_version").await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,20 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        add_column(m, "app_versions", "scheduled_publish_at", ColType::TimestampWithTimeZoneNull).await?;
+        add_column(m, "app_versions", "scheduled_rollout_percentage", ColType::SmallIntegerNull).await?;
+        Ok(())
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        remove_column(m, "app_versions", "scheduled_rollout_percentage").await?;
+        remove_column(m, "app_versions", "scheduled_publish_at").await?;
+        Ok(())
+    }
+}
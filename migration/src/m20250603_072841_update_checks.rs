@@ -0,0 +1,42 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        create_table(m, "update_checks",
+            &[
+            
+            ("id", ColType::PkAuto),
+            
+            ("version_code", ColType::BigInteger),
+            ("offered_version_code", ColType::BigIntegerNull),
+            ("channel", ColType::StringNull),
+            ("device_hash", ColType::StringNull),
+            ("sdk_int", ColType::IntegerNull),
+            ("abi", ColType::StringNull),
+            ("checked_on", ColType::Date),
+            ],
+            &[
+            ("app", ""),
+            ]
+        ).await?;
+        m.create_index(
+            Index::create()
+                .name("idx-update_checks-app_id-checked_on")
+                .table(Alias::new("update_checks"))
+                .col(Alias::new("app_id"))
+                .col(Alias::new("checked_on"))
+                .to_owned(),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        drop_table(m, "update_checks").await
+    }
+}
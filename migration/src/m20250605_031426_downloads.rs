@@ -0,0 +1,60 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        create_table(m, "downloads",
+            &[
+            
+            ("id", ColType::PkAuto),
+            
+            ("app_id", ColType::IntegerNull),
+            ("app_version_id", ColType::IntegerNull),
+            ("client_ip", ColType::StringNull),
+            ("user_agent", ColType::TextNull),
+            ("size_bytes", ColType::BigInteger),
+            ("bytes_sent", ColType::BigInteger),
+            ("completed", ColType::Boolean),
+            ("file_id", ColType::IntegerNull),
+            ],
+            &[
+            ]
+        ).await?;
+        // downloads outlive the files, apps and versions they were made for
+        for (column, table) in [
+            ("file_id", "files"),
+            ("app_id", "apps"),
+            ("app_version_id", "app_versions"),
+        ] {
+            m.create_foreign_key(
+                ForeignKey::create()
+                    .name(format!("fk-downloads-{column}-to-{table}"))
+                    .from(Alias::new("downloads"), Alias::new(column))
+                    .to(Alias::new(table), Alias::new("id"))
+                    .on_delete(ForeignKeyAction::SetNull)
+                    .on_update(ForeignKeyAction::Cascade)
+                    .to_owned(),
+            )
+            .await?;
+        }
+        for column in ["app_id", "app_version_id"] {
+            m.create_index(
+                Index::create()
+                    .name(format!("idx-downloads-{column}"))
+                    .table(Alias::new("downloads"))
+                    .col(Alias::new(column))
+                    .to_owned(),
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        drop_table(m, "downloads").await
+    }
+}
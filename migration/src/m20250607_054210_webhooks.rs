@@ -0,0 +1,41 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        create_table(m, "webhooks",
+            &[
+            
+            ("id", ColType::PkAuto),
+            
+            ("url", ColType::String),
+            ("secret", ColType::String),
+            ("events", ColType::JsonBinary),
+            ("active", ColType::BooleanWithDefault(true)),
+            ("app_id", ColType::IntegerNull),
+            ],
+            &[
+            ]
+        ).await?;
+        // webhooks without an app hear about every app
+        m.create_foreign_key(
+            ForeignKey::create()
+                .name("fk-webhooks-app_id-to-apps")
+                .from(Alias::new("webhooks"), Alias::new("app_id"))
+                .to(Alias::new("apps"), Alias::new("id"))
+                .on_delete(ForeignKeyAction::Cascade)
+                .on_update(ForeignKeyAction::Cascade)
+                .to_owned(),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        drop_table(m, "webhooks").await
+    }
+}
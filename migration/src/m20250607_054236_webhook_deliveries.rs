@@ -0,0 +1,42 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        create_table(m, "webhook_deliveries",
+            &[
+            
+            ("id", ColType::PkAuto),
+            
+            ("event", ColType::String),
+            ("payload", ColType::JsonBinary),
+            ("status", ColType::String),
+            ("attempts", ColType::IntegerWithDefault(0)),
+            ("next_attempt_at", ColType::TimestampWithTimeZoneNull),
+            ("response_status", ColType::SmallIntegerNull),
+            ("error", ColType::TextNull),
+            ("delivered_at", ColType::TimestampWithTimeZoneNull),
+            ],
+            &[
+            ("webhook", ""),
+            ]
+        ).await?;
+        m.create_index(
+            Index::create()
+                .name("idx-webhook_deliveries-next_attempt_at")
+                .table(Alias::new("webhook_deliveries"))
+                .col(Alias::new("next_attempt_at"))
+                .to_owned(),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        drop_table(m, "webhook_deliveries").await
+    }
+}
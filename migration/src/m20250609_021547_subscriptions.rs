@@ -0,0 +1,50 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        create_table(m, "subscriptions",
+            &[
+            
+            ("id", ColType::PkAuto),
+            
+            ("channel_id", ColType::IntegerNull),
+            ("locale", ColType::StringNull),
+            ("unsubscribe_token", ColType::StringUniq),
+            ],
+            &[
+            ("user", ""),
+            ("app", ""),
+            ]
+        ).await?;
+        // subscriptions without a channel follow the app's releases
+        m.create_foreign_key(
+            ForeignKey::create()
+                .name("fk-subscriptions-channel_id-to-channels")
+                .from(Alias::new("subscriptions"), Alias::new("channel_id"))
+                .to(Alias::new("channels"), Alias::new("id"))
+                .on_delete(ForeignKeyAction::Cascade)
+                .on_update(ForeignKeyAction::Cascade)
+                .to_owned(),
+        )
+        .await?;
+        m.create_index(
+            Index::create()
+                .name("idx-subscriptions-app_id-channel_id")
+                .table(Alias::new("subscriptions"))
+                .col(Alias::new("app_id"))
+                .col(Alias::new("channel_id"))
+                .to_owned(),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        drop_table(m, "subscriptions").await
+    }
+}
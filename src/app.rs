@@ -16,7 +16,15 @@ use std::path::Path;
 use std::sync::Arc;
 
 #[allow(unused_imports)]
-use crate::{controllers, models::_entities::users, tasks, workers::downloader::DownloadWorker};
+use crate::{
+    controllers,
+    models::_entities::users,
+    tasks,
+    workers::{
+        announcer::AnnounceWorker, downloader::DownloadWorker, patcher::PatchWorker,
+        webhook::WebhookWorker,
+    },
+};
 
 pub struct App;
 #[async_trait]
@@ -49,6 +57,9 @@ impl Hooks for App {
 
     fn routes(_ctx: &AppContext) -> AppRoutes {
         AppRoutes::with_default_routes() // controller routes below
+            .add_route(controllers::subscription::routes())
+            .add_route(controllers::webhook::routes())
+            .add_route(controllers::build::routes())
             .add_route(controllers::app_version::routes())
             .add_route(controllers::app::routes())
             .add_route(controllers::file::routes())
@@ -56,12 +67,16 @@ impl Hooks for App {
             .add_route(controllers::auth::routes())
     }
     async fn connect_workers(ctx: &AppContext, queue: &Queue) -> Result<()> {
+        queue.register(AnnounceWorker::build(ctx)).await?;
         queue.register(DownloadWorker::build(ctx)).await?;
+        queue.register(PatchWorker::build(ctx)).await?;
+        queue.register(WebhookWorker::build(ctx)).await?;
         Ok(())
     }
 
     #[allow(unused_variables)]
     fn register_tasks(tasks: &mut Tasks) {
+        tasks.register(tasks::publish_scheduled::PublishScheduled);
         // tasks-inject (do not remove)
     }
     async fn truncate(ctx: &AppContext) -> Result<()> {
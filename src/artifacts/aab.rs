@@ -0,0 +1,60 @@
+//! Android App Bundle inspection. Bundles keep their manifests as protocol
+//! buffers instead of binary XML, the metadata read from them is the same.
+use std::io::Cursor;
+
+use zip::ZipArchive;
+
+use super::{
+    apk::{manifest_metadata, native_abis, read_entry},
+    chunk::ResValue,
+    proto::{self, Fields, Value},
+    ArtifactError, ArtifactMetadata, ArtifactType, Result,
+};
+
+pub const BUNDLE_CONFIG_PATH: &str = "BundleConfig.pb";
+pub const MANIFEST_PATH: &str = "base/manifest/AndroidManifest.xml";
+
+/// The parts of `android.bundle.BundleConfig` we keep.
+#[derive(Debug, Clone, Default)]
+pub struct BundleConfig {
+    /// Version of bundletool that built the bundle
+    pub bundletool_version: Option<String>,
+}
+
+impl BundleConfig {
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        let mut config = Self::default();
+        for field in Fields::new(data) {
+            if let (1, Value::Bytes(bundletool)) = field? {
+                for field in Fields::new(bundletool) {
+                    if let (2, value) = field? {
+                        config.bundletool_version = value.as_str().map(ToOwned::to_owned);
+                    }
+                }
+            }
+        }
+        Ok(config)
+    }
+}
+
+/// Reads the package and version information out of the base module of a
+/// bundle.
+pub fn inspect(bytes: &[u8]) -> Result<ArtifactMetadata> {
+    let mut archive = ZipArchive::new(Cursor::new(bytes))?;
+    let config = BundleConfig::parse(&read_entry(&mut archive, BUNDLE_CONFIG_PATH)?)?;
+    if config.bundletool_version.is_none() {
+        return Err(ArtifactError::Missing("bundletool version"));
+    }
+
+    let manifest = proto::parse_xml(&read_entry(&mut archive, MANIFEST_PATH)?)?;
+    let mut meta = manifest_metadata(&manifest)?;
+    meta.artifact_type = ArtifactType::Aab;
+    meta.native_abis = native_abis(&archive, "base/lib/");
+    // resources are protobuf encoded too, only literal labels are resolved
+    meta.label = manifest
+        .child("application")
+        .and_then(|a| a.android_attr("label"))
+        .and_then(ResValue::as_str)
+        .map(ToOwned::to_owned);
+    Ok(meta)
+}
@@ -0,0 +1,162 @@
+//! Android APK inspection.
+use std::io::{Cursor, Read, Seek};
+
+use zip::ZipArchive;
+
+use super::{
+    arsc::ResourceTable,
+    axml::{self, XmlElement},
+    chunk::ResValue,
+    signing, ArtifactError, ArtifactIcon, ArtifactMetadata, Result,
+};
+
+pub const MIME: &str = "application/vnd.android.package-archive";
+pub const MANIFEST_PATH: &str = "AndroidManifest.xml";
+pub const RESOURCES_PATH: &str = "resources.arsc";
+
+/// Icon formats we can store and serve as is. Adaptive icons are XML and
+/// are skipped in favour of their raster fallbacks.
+const ICON_FORMATS: &[(&str, &str)] = &[("png", "image/png"), ("webp", "image/webp")];
+
+/// Reads the package and version information out of an APK, verifies its
+/// signatures and resolves the launcher label and icon.
+pub fn inspect(bytes: &[u8]) -> Result<ArtifactMetadata> {
+    let mut archive = ZipArchive::new(Cursor::new(bytes))?;
+    let manifest = axml::parse(&read_entry(&mut archive, MANIFEST_PATH)?)?;
+    let mut meta = manifest_metadata(&manifest)?;
+    meta.signing = Some(signing::verify(bytes)?);
+    meta.native_abis = native_abis(&archive, "lib/");
+
+    if let Some(application) = manifest.child("application") {
+        let resources = if archive.index_for_name(RESOURCES_PATH).is_some() {
+            ResourceTable::parse(&read_entry(&mut archive, RESOURCES_PATH)?)?
+        } else {
+            ResourceTable::default()
+        };
+        meta.label = match application.android_attr("label") {
+            Some(ResValue::String(label)) => Some(label.clone()),
+            Some(ResValue::Reference(id)) => resources.string(*id),
+            _ => None,
+        };
+        meta.icon = match application.android_attr("icon") {
+            Some(ResValue::Reference(id)) => resources
+                .best_file(*id, |path| icon_mime(path).is_some())
+                .map(|path| read_icon(&mut archive, &path))
+                .transpose()?,
+            _ => None,
+        };
+    }
+    Ok(meta)
+}
+
+fn icon_mime(path: &str) -> Option<&'static str> {
+    let ext = std::path::Path::new(path).extension()?;
+    ICON_FORMATS
+        .iter()
+        .find(|(e, _)| ext.eq_ignore_ascii_case(e))
+        .map(|(_, mime)| *mime)
+}
+
+fn read_icon<R: Read + Seek>(archive: &mut ZipArchive<R>, path: &str) -> Result<ArtifactIcon> {
+    Ok(ArtifactIcon {
+        name: path.rsplit('/').next().unwrap_or(path).to_owned(),
+        mime: icon_mime(path).unwrap_or_default(),
+        bytes: read_entry(archive, path)?,
+    })
+}
+
+/// ABIs with shared libraries under `lib_dir`, laid out as
+/// `<lib_dir><abi>/<name>.so`.
+pub(crate) fn native_abis<R: Read + Seek>(archive: &ZipArchive<R>, lib_dir: &str) -> Vec<String> {
+    let mut abis: Vec<String> = archive
+        .file_names()
+        .filter_map(|name| {
+            let (abi, lib) = name.strip_prefix(lib_dir)?.split_once('/')?;
+            (!abi.is_empty() && !lib.contains('/') && lib.ends_with(".so")).then(|| abi.to_owned())
+        })
+        .collect();
+    abis.sort();
+    abis.dedup();
+    abis
+}
+
+pub(crate) fn read_entry<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    name: &str,
+) -> Result<Vec<u8>> {
+    let mut entry = archive.by_name(name).map_err(|err| match err {
+        zip::result::ZipError::FileNotFound => {
+            ArtifactError::Malformed(format!("{name} not found"))
+        }
+        err => err.into(),
+    })?;
+    let mut buf = Vec::with_capacity(usize::try_from(entry.size()).unwrap_or_default());
+    entry.read_to_end(&mut buf)?;
+    Ok(buf)
+}
+
+/// Extracts [`ArtifactMetadata`] from a decoded `<manifest>` element.
+pub(crate) fn manifest_metadata(manifest: &XmlElement) -> Result<ArtifactMetadata> {
+    if manifest.name != "manifest" {
+        return Err(ArtifactError::Malformed(format!(
+            "unexpected manifest root <{}>",
+            manifest.name
+        )));
+    }
+
+    let package_name = manifest
+        .attr("package")
+        .and_then(|v| v.as_str())
+        .ok_or(ArtifactError::Missing("package name"))?
+        .to_owned();
+    let version_code = manifest
+        .android_attr("versionCode")
+        .and_then(|v| v.as_i64())
+        .ok_or(ArtifactError::Missing("versionCode"))?;
+    let version_name = manifest
+        .android_attr("versionName")
+        .and_then(|v| v.as_str())
+        .map(ToOwned::to_owned);
+
+    let uses_sdk = manifest.child("uses-sdk");
+    let sdk_attr = |name: &str| {
+        uses_sdk
+            .and_then(|e| e.android_attr(name))
+            .and_then(|v| v.as_i64())
+            .and_then(|v| i32::try_from(v).ok())
+    };
+    // an absent minSdkVersion means API level 1, and targetSdkVersion defaults to it
+    let min_sdk_version = sdk_attr("minSdkVersion").or(Some(1));
+    let target_sdk_version = sdk_attr("targetSdkVersion").or(min_sdk_version);
+    let max_sdk_version = sdk_attr("maxSdkVersion");
+
+    let permissions = manifest
+        .children
+        .iter()
+        .filter(|e| e.name == "uses-permission" || e.name == "uses-permission-sdk-23")
+        .filter_map(|e| e.android_attr("name").and_then(|v| v.as_str()))
+        .map(ToOwned::to_owned)
+        .collect();
+    // features are required unless marked `android:required="false"`, and
+    // `glEsVersion` entries carry no name
+    let required_features = manifest
+        .children
+        .iter()
+        .filter(|e| e.name == "uses-feature")
+        .filter(|e| e.android_attr("required").and_then(ResValue::as_bool) != Some(false))
+        .filter_map(|e| e.android_attr("name").and_then(|v| v.as_str()))
+        .map(ToOwned::to_owned)
+        .collect();
+
+    Ok(ArtifactMetadata {
+        package_name,
+        version_code,
+        version_name,
+        min_sdk_version,
+        target_sdk_version,
+        max_sdk_version,
+        permissions,
+        required_features,
+        ..Default::default()
+    })
+}
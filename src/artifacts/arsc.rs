@@ -0,0 +1,227 @@
+//! Decoder for the compiled resource table (`resources.arsc`), just enough to
+//! resolve the string and file references used by the manifest.
+use std::collections::HashMap;
+
+use super::{
+    chunk::{
+        children, read_u16, read_u32, read_u8, ChunkHeader, ResValue, StringPool,
+        RES_STRING_POOL_TYPE,
+    },
+    ArtifactError, Result,
+};
+
+const RES_TABLE_TYPE: u16 = 0x0002;
+const RES_TABLE_PACKAGE_TYPE: u16 = 0x0200;
+const RES_TABLE_TYPE_TYPE: u16 = 0x0201;
+
+const FLAG_SPARSE: u8 = 0x01;
+const FLAG_OFFSET16: u8 = 0x02;
+
+const ENTRY_FLAG_COMPLEX: u16 = 0x0001;
+const ENTRY_FLAG_COMPACT: u16 = 0x0008;
+
+const NO_ENTRY: u32 = 0xffff_ffff;
+const NO_ENTRY16: u16 = 0xffff;
+
+/// `ResTable_config` density values that do not name a real density.
+const DENSITY_DEFAULT: u16 = 0;
+const DENSITY_MEDIUM: u16 = 160;
+const DENSITY_ANY: u16 = 0xfffe;
+const DENSITY_NONE: u16 = 0xffff;
+
+/// References are followed at most this many times, guarding against cycles.
+const MAX_REFERENCE_DEPTH: usize = 8;
+
+/// The parts of a `ResTable_config` we select values by.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResConfig {
+    pub language: String,
+    pub density: u16,
+}
+
+impl ResConfig {
+    /// Density in dpi used to rank raster alternatives. Unqualified resources
+    /// are `mdpi`, `anydpi` and `nodpi` rank below every real density.
+    fn raster_density(&self) -> u16 {
+        match self.density {
+            DENSITY_DEFAULT => DENSITY_MEDIUM,
+            DENSITY_ANY | DENSITY_NONE => 0,
+            density => density,
+        }
+    }
+}
+
+/// The values of one resource across configurations.
+pub type Alternatives = [(ResConfig, ResValue)];
+
+/// Simple (non bag) values of a resource table keyed by resource id.
+#[derive(Debug, Default)]
+pub struct ResourceTable {
+    values: HashMap<u32, Vec<(ResConfig, ResValue)>>,
+}
+
+impl ResourceTable {
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        let root = ChunkHeader::read(data, 0)?;
+        if root.kind != RES_TABLE_TYPE {
+            return Err(ArtifactError::Malformed(format!(
+                "unexpected resource table chunk type 0x{:04x}",
+                root.kind
+            )));
+        }
+
+        let mut table = Self::default();
+        let mut pool = StringPool::default();
+        for chunk in children(data, root.body(), root.end()) {
+            let chunk = chunk?;
+            match chunk.kind {
+                RES_STRING_POOL_TYPE => pool = StringPool::parse(data, &chunk)?,
+                RES_TABLE_PACKAGE_TYPE => table.read_package(data, &chunk, &pool)?,
+                _ => {}
+            }
+        }
+        Ok(table)
+    }
+
+    /// All configurations of a resource.
+    pub fn get(&self, id: u32) -> &Alternatives {
+        self.values.get(&id).map_or(&[], Vec::as_slice)
+    }
+
+    /// Resolves a string resource, preferring the default locale.
+    pub fn string(&self, id: u32) -> Option<String> {
+        self.resolve(id, 0, &|values| {
+            values
+                .iter()
+                .min_by_key(|(config, _)| !config.language.is_empty())
+                .map(|(_, value)| value.clone())
+        })
+        .and_then(|v| v.as_str().map(ToOwned::to_owned))
+    }
+
+    /// Resolves a file resource to the path of its highest density
+    /// alternative accepted by `filter`.
+    pub fn best_file(&self, id: u32, filter: impl Fn(&str) -> bool) -> Option<String> {
+        let pick = |values: &Alternatives| {
+            values
+                .iter()
+                .filter(|(_, value)| match value {
+                    ResValue::String(path) => filter(path),
+                    ResValue::Reference(_) => true,
+                    _ => false,
+                })
+                .max_by_key(|(config, value)| {
+                    // real files win over aliases of the same density
+                    (
+                        config.raster_density(),
+                        matches!(value, ResValue::String(_)),
+                    )
+                })
+                .map(|(_, value)| value.clone())
+        };
+        self.resolve(id, 0, &pick)
+            .and_then(|v| v.as_str().map(ToOwned::to_owned))
+    }
+
+    fn resolve(
+        &self,
+        id: u32,
+        depth: usize,
+        pick: &dyn Fn(&Alternatives) -> Option<ResValue>,
+    ) -> Option<ResValue> {
+        match pick(self.get(id))? {
+            ResValue::Reference(next) if depth < MAX_REFERENCE_DEPTH => {
+                self.resolve(next, depth + 1, pick)
+            }
+            ResValue::Reference(_) => None,
+            value => Some(value),
+        }
+    }
+
+    fn read_package(
+        &mut self,
+        data: &[u8],
+        package: &ChunkHeader,
+        pool: &StringPool,
+    ) -> Result<()> {
+        let package_id = read_u32(data, package.offset + 8)?;
+        for chunk in children(data, package.body(), package.end()) {
+            let chunk = chunk?;
+            if chunk.kind == RES_TABLE_TYPE_TYPE {
+                self.read_type(data, &chunk, package_id, pool)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads a `ResTable_type` chunk, the entries of one type in one
+    /// configuration.
+    fn read_type(
+        &mut self,
+        data: &[u8],
+        chunk: &ChunkHeader,
+        package_id: u32,
+        pool: &StringPool,
+    ) -> Result<()> {
+        let type_id = u32::from(read_u8(data, chunk.offset + 8)?);
+        let flags = read_u8(data, chunk.offset + 9)?;
+        let entry_count = read_u32(data, chunk.offset + 12)? as usize;
+        let entries_start = chunk.offset + read_u32(data, chunk.offset + 16)? as usize;
+        let config = read_config(data, chunk.offset + 20)?;
+
+        let offsets = chunk.body();
+        for i in 0..entry_count {
+            let (index, offset) = if flags & FLAG_SPARSE != 0 {
+                let index = read_u16(data, offsets + i * 4)?;
+                let offset = u32::from(read_u16(data, offsets + i * 4 + 2)?) * 4;
+                (u32::from(index), offset)
+            } else if flags & FLAG_OFFSET16 != 0 {
+                match read_u16(data, offsets + i * 2)? {
+                    NO_ENTRY16 => continue,
+                    offset => (i as u32, u32::from(offset) * 4),
+                }
+            } else {
+                match read_u32(data, offsets + i * 4)? {
+                    NO_ENTRY => continue,
+                    offset => (i as u32, offset),
+                }
+            };
+
+            let entry = entries_start + offset as usize;
+            let size = read_u16(data, entry)?;
+            let entry_flags = read_u16(data, entry + 2)?;
+            let value = if entry_flags & ENTRY_FLAG_COMPACT != 0 {
+                // the data type lives in the high byte of the flags
+                let data_type = (entry_flags >> 8) as u8;
+                ResValue::from_raw(data_type, read_u32(data, entry + 4)?, pool)
+            } else if entry_flags & ENTRY_FLAG_COMPLEX != 0 {
+                // bags (styles, arrays, plurals) are never needed here
+                continue;
+            } else {
+                ResValue::read(data, entry + size as usize, pool)?
+            };
+
+            let id = (package_id << 24) | (type_id << 16) | index;
+            self.values
+                .entry(id)
+                .or_default()
+                .push((config.clone(), value));
+        }
+        Ok(())
+    }
+}
+
+fn read_config(data: &[u8], offset: usize) -> Result<ResConfig> {
+    let language = [read_u8(data, offset + 8)?, read_u8(data, offset + 9)?];
+    Ok(ResConfig {
+        // three letter codes are packed into the two bytes, keep them as hex
+        language: if language[0] & 0x80 == 0 {
+            String::from_utf8_lossy(&language)
+                .trim_end_matches('\0')
+                .to_owned()
+        } else {
+            hex::encode(language)
+        },
+        density: read_u16(data, offset + 14)?,
+    })
+}
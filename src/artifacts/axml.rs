@@ -0,0 +1,166 @@
+//! Decoder for Android binary XML (AXML), the format `aapt` compiles
+//! `AndroidManifest.xml` into.
+use super::{
+    chunk::{
+        children, read_u16, read_u32, ChunkHeader, ResValue, StringPool, NO_INDEX,
+        RES_STRING_POOL_TYPE,
+    },
+    ArtifactError, Result,
+};
+
+pub const ANDROID_NS: &str = "http://schemas.android.com/apk/res/android";
+
+const RES_XML_TYPE: u16 = 0x0003;
+const RES_XML_START_ELEMENT_TYPE: u16 = 0x0102;
+const RES_XML_END_ELEMENT_TYPE: u16 = 0x0103;
+const RES_XML_RESOURCE_MAP_TYPE: u16 = 0x0180;
+
+/// Resource ids of the framework attributes we look up. Obfuscated APKs strip
+/// attribute names, so lookups fall back to these ids.
+const ANDROID_ATTRS: &[(&str, u32)] = &[
+    ("label", 0x0101_0001),
+    ("icon", 0x0101_0002),
+    ("name", 0x0101_0003),
+    ("minSdkVersion", 0x0101_020c),
+    ("versionCode", 0x0101_021b),
+    ("versionName", 0x0101_021c),
+    ("targetSdkVersion", 0x0101_0270),
+    ("maxSdkVersion", 0x0101_0271),
+    ("required", 0x0101_028e),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XmlAttribute {
+    pub namespace: Option<String>,
+    pub name: String,
+    pub resource_id: Option<u32>,
+    pub value: ResValue,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct XmlElement {
+    pub name: String,
+    pub attributes: Vec<XmlAttribute>,
+    pub children: Vec<XmlElement>,
+}
+
+impl XmlElement {
+    /// Looks up an attribute without a namespace, e.g. `package` on `<manifest>`.
+    pub fn attr(&self, name: &str) -> Option<&ResValue> {
+        self.attributes
+            .iter()
+            .find(|a| a.namespace.is_none() && a.name == name)
+            .map(|a| &a.value)
+    }
+
+    /// Looks up an `android:` attribute by name or by its framework resource id.
+    pub fn android_attr(&self, name: &str) -> Option<&ResValue> {
+        let resource_id = ANDROID_ATTRS
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, id)| *id);
+        self.attributes
+            .iter()
+            .find(|a| {
+                (a.namespace.as_deref() == Some(ANDROID_NS) && a.name == name)
+                    || (resource_id.is_some() && a.resource_id == resource_id)
+            })
+            .map(|a| &a.value)
+    }
+
+    /// Direct children with the given tag name.
+    pub fn children_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Self> + 'a {
+        self.children.iter().filter(move |c| c.name == name)
+    }
+
+    pub fn child(&self, name: &str) -> Option<&Self> {
+        self.children.iter().find(|c| c.name == name)
+    }
+}
+
+/// Decodes a binary XML document and returns its root element.
+pub fn parse(data: &[u8]) -> Result<XmlElement> {
+    let root = ChunkHeader::read(data, 0)?;
+    if root.kind != RES_XML_TYPE {
+        return Err(ArtifactError::Malformed(format!(
+            "unexpected binary xml chunk type 0x{:04x}",
+            root.kind
+        )));
+    }
+
+    let mut pool = StringPool::default();
+    let mut resource_map: Vec<u32> = vec![];
+    let mut stack: Vec<XmlElement> = vec![];
+
+    for chunk in children(data, root.body(), root.end()) {
+        let chunk = chunk?;
+        match chunk.kind {
+            RES_STRING_POOL_TYPE => pool = StringPool::parse(data, &chunk)?,
+            RES_XML_RESOURCE_MAP_TYPE => {
+                resource_map = (chunk.body()..chunk.end())
+                    .step_by(4)
+                    .map(|offset| read_u32(data, offset))
+                    .collect::<Result<_>>()?;
+            }
+            RES_XML_START_ELEMENT_TYPE => {
+                stack.push(read_element(data, &chunk, &pool, &resource_map)?);
+            }
+            RES_XML_END_ELEMENT_TYPE => {
+                let element = stack
+                    .pop()
+                    .ok_or_else(|| ArtifactError::Malformed("unbalanced end element".into()))?;
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(element),
+                    None => return Ok(element),
+                }
+            }
+            // namespaces, cdata and unknown chunks carry nothing we need
+            _ => {}
+        }
+    }
+
+    Err(ArtifactError::Malformed(
+        "binary xml has no root element".into(),
+    ))
+}
+
+fn read_element(
+    data: &[u8],
+    chunk: &ChunkHeader,
+    pool: &StringPool,
+    resource_map: &[u32],
+) -> Result<XmlElement> {
+    // ResXMLTree_attrExt follows the node header
+    let ext = chunk.body();
+    let name = pool
+        .get(read_u32(data, ext + 4)?)
+        .unwrap_or_default()
+        .to_owned();
+    let attribute_start = read_u16(data, ext + 8)? as usize;
+    let attribute_size = read_u16(data, ext + 10)? as usize;
+    let attribute_count = read_u16(data, ext + 12)? as usize;
+
+    let mut attributes = Vec::with_capacity(attribute_count);
+    for i in 0..attribute_count {
+        let offset = ext + attribute_start + i * attribute_size;
+        let ns = read_u32(data, offset)?;
+        let name_index = read_u32(data, offset + 4)?;
+        let raw_value = read_u32(data, offset + 8)?;
+        let mut value = ResValue::read(data, offset + 12, pool)?;
+        if let (ResValue::Other { .. }, Some(raw)) = (&value, pool.get(raw_value)) {
+            value = ResValue::String(raw.to_owned());
+        }
+        attributes.push(XmlAttribute {
+            namespace: (ns != NO_INDEX).then(|| pool.get(ns).unwrap_or_default().to_owned()),
+            name: pool.get(name_index).unwrap_or_default().to_owned(),
+            resource_id: resource_map.get(name_index as usize).copied(),
+            value,
+        });
+    }
+
+    Ok(XmlElement {
+        name,
+        attributes,
+        children: vec![],
+    })
+}
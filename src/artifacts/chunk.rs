@@ -0,0 +1,237 @@
+//! Primitives shared by the Android binary resource formats (binary XML and
+//! `resources.arsc`): little endian readers, chunk headers and string pools.
+use super::{ArtifactError, Result};
+
+pub const RES_STRING_POOL_TYPE: u16 = 0x0001;
+
+const UTF8_FLAG: u32 = 1 << 8;
+
+// Res_value data types
+pub const TYPE_REFERENCE: u8 = 0x01;
+pub const TYPE_STRING: u8 = 0x03;
+pub const TYPE_INT_DEC: u8 = 0x10;
+pub const TYPE_INT_HEX: u8 = 0x11;
+pub const TYPE_INT_BOOLEAN: u8 = 0x12;
+
+pub const NO_INDEX: u32 = 0xffff_ffff;
+
+pub fn read_u8(data: &[u8], offset: usize) -> Result<u8> {
+    data.get(offset)
+        .copied()
+        .ok_or_else(|| out_of_bounds(offset, 1))
+}
+
+pub fn read_u16(data: &[u8], offset: usize) -> Result<u16> {
+    let bytes = data
+        .get(offset..offset + 2)
+        .ok_or_else(|| out_of_bounds(offset, 2))?;
+    Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+pub fn read_u32(data: &[u8], offset: usize) -> Result<u32> {
+    let bytes = data
+        .get(offset..offset + 4)
+        .ok_or_else(|| out_of_bounds(offset, 4))?;
+    Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn out_of_bounds(offset: usize, len: usize) -> ArtifactError {
+    ArtifactError::Malformed(format!(
+        "read of {len} bytes at offset {offset} is out of bounds"
+    ))
+}
+
+/// `ResChunk_header`, the 8 byte prefix of every chunk.
+#[derive(Debug, Clone, Copy)]
+pub struct ChunkHeader {
+    pub offset: usize,
+    pub kind: u16,
+    pub header_size: usize,
+    pub size: usize,
+}
+
+impl ChunkHeader {
+    pub fn read(data: &[u8], offset: usize) -> Result<Self> {
+        let header = Self {
+            offset,
+            kind: read_u16(data, offset)?,
+            header_size: read_u16(data, offset + 2)? as usize,
+            size: read_u32(data, offset + 4)? as usize,
+        };
+        if header.size < 8 || header.header_size < 8 || offset + header.size > data.len() {
+            return Err(ArtifactError::Malformed(format!(
+                "chunk 0x{:04x} at offset {offset} has an invalid size",
+                header.kind
+            )));
+        }
+        Ok(header)
+    }
+
+    /// Offset of the chunk body, right after the header.
+    pub fn body(&self) -> usize {
+        self.offset + self.header_size
+    }
+
+    /// Offset of the first byte after this chunk.
+    pub fn end(&self) -> usize {
+        self.offset + self.size
+    }
+
+    /// The whole chunk, header included.
+    pub fn slice<'a>(&self, data: &'a [u8]) -> &'a [u8] {
+        &data[self.offset..self.end()]
+    }
+}
+
+/// Iterates the child chunks found between `start` and `end`.
+pub fn children(
+    data: &[u8],
+    start: usize,
+    end: usize,
+) -> impl Iterator<Item = Result<ChunkHeader>> + '_ {
+    let mut offset = start;
+    std::iter::from_fn(move || {
+        if offset + 8 > end {
+            return None;
+        }
+        let header = ChunkHeader::read(data, offset);
+        match &header {
+            Ok(h) => offset = h.end(),
+            Err(_) => offset = end,
+        }
+        Some(header)
+    })
+}
+
+/// A decoded `ResStringPool`.
+#[derive(Debug, Default, Clone)]
+pub struct StringPool {
+    strings: Vec<String>,
+}
+
+impl StringPool {
+    /// Decodes the string pool chunk that `header` points at.
+    pub fn parse(data: &[u8], header: &ChunkHeader) -> Result<Self> {
+        let chunk = header.slice(data);
+        let count = read_u32(chunk, 8)? as usize;
+        let flags = read_u32(chunk, 16)?;
+        let strings_start = read_u32(chunk, 20)? as usize;
+        let utf8 = flags & UTF8_FLAG != 0;
+
+        let mut strings = Vec::with_capacity(count);
+        for i in 0..count {
+            let offset = strings_start + read_u32(chunk, header.header_size + i * 4)? as usize;
+            let value = if utf8 {
+                decode_utf8(chunk, offset)?
+            } else {
+                decode_utf16(chunk, offset)?
+            };
+            strings.push(value);
+        }
+        Ok(Self { strings })
+    }
+
+    pub fn get(&self, index: u32) -> Option<&str> {
+        if index == NO_INDEX {
+            return None;
+        }
+        self.strings.get(index as usize).map(String::as_str)
+    }
+}
+
+fn decode_utf8(chunk: &[u8], offset: usize) -> Result<String> {
+    // the utf-16 length comes first, we only need the byte length after it
+    let (_, skip) = decode_len8(chunk, offset)?;
+    let (len, consumed) = decode_len8(chunk, offset + skip)?;
+    let start = offset + skip + consumed;
+    let bytes = chunk
+        .get(start..start + len)
+        .ok_or_else(|| out_of_bounds(start, len))?;
+    Ok(String::from_utf8_lossy(bytes).into_owned())
+}
+
+fn decode_utf16(chunk: &[u8], offset: usize) -> Result<String> {
+    let (len, consumed) = decode_len16(chunk, offset)?;
+    let start = offset + consumed;
+    let units = (0..len)
+        .map(|i| read_u16(chunk, start + i * 2))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(String::from_utf16_lossy(&units))
+}
+
+fn decode_len8(chunk: &[u8], offset: usize) -> Result<(usize, usize)> {
+    let first = read_u8(chunk, offset)? as usize;
+    if first & 0x80 != 0 {
+        let second = read_u8(chunk, offset + 1)? as usize;
+        Ok((((first & 0x7f) << 8) | second, 2))
+    } else {
+        Ok((first, 1))
+    }
+}
+
+fn decode_len16(chunk: &[u8], offset: usize) -> Result<(usize, usize)> {
+    let first = read_u16(chunk, offset)? as usize;
+    if first & 0x8000 != 0 {
+        let second = read_u16(chunk, offset + 2)? as usize;
+        Ok((((first & 0x7fff) << 16) | second, 4))
+    } else {
+        Ok((first, 2))
+    }
+}
+
+/// A typed `Res_value`, resolved against a string pool where possible.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResValue {
+    String(String),
+    Integer(i64),
+    Boolean(bool),
+    Reference(u32),
+    Other { data_type: u8, data: u32 },
+}
+
+impl ResValue {
+    /// Reads the 8 byte `Res_value` at `offset`.
+    pub fn read(data: &[u8], offset: usize, pool: &StringPool) -> Result<Self> {
+        let data_type = read_u8(data, offset + 3)?;
+        let value = read_u32(data, offset + 4)?;
+        Ok(Self::from_raw(data_type, value, pool))
+    }
+
+    pub fn from_raw(data_type: u8, data: u32, pool: &StringPool) -> Self {
+        match data_type {
+            TYPE_STRING => pool.get(data).map_or(Self::Other { data_type, data }, |s| {
+                Self::String(s.to_owned())
+            }),
+            #[allow(clippy::cast_possible_wrap)]
+            TYPE_INT_DEC | TYPE_INT_HEX => Self::Integer(i64::from(data as i32)),
+            TYPE_INT_BOOLEAN => Self::Boolean(data != 0),
+            TYPE_REFERENCE => Self::Reference(data),
+            _ => Self::Other { data_type, data },
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Integer view of the value, also accepting numeric strings since some
+    /// build tools write `versionCode` as a raw string.
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Self::Integer(i) => Some(*i),
+            Self::String(s) => s.trim().parse().ok(),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Boolean(b) => Some(*b),
+            Self::String(s) => s.parse().ok(),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,155 @@
+//! HarmonyOS package inspection. A `.hap` is a single module and describes
+//! itself in `module.json`, a `.app` pack bundles several haps and lists them
+//! in `pack.info`. Both are plain JSON.
+use std::io::{Cursor, Read, Seek};
+
+use serde::{de::DeserializeOwned, Deserialize};
+use zip::ZipArchive;
+
+use super::{
+    apk::{native_abis, read_entry},
+    ArtifactError, ArtifactMetadata, ArtifactType, Result,
+};
+
+pub const MODULE_PATH: &str = "module.json";
+pub const PACK_INFO_PATH: &str = "pack.info";
+
+#[derive(Debug, Deserialize)]
+struct ModuleJson {
+    app: ModuleApp,
+    module: Option<Module>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ModuleApp {
+    bundle_name: String,
+    version_code: i64,
+    version_name: Option<String>,
+    #[serde(rename = "minAPIVersion")]
+    min_api_version: Option<i64>,
+    #[serde(rename = "targetAPIVersion")]
+    target_api_version: Option<i64>,
+    label: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Module {
+    #[serde(default)]
+    request_permissions: Vec<Permission>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Permission {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct PackInfo {
+    summary: Summary,
+}
+
+#[derive(Debug, Deserialize)]
+struct Summary {
+    app: PackApp,
+    #[serde(default)]
+    modules: Vec<PackModule>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PackApp {
+    bundle_name: String,
+    version: PackVersion,
+}
+
+#[derive(Debug, Deserialize)]
+struct PackVersion {
+    code: i64,
+    name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PackModule {
+    api_version: Option<ApiVersion>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiVersion {
+    compatible: Option<i64>,
+    target: Option<i64>,
+}
+
+/// Whether the archive is an app pack: a `pack.info` next to the haps.
+pub fn is_app_pack<R: Read + Seek>(archive: &ZipArchive<R>) -> bool {
+    archive.index_for_name(PACK_INFO_PATH).is_some()
+        && archive
+            .file_names()
+            .any(|name| !name.contains('/') && name.ends_with(".hap"))
+}
+
+/// Reads the bundle and version information out of a `.hap`.
+pub fn inspect(bytes: &[u8]) -> Result<ArtifactMetadata> {
+    let mut archive = ZipArchive::new(Cursor::new(bytes))?;
+    let module: ModuleJson = read_json(&mut archive, MODULE_PATH)?;
+    let app = module.app;
+    Ok(ArtifactMetadata {
+        artifact_type: ArtifactType::Hap,
+        package_name: app.bundle_name,
+        version_code: app.version_code,
+        version_name: app.version_name,
+        min_sdk_version: app.min_api_version.and_then(api_level),
+        target_sdk_version: app.target_api_version.and_then(api_level),
+        permissions: module
+            .module
+            .map(|m| m.request_permissions.into_iter().map(|p| p.name).collect())
+            .unwrap_or_default(),
+        // `$string:` references need the compiled resources.index, only
+        // literal labels are taken
+        label: app.label.filter(|label| !label.starts_with('$')),
+        native_abis: native_abis(&archive, "libs/"),
+        ..Default::default()
+    })
+}
+
+/// Reads the bundle and version information out of a `.app` pack. Its
+/// minimum API version is the highest one any of its modules requires.
+pub fn inspect_pack(bytes: &[u8]) -> Result<ArtifactMetadata> {
+    let mut archive = ZipArchive::new(Cursor::new(bytes))?;
+    let summary = read_json::<_, PackInfo>(&mut archive, PACK_INFO_PATH)?.summary;
+    let api_versions = || {
+        summary
+            .modules
+            .iter()
+            .filter_map(|m| m.api_version.as_ref())
+    };
+    Ok(ArtifactMetadata {
+        artifact_type: ArtifactType::HarmonyApp,
+        package_name: summary.app.bundle_name.clone(),
+        version_code: summary.app.version.code,
+        version_name: summary.app.version.name.clone(),
+        min_sdk_version: api_versions()
+            .filter_map(|v| v.compatible.and_then(api_level))
+            .max(),
+        target_sdk_version: api_versions()
+            .filter_map(|v| v.target.and_then(api_level))
+            .max(),
+        ..Default::default()
+    })
+}
+
+/// API level of a version as written by the build tools, which encode the
+/// release next to the API level in newer SDKs (`50000012` is API 12).
+fn api_level(version: i64) -> Option<i32> {
+    i32::try_from(version % 1000).ok()
+}
+
+fn read_json<R: Read + Seek, T: DeserializeOwned>(
+    archive: &mut ZipArchive<R>,
+    name: &str,
+) -> Result<T> {
+    serde_json::from_slice(&read_entry(archive, name)?)
+        .map_err(|err| ArtifactError::Malformed(format!("invalid {name}: {err}")))
+}
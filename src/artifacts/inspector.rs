@@ -0,0 +1,166 @@
+//! Per platform artifact handling. Every platform registers an
+//! [`ArtifactInspector`] naming the artifact types it accepts, how they are
+//! inspected and how a file is checked against the app it is released for.
+//! Uploads nobody accepts are stored as plain files.
+use loco_rs::Error;
+
+use super::{aab, apk, hap, ipa, ArtifactMetadata, ArtifactType, Result};
+use crate::models::{
+    _entities::{apps, files},
+    platforms::PlatformCode,
+};
+
+pub trait ArtifactInspector: Send + Sync {
+    /// The `platforms.code` the inspector is registered under.
+    fn platform(&self) -> PlatformCode;
+
+    /// Artifact types the platform accepts.
+    fn artifact_types(&self) -> &'static [ArtifactType];
+
+    /// Whether devices install artifacts of this type, as opposed to
+    /// formats that are only archived.
+    fn is_installable(&self, artifact_type: ArtifactType) -> bool {
+        self.artifact_types().contains(&artifact_type)
+    }
+
+    /// The artifact type an upload claims to be, judged by its name and
+    /// mime. A claimed artifact that fails to inspect is rejected instead of
+    /// being stored as a plain file.
+    fn claimed_type(&self, name: &str, _mime: &str) -> Option<ArtifactType> {
+        by_extension(self.artifact_types(), name)
+    }
+
+    /// Extracts the package identity and version of an artifact.
+    fn inspect(&self, artifact_type: ArtifactType, bytes: &[u8]) -> Result<ArtifactMetadata>;
+
+    /// Rejects an inspected file that does not belong to `app`.
+    fn validate(&self, app: &apps::Model, file: &files::Model) -> loco_rs::Result<()> {
+        match &file.package_name {
+            Some(package_name) if package_name != &app.bundle_id => {
+                Err(Error::BadRequest(format!(
+                    "artifact package {} does not match bundle_id {} of app {}",
+                    package_name, app.bundle_id, app.id
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+pub struct Android;
+
+impl ArtifactInspector for Android {
+    fn platform(&self) -> PlatformCode {
+        PlatformCode::Android
+    }
+
+    fn artifact_types(&self) -> &'static [ArtifactType] {
+        &[ArtifactType::Apk, ArtifactType::Aab]
+    }
+
+    fn is_installable(&self, artifact_type: ArtifactType) -> bool {
+        artifact_type == ArtifactType::Apk
+    }
+
+    fn claimed_type(&self, name: &str, mime: &str) -> Option<ArtifactType> {
+        if mime == apk::MIME {
+            return Some(ArtifactType::Apk);
+        }
+        by_extension(self.artifact_types(), name)
+    }
+
+    fn inspect(&self, artifact_type: ArtifactType, bytes: &[u8]) -> Result<ArtifactMetadata> {
+        match artifact_type {
+            ArtifactType::Aab => aab::inspect(bytes),
+            _ => apk::inspect(bytes),
+        }
+    }
+}
+
+pub struct Ios;
+
+impl ArtifactInspector for Ios {
+    fn platform(&self) -> PlatformCode {
+        PlatformCode::Ios
+    }
+
+    fn artifact_types(&self) -> &'static [ArtifactType] {
+        &[ArtifactType::Ipa]
+    }
+
+    fn inspect(&self, _artifact_type: ArtifactType, bytes: &[u8]) -> Result<ArtifactMetadata> {
+        ipa::inspect(bytes)
+    }
+}
+
+pub struct HarmonyOs;
+
+impl ArtifactInspector for HarmonyOs {
+    fn platform(&self) -> PlatformCode {
+        PlatformCode::HarmonyOs
+    }
+
+    fn artifact_types(&self) -> &'static [ArtifactType] {
+        &[ArtifactType::Hap, ArtifactType::HarmonyApp]
+    }
+
+    fn inspect(&self, artifact_type: ArtifactType, bytes: &[u8]) -> Result<ArtifactMetadata> {
+        match artifact_type {
+            ArtifactType::HarmonyApp => hap::inspect_pack(bytes),
+            _ => hap::inspect(bytes),
+        }
+    }
+}
+
+/// The type among `artifact_types` named by the extension of `name`.
+fn by_extension(artifact_types: &[ArtifactType], name: &str) -> Option<ArtifactType> {
+    let extension = std::path::Path::new(name).extension()?;
+    artifact_types
+        .iter()
+        .copied()
+        .find(|artifact_type| extension.eq_ignore_ascii_case(artifact_type.as_str()))
+}
+
+/// Registered inspectors, a new platform only needs an entry here.
+static INSPECTORS: &[&dyn ArtifactInspector] = &[&Android, &Ios, &HarmonyOs];
+
+/// The inspector registered under a `platforms.code`.
+pub fn for_platform(code: i16) -> Option<&'static dyn ArtifactInspector> {
+    INSPECTORS
+        .iter()
+        .copied()
+        .find(|inspector| inspector.platform() as i16 == code)
+}
+
+/// The inspector of the platform accepting an artifact type.
+pub fn for_type(artifact_type: ArtifactType) -> Option<&'static dyn ArtifactInspector> {
+    INSPECTORS
+        .iter()
+        .copied()
+        .find(|inspector| inspector.artifact_types().contains(&artifact_type))
+}
+
+/// The platform whose devices install an artifact type, `None` for formats
+/// that are only archived.
+pub fn installed_on(artifact_type: ArtifactType) -> Option<PlatformCode> {
+    for_type(artifact_type)
+        .filter(|inspector| inspector.is_installable(artifact_type))
+        .map(|inspector| inspector.platform())
+}
+
+/// The artifact type an upload claims to be by its name or mime, whatever
+/// its content.
+pub fn claimed_type(name: &str, mime: &str) -> Option<ArtifactType> {
+    INSPECTORS
+        .iter()
+        .find_map(|inspector| inspector.claimed_type(name, mime))
+}
+
+/// Recognises an artifact by its content and inspects it with the inspector
+/// of its platform. `None` for anything no platform accepts.
+pub fn inspect(bytes: &[u8]) -> Result<Option<ArtifactMetadata>> {
+    ArtifactType::detect(bytes)
+        .and_then(|artifact_type| Some((artifact_type, for_type(artifact_type)?)))
+        .map(|(artifact_type, inspector)| inspector.inspect(artifact_type, bytes))
+        .transpose()
+}
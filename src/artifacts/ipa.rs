@@ -0,0 +1,61 @@
+//! iOS IPA inspection. The bundle identity lives in the `Info.plist` of the
+//! single app bundle under `Payload/`, in binary or XML form.
+use std::io::{Cursor, Read, Seek};
+
+use plist::{Dictionary, Value};
+use zip::ZipArchive;
+
+use super::{apk::read_entry, ArtifactError, ArtifactMetadata, ArtifactType, Result};
+
+/// Path of the app's `Info.plist`, `Payload/<name>.app/Info.plist`. Plists
+/// of nested bundles (extensions, frameworks) live deeper and are skipped.
+pub fn info_plist_path<R: Read + Seek>(archive: &ZipArchive<R>) -> Option<String> {
+    archive
+        .file_names()
+        .find(|name| {
+            let mut parts = name.split('/');
+            matches!(
+                (parts.next(), parts.next(), parts.next(), parts.next()),
+                (Some("Payload"), Some(app), Some("Info.plist"), None) if app.ends_with(".app")
+            )
+        })
+        .map(ToOwned::to_owned)
+}
+
+/// Reads the bundle identifier and versions out of an IPA.
+///
+/// `CFBundleVersion` becomes the version code, so it has to be a plain
+/// integer build number for versions to be ordered.
+pub fn inspect(bytes: &[u8]) -> Result<ArtifactMetadata> {
+    let mut archive = ZipArchive::new(Cursor::new(bytes))?;
+    let path = info_plist_path(&archive).ok_or(ArtifactError::Missing("Info.plist"))?;
+    let info = Value::from_reader(Cursor::new(read_entry(&mut archive, &path)?))
+        .map_err(|err| ArtifactError::Malformed(format!("invalid Info.plist: {err}")))?;
+    let info = info
+        .as_dictionary()
+        .ok_or_else(|| ArtifactError::Malformed("Info.plist is not a dictionary".into()))?;
+
+    let build = string(info, "CFBundleVersion").ok_or(ArtifactError::Missing("CFBundleVersion"))?;
+    Ok(ArtifactMetadata {
+        artifact_type: ArtifactType::Ipa,
+        package_name: string(info, "CFBundleIdentifier")
+            .ok_or(ArtifactError::Missing("CFBundleIdentifier"))?,
+        version_code: build.parse().map_err(|_| {
+            ArtifactError::Malformed(format!(
+                "CFBundleVersion {build} must be an integer build number, dotted build \
+                 numbers are not supported"
+            ))
+        })?,
+        version_name: string(info, "CFBundleShortVersionString"),
+        label: string(info, "CFBundleDisplayName").or_else(|| string(info, "CFBundleName")),
+        ..Default::default()
+    })
+}
+
+fn string(info: &Dictionary, key: &str) -> Option<String> {
+    info.get(key)
+        .and_then(Value::as_string)
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(ToOwned::to_owned)
+}
@@ -0,0 +1,134 @@
+//! Inspection of uploaded build artifacts.
+//!
+//! The parsers in here only ever look at the bytes of an upload, they never
+//! touch the database. The extracted [`ArtifactMetadata`] is persisted on the
+//! `files` row by the upload path and checked again when a version is created.
+pub mod aab;
+pub mod apk;
+pub mod arsc;
+pub mod axml;
+pub mod chunk;
+pub mod hap;
+pub mod inspector;
+pub mod ipa;
+pub mod proto;
+pub mod signing;
+
+use std::io::Cursor;
+
+use serde::Serialize;
+use zip::ZipArchive;
+
+use self::signing::SigningInfo;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ArtifactError {
+    #[error("malformed artifact: {0}")]
+    Malformed(String),
+    #[error("signature verification failed: {0}")]
+    Signature(String),
+    #[error("artifact is missing {0}")]
+    Missing(&'static str),
+    #[error(transparent)]
+    Zip(#[from] zip::result::ZipError),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+pub type Result<T> = std::result::Result<T, ArtifactError>;
+
+impl From<ArtifactError> for loco_rs::Error {
+    fn from(err: ArtifactError) -> Self {
+        Self::BadRequest(err.to_string())
+    }
+}
+
+/// Artifact formats recognised by their content.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ArtifactType {
+    #[default]
+    Apk,
+    Aab,
+    Ipa,
+    Hap,
+    /// HarmonyOS app pack
+    #[serde(rename = "app")]
+    HarmonyApp,
+}
+
+impl ArtifactType {
+    pub const ALL: [Self; 5] = [Self::Apk, Self::Aab, Self::Ipa, Self::Hap, Self::HarmonyApp];
+
+    /// Sniffs the format from the archive layout, whatever the upload claims
+    /// to be.
+    pub fn detect(bytes: &[u8]) -> Option<Self> {
+        let archive = ZipArchive::new(Cursor::new(bytes)).ok()?;
+        if archive.index_for_name(apk::MANIFEST_PATH).is_some() {
+            Some(Self::Apk)
+        } else if archive.index_for_name(aab::BUNDLE_CONFIG_PATH).is_some()
+            && archive.index_for_name(aab::MANIFEST_PATH).is_some()
+        {
+            Some(Self::Aab)
+        } else if ipa::info_plist_path(&archive).is_some() {
+            Some(Self::Ipa)
+        } else if archive.index_for_name(hap::MODULE_PATH).is_some() {
+            Some(Self::Hap)
+        } else if hap::is_app_pack(&archive) {
+            Some(Self::HarmonyApp)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Apk => "apk",
+            Self::Aab => "aab",
+            Self::Ipa => "ipa",
+            Self::Hap => "hap",
+            Self::HarmonyApp => "app",
+        }
+    }
+
+    /// The type stored as `files.artifact_type`.
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|t| t.as_str() == s)
+    }
+}
+
+/// Package identity and version information read from an artifact.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ArtifactMetadata {
+    pub artifact_type: ArtifactType,
+    pub package_name: String,
+    /// `versionCode` on Android and HarmonyOS, `CFBundleVersion` on iOS
+    pub version_code: i64,
+    pub version_name: Option<String>,
+    pub min_sdk_version: Option<i32>,
+    pub target_sdk_version: Option<i32>,
+    /// Highest API level the artifact installs on, rarely set
+    pub max_sdk_version: Option<i32>,
+    pub permissions: Vec<String>,
+    /// ABIs the artifact ships native libraries for, empty for pure Java or
+    /// ArkTS code that runs anywhere
+    pub native_abis: Vec<String>,
+    /// Hardware and software features a device must have, e.g.
+    /// `android.hardware.type.watch`
+    pub required_features: Vec<String>,
+    /// Verified signers, for formats that carry a signature
+    pub signing: Option<SigningInfo>,
+    /// Application label in the default locale
+    pub label: Option<String>,
+    #[serde(skip)]
+    pub icon: Option<ArtifactIcon>,
+}
+
+/// Launcher icon extracted from an artifact.
+#[derive(Debug, Clone)]
+pub struct ArtifactIcon {
+    /// File name of the icon inside the artifact
+    pub name: String,
+    pub mime: &'static str,
+    pub bytes: Vec<u8>,
+}
@@ -0,0 +1,241 @@
+//! Protocol buffer wire format reader and the `aapt2` messages Android App
+//! Bundles store their manifests in (`Resources.proto`). Only the fields we
+//! read are decoded, everything else is skipped.
+use super::{
+    axml::{XmlAttribute, XmlElement},
+    chunk::ResValue,
+    ArtifactError, Result,
+};
+
+/// A decoded field value. Length delimited fields are left undecoded since
+/// the wire format does not tell strings, bytes and messages apart.
+#[derive(Debug, Clone, Copy)]
+pub enum Value<'a> {
+    Varint(u64),
+    Fixed64(u64),
+    Bytes(&'a [u8]),
+    Fixed32(u32),
+}
+
+impl<'a> Value<'a> {
+    pub fn as_u64(self) -> Option<u64> {
+        match self {
+            Self::Varint(v) | Self::Fixed64(v) => Some(v),
+            Self::Fixed32(v) => Some(u64::from(v)),
+            Self::Bytes(_) => None,
+        }
+    }
+
+    pub fn as_bytes(self) -> Option<&'a [u8]> {
+        match self {
+            Self::Bytes(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> Option<&'a str> {
+        self.as_bytes().and_then(|b| std::str::from_utf8(b).ok())
+    }
+}
+
+/// Iterates the fields of an encoded message as `(number, value)` pairs.
+pub struct Fields<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Fields<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data, offset: 0 }
+    }
+
+    fn varint(&mut self) -> Result<u64> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = *self
+                .data
+                .get(self.offset)
+                .ok_or_else(|| truncated(self.offset))?;
+            self.offset += 1;
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(ArtifactError::Malformed(format!(
+            "protobuf varint at offset {} is too long",
+            self.offset
+        )))
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let bytes = self
+            .data
+            .get(self.offset..self.offset + len)
+            .ok_or_else(|| truncated(self.offset))?;
+        self.offset += len;
+        Ok(bytes)
+    }
+
+    fn field(&mut self) -> Result<(u32, Value<'a>)> {
+        let key = self.varint()?;
+        let number = u32::try_from(key >> 3)
+            .map_err(|_| ArtifactError::Malformed(format!("invalid protobuf field {key}")))?;
+        let value = match key & 0x7 {
+            0 => Value::Varint(self.varint()?),
+            1 => {
+                let bytes = self.take(8)?;
+                let mut buf = [0; 8];
+                buf.copy_from_slice(bytes);
+                Value::Fixed64(u64::from_le_bytes(buf))
+            }
+            2 => {
+                let len = usize::try_from(self.varint()?).unwrap_or(usize::MAX);
+                Value::Bytes(self.take(len)?)
+            }
+            5 => {
+                let bytes = self.take(4)?;
+                Value::Fixed32(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            }
+            wire_type => {
+                return Err(ArtifactError::Malformed(format!(
+                    "unsupported protobuf wire type {wire_type}"
+                )))
+            }
+        };
+        Ok((number, value))
+    }
+}
+
+impl<'a> Iterator for Fields<'a> {
+    type Item = Result<(u32, Value<'a>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.offset >= self.data.len() {
+            return None;
+        }
+        let field = self.field();
+        if field.is_err() {
+            // stop after the first error
+            self.offset = self.data.len();
+        }
+        Some(field)
+    }
+}
+
+fn truncated(offset: usize) -> ArtifactError {
+    ArtifactError::Malformed(format!("protobuf message truncated at offset {offset}"))
+}
+
+/// Decodes an `aapt.pb.XmlNode` and returns its root element.
+pub fn parse_xml(data: &[u8]) -> Result<XmlElement> {
+    for field in Fields::new(data) {
+        if let (1, Value::Bytes(element)) = field? {
+            return read_element(element);
+        }
+    }
+    Err(ArtifactError::Malformed(
+        "xml node has no root element".into(),
+    ))
+}
+
+/// `aapt.pb.XmlElement`
+fn read_element(data: &[u8]) -> Result<XmlElement> {
+    let mut element = XmlElement::default();
+    for field in Fields::new(data) {
+        match field? {
+            (3, value) => element.name = value.as_str().unwrap_or_default().to_owned(),
+            (4, Value::Bytes(attribute)) => element.attributes.push(read_attribute(attribute)?),
+            (5, Value::Bytes(node)) => {
+                // text nodes carry field 2 instead of an element
+                for field in Fields::new(node) {
+                    if let (1, Value::Bytes(child)) = field? {
+                        element.children.push(read_element(child)?);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(element)
+}
+
+/// `aapt.pb.XmlAttribute`, preferring the compiled value over the raw string.
+fn read_attribute(data: &[u8]) -> Result<XmlAttribute> {
+    let mut namespace = None;
+    let mut name = String::new();
+    let mut raw = String::new();
+    let mut resource_id = None;
+    let mut compiled = None;
+    for field in Fields::new(data) {
+        match field? {
+            (1, value) => {
+                namespace = value
+                    .as_str()
+                    .filter(|ns| !ns.is_empty())
+                    .map(ToOwned::to_owned);
+            }
+            (2, value) => name = value.as_str().unwrap_or_default().to_owned(),
+            (3, value) => raw = value.as_str().unwrap_or_default().to_owned(),
+            (5, value) => {
+                resource_id = value
+                    .as_u64()
+                    .and_then(|id| u32::try_from(id).ok())
+                    .filter(|id| *id != 0);
+            }
+            (6, Value::Bytes(item)) => compiled = read_item(item)?,
+            _ => {}
+        }
+    }
+    Ok(XmlAttribute {
+        namespace,
+        name,
+        resource_id,
+        value: compiled.unwrap_or(ResValue::String(raw)),
+    })
+}
+
+/// `aapt.pb.Item`, mapped onto the binary XML value types.
+fn read_item(data: &[u8]) -> Result<Option<ResValue>> {
+    for field in Fields::new(data) {
+        match field? {
+            // Reference
+            (1, Value::Bytes(reference)) => {
+                for field in Fields::new(reference) {
+                    if let (2, value) = field? {
+                        let id = value.as_u64().and_then(|id| u32::try_from(id).ok());
+                        return Ok(id.map(ResValue::Reference));
+                    }
+                }
+            }
+            // String and RawString both keep the text in field 1
+            (2 | 3, Value::Bytes(string)) => {
+                for field in Fields::new(string) {
+                    if let (1, value) = field? {
+                        return Ok(value.as_str().map(|s| ResValue::String(s.to_owned())));
+                    }
+                }
+                return Ok(Some(ResValue::String(String::new())));
+            }
+            // Primitive
+            (7, Value::Bytes(primitive)) => return read_primitive(primitive),
+            _ => {}
+        }
+    }
+    Ok(None)
+}
+
+/// `aapt.pb.Primitive`, the integer and boolean cases of it.
+#[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+fn read_primitive(data: &[u8]) -> Result<Option<ResValue>> {
+    for field in Fields::new(data) {
+        match field? {
+            // int32 values are sign extended to 64 bits on the wire
+            (6, Value::Varint(v)) => return Ok(Some(ResValue::Integer(i64::from(v as i32)))),
+            (7, Value::Varint(v)) => return Ok(Some(ResValue::Integer(i64::from(v as u32)))),
+            (8, Value::Varint(v)) => return Ok(Some(ResValue::Boolean(v != 0))),
+            _ => {}
+        }
+    }
+    Ok(None)
+}
@@ -0,0 +1,368 @@
+//! APK Signing Block and the v2/v3 schemes stored inside it.
+//!
+//! See <https://source.android.com/docs/security/features/apksigning/v2>.
+use der::{Decode, Encode};
+use sha2::{Digest, Sha256, Sha512};
+use x509_cert::Certificate;
+
+use super::{verify_signature, HashAlgorithm, KeyAlgorithm, Reader};
+use crate::artifacts::{ArtifactError, Result};
+
+pub const V2_BLOCK_ID: u32 = 0x7109_871a;
+pub const V3_BLOCK_ID: u32 = 0xf053_68c0;
+const PROOF_OF_ROTATION_ATTR_ID: u32 = 0x3ba0_6f8c;
+const LINEAGE_VERSION: u32 = 1;
+
+const MAGIC: &[u8; 16] = b"APK Sig Block 42";
+const EOCD_SIGNATURE: u32 = 0x0605_4b50;
+const EOCD_MIN_SIZE: usize = 22;
+const CHUNK_SIZE: usize = 1024 * 1024;
+
+/// Offsets of the zip central directory and end of central directory record.
+pub struct ZipSections {
+    cd_offset: usize,
+    eocd_offset: usize,
+}
+
+impl ZipSections {
+    pub fn locate(data: &[u8]) -> Result<Self> {
+        let last = data
+            .len()
+            .checked_sub(EOCD_MIN_SIZE)
+            .ok_or_else(|| ArtifactError::Malformed("file is too small for a zip".into()))?;
+        // the record is followed by a comment of at most u16::MAX bytes
+        let eocd_offset = (last.saturating_sub(usize::from(u16::MAX))..=last)
+            .rev()
+            .find(|&offset| {
+                let signature = u32::from_le_bytes([
+                    data[offset],
+                    data[offset + 1],
+                    data[offset + 2],
+                    data[offset + 3],
+                ]);
+                let comment_len =
+                    usize::from(u16::from_le_bytes([data[offset + 20], data[offset + 21]]));
+                signature == EOCD_SIGNATURE && offset + EOCD_MIN_SIZE + comment_len == data.len()
+            })
+            .ok_or_else(|| {
+                ArtifactError::Malformed("zip end of central directory not found".into())
+            })?;
+
+        let cd_offset = u32::from_le_bytes([
+            data[eocd_offset + 16],
+            data[eocd_offset + 17],
+            data[eocd_offset + 18],
+            data[eocd_offset + 19],
+        ]) as usize;
+        if cd_offset > eocd_offset {
+            return Err(ArtifactError::Malformed(
+                "zip central directory offset is out of bounds".into(),
+            ));
+        }
+
+        Ok(Self {
+            cd_offset,
+            eocd_offset,
+        })
+    }
+}
+
+/// The ID-value pairs of an APK Signing Block.
+pub struct SigningBlock<'a> {
+    offset: usize,
+    pairs: Vec<(u32, &'a [u8])>,
+}
+
+impl<'a> SigningBlock<'a> {
+    /// Finds the signing block right in front of the central directory.
+    pub fn find(data: &'a [u8], zip: &ZipSections) -> Result<Option<Self>> {
+        if zip.cd_offset < 32 || &data[zip.cd_offset - 16..zip.cd_offset] != MAGIC {
+            return Ok(None);
+        }
+        let size = Reader::new(&data[zip.cd_offset - 24..]).u64()?;
+        // the size covers at least the trailing size field and the magic
+        let offset = usize::try_from(size)
+            .ok()
+            .filter(|&size| size >= 24)
+            .and_then(|size| size.checked_add(8))
+            .and_then(|len| zip.cd_offset.checked_sub(len))
+            .ok_or_else(|| ArtifactError::Malformed("apk signing block size is invalid".into()))?;
+        if Reader::new(&data[offset..]).u64()? != size {
+            return Err(ArtifactError::Malformed(
+                "apk signing block sizes do not match".into(),
+            ));
+        }
+
+        let mut reader = Reader::new(&data[offset + 8..zip.cd_offset - 24]);
+        let mut pairs = vec![];
+        while !reader.is_empty() {
+            let len = usize::try_from(reader.u64()?)
+                .map_err(|_| ArtifactError::Malformed("apk signing block pair too large".into()))?;
+            let mut pair = Reader::new(reader.take(len)?);
+            let id = pair.u32()?;
+            pairs.push((id, pair.rest()));
+        }
+        Ok(Some(Self { offset, pairs }))
+    }
+
+    pub fn get(&self, id: u32) -> Option<&'a [u8]> {
+        self.pairs.iter().find(|(i, _)| *i == id).map(|(_, v)| *v)
+    }
+}
+
+/// A verified v2/v3 signer.
+pub struct Signer {
+    pub certificate: Vec<u8>,
+    /// Certificates of the proof-of-rotation lineage, oldest first
+    pub lineage: Vec<Vec<u8>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct SignatureAlgorithm {
+    id: u32,
+    key: KeyAlgorithm,
+    hash: HashAlgorithm,
+    /// Verity digests are computed over a merkle tree instead of chunks, we
+    /// only accept them when nothing else is offered.
+    verity: bool,
+}
+
+impl SignatureAlgorithm {
+    fn from_id(id: u32) -> Option<Self> {
+        let (key, hash, verity) = match id {
+            0x0101 => (KeyAlgorithm::RsaPss, HashAlgorithm::Sha256, false),
+            0x0102 => (KeyAlgorithm::RsaPss, HashAlgorithm::Sha512, false),
+            0x0103 => (KeyAlgorithm::RsaPkcs1, HashAlgorithm::Sha256, false),
+            0x0104 => (KeyAlgorithm::RsaPkcs1, HashAlgorithm::Sha512, false),
+            0x0201 => (KeyAlgorithm::Ecdsa, HashAlgorithm::Sha256, false),
+            0x0202 => (KeyAlgorithm::Ecdsa, HashAlgorithm::Sha512, false),
+            0x0301 => (KeyAlgorithm::Dsa, HashAlgorithm::Sha256, false),
+            0x0421 => (KeyAlgorithm::RsaPkcs1, HashAlgorithm::Sha256, true),
+            0x0423 => (KeyAlgorithm::Ecdsa, HashAlgorithm::Sha256, true),
+            0x0425 => (KeyAlgorithm::Dsa, HashAlgorithm::Sha256, true),
+            _ => return None,
+        };
+        Some(Self {
+            id,
+            key,
+            hash,
+            verity,
+        })
+    }
+
+    fn rank(self) -> u8 {
+        match (self.verity, self.hash) {
+            (true, _) => 0,
+            (false, HashAlgorithm::Sha512) => 2,
+            (false, _) => 1,
+        }
+    }
+}
+
+/// Verifies every signer of a v2 or v3 scheme block.
+pub fn verify_scheme(
+    data: &[u8],
+    zip: &ZipSections,
+    block: &SigningBlock<'_>,
+    value: &[u8],
+    v3: bool,
+) -> Result<Vec<Signer>> {
+    let scheme = if v3 { "v3" } else { "v2" };
+    let mut signers = Reader::new(Reader::new(value).prefixed()?);
+    let mut verified = vec![];
+
+    while !signers.is_empty() {
+        let mut signer = Reader::new(signers.prefixed()?);
+        let signed_data = signer.prefixed()?;
+        if v3 {
+            // min and max sdk, repeated inside the signed data
+            signer.u32()?;
+            signer.u32()?;
+        }
+        let mut signatures = Reader::new(signer.prefixed()?);
+        let public_key = signer.prefixed()?;
+
+        let mut best: Option<(SignatureAlgorithm, &[u8])> = None;
+        while !signatures.is_empty() {
+            let mut record = Reader::new(signatures.prefixed()?);
+            let id = record.u32()?;
+            let signature = record.prefixed()?;
+            if let Some(algorithm) = SignatureAlgorithm::from_id(id) {
+                if best.is_none_or(|(b, _)| algorithm.rank() > b.rank()) {
+                    best = Some((algorithm, signature));
+                }
+            }
+        }
+        let (algorithm, signature) = best.ok_or_else(|| {
+            ArtifactError::Signature(format!("{scheme} signer has no supported signature"))
+        })?;
+        if algorithm.verity {
+            return Err(ArtifactError::Signature(format!(
+                "{scheme} signer only offers verity digests"
+            )));
+        }
+        verify_signature(
+            algorithm.key,
+            algorithm.hash,
+            public_key,
+            signed_data,
+            signature,
+        )?;
+
+        let mut signed = Reader::new(signed_data);
+        let mut digests = Reader::new(signed.prefixed()?);
+        let mut certificates = Reader::new(signed.prefixed()?);
+        if v3 {
+            signed.u32()?;
+            signed.u32()?;
+        }
+        let mut attributes = Reader::new(signed.prefixed()?);
+
+        let mut expected = None;
+        while !digests.is_empty() {
+            let mut record = Reader::new(digests.prefixed()?);
+            if record.u32()? == algorithm.id {
+                expected = Some(record.prefixed()?);
+            }
+        }
+        let expected = expected.ok_or_else(|| {
+            ArtifactError::Signature(format!("{scheme} signed data lacks the signed digest"))
+        })?;
+        if content_digest(data, zip, block, algorithm.hash) != expected {
+            return Err(ArtifactError::Signature(format!(
+                "{scheme} content digest does not match, the apk was modified after signing"
+            )));
+        }
+
+        let certificate = certificates.prefixed()?;
+        if subject_public_key(certificate)? != public_key {
+            return Err(ArtifactError::Signature(format!(
+                "{scheme} signer public key does not match its certificate"
+            )));
+        }
+
+        let mut lineage = vec![];
+        while v3 && !attributes.is_empty() {
+            let mut attribute = Reader::new(attributes.prefixed()?);
+            if attribute.u32()? == PROOF_OF_ROTATION_ATTR_ID {
+                lineage = verify_lineage(attribute.rest())?;
+                if lineage.last().map(Vec::as_slice) != Some(certificate) {
+                    return Err(ArtifactError::Signature(
+                        "v3 lineage does not end with the signing certificate".into(),
+                    ));
+                }
+            }
+        }
+
+        verified.push(Signer {
+            certificate: certificate.to_vec(),
+            lineage,
+        });
+    }
+
+    if verified.is_empty() {
+        return Err(ArtifactError::Signature(format!(
+            "{scheme} block has no signers"
+        )));
+    }
+    Ok(verified)
+}
+
+/// Checks that every certificate of a proof-of-rotation lineage was signed by
+/// its predecessor and returns the certificates, oldest first.
+fn verify_lineage(value: &[u8]) -> Result<Vec<Vec<u8>>> {
+    let mut reader = Reader::new(value);
+    if reader.u32()? != LINEAGE_VERSION {
+        return Err(ArtifactError::Signature(
+            "unsupported v3 lineage version".into(),
+        ));
+    }
+
+    let mut certificates: Vec<Vec<u8>> = vec![];
+    let mut last_algorithm: Option<u32> = None;
+    while !reader.is_empty() {
+        let mut node = Reader::new(reader.prefixed()?);
+        let signed_data = node.prefixed()?;
+        let _flags = node.u32()?;
+        let algorithm_id = node.u32()?;
+        let signature = node.prefixed()?;
+
+        let mut signed = Reader::new(signed_data);
+        let certificate = signed.prefixed()?;
+        let signed_algorithm = signed.u32()?;
+
+        // each node is signed by the key of the node before it
+        if let (Some(previous), Some(last_id)) = (certificates.last(), last_algorithm) {
+            if signed_algorithm != last_id {
+                return Err(ArtifactError::Signature(
+                    "v3 lineage signature algorithm mismatch".into(),
+                ));
+            }
+            let algorithm = SignatureAlgorithm::from_id(last_id).ok_or_else(|| {
+                ArtifactError::Signature("v3 lineage uses an unknown signature algorithm".into())
+            })?;
+            verify_signature(
+                algorithm.key,
+                algorithm.hash,
+                &subject_public_key(previous)?,
+                signed_data,
+                signature,
+            )?;
+        }
+
+        certificates.push(certificate.to_vec());
+        last_algorithm = Some(algorithm_id);
+    }
+    Ok(certificates)
+}
+
+fn subject_public_key(certificate: &[u8]) -> Result<Vec<u8>> {
+    let certificate = Certificate::from_der(certificate)
+        .map_err(|e| ArtifactError::Signature(format!("invalid signer certificate: {e}")))?;
+    certificate
+        .tbs_certificate
+        .subject_public_key_info
+        .to_der()
+        .map_err(|e| ArtifactError::Signature(format!("invalid signer public key: {e}")))
+}
+
+/// The chunked digest over everything but the signing block, with the end of
+/// central directory pointing at where the signing block starts.
+fn content_digest(
+    data: &[u8],
+    zip: &ZipSections,
+    block: &SigningBlock<'_>,
+    hash: HashAlgorithm,
+) -> Vec<u8> {
+    let mut eocd = data[zip.eocd_offset..].to_vec();
+    #[allow(clippy::cast_possible_truncation)]
+    eocd[16..20].copy_from_slice(&(block.offset as u32).to_le_bytes());
+
+    let sections = [
+        &data[..block.offset],
+        &data[zip.cd_offset..zip.eocd_offset],
+        eocd.as_slice(),
+    ];
+    match hash {
+        HashAlgorithm::Sha512 => chunked_digest::<Sha512>(&sections),
+        _ => chunked_digest::<Sha256>(&sections),
+    }
+}
+
+#[allow(clippy::cast_possible_truncation)]
+fn chunked_digest<D: Digest>(sections: &[&[u8]]) -> Vec<u8> {
+    let chunk_count: usize = sections.iter().map(|s| s.len().div_ceil(CHUNK_SIZE)).sum();
+    let mut top = D::new();
+    top.update([0x5a]);
+    top.update((chunk_count as u32).to_le_bytes());
+    for section in sections {
+        for chunk in section.chunks(CHUNK_SIZE) {
+            let mut hasher = D::new();
+            hasher.update([0xa5]);
+            hasher.update((chunk.len() as u32).to_le_bytes());
+            hasher.update(chunk);
+            top.update(hasher.finalize());
+        }
+    }
+    top.finalize().to_vec()
+}
@@ -0,0 +1,271 @@
+//! v1 (JAR) signature scheme: `META-INF/MANIFEST.MF`, the `.SF` signature
+//! files and their PKCS#7 signature blocks.
+use std::{
+    collections::HashMap,
+    io::{Read, Seek},
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use cms::{
+    cert::CertificateChoices,
+    content_info::ContentInfo,
+    signed_data::{SignedData, SignerIdentifier},
+};
+use der::{Decode, Encode};
+use zip::ZipArchive;
+
+use super::{verify_signature, HashAlgorithm, KeyAlgorithm};
+use crate::artifacts::{apk::read_entry, ArtifactError, Result};
+
+const MANIFEST: &str = "META-INF/MANIFEST.MF";
+const MESSAGE_DIGEST_OID: &str = "1.2.840.113549.1.9.4";
+
+/// Verifies the v1 signature and returns the DER encoded signer certificates,
+/// or `None` when the archive carries no v1 signature.
+pub fn verify<R: Read + Seek>(archive: &mut ZipArchive<R>) -> Result<Option<Vec<Vec<u8>>>> {
+    let names: Vec<String> = archive.file_names().map(ToOwned::to_owned).collect();
+    let blocks: Vec<&String> = names.iter().filter(|n| is_signature_block(n)).collect();
+    if blocks.is_empty() {
+        return Ok(None);
+    }
+
+    let manifest = read_entry(archive, MANIFEST)?;
+    let mut certificates = vec![];
+    for block in blocks {
+        let stem = block
+            .rsplit_once('.')
+            .map_or(block.as_str(), |(stem, _)| stem);
+        let signature_file = read_entry(archive, &format!("{stem}.SF"))?;
+        let certificate = verify_signature_block(&read_entry(archive, block)?, &signature_file)?;
+        verify_signature_file(&signature_file, &manifest)?;
+        certificates.push(certificate);
+    }
+    verify_entries(archive, &names, &manifest)?;
+
+    Ok(Some(certificates))
+}
+
+fn is_signature_block(name: &str) -> bool {
+    let Some(file) = name.strip_prefix("META-INF/") else {
+        return false;
+    };
+    let upper = file.to_ascii_uppercase();
+    !file.contains('/')
+        && (upper.ends_with(".RSA") || upper.ends_with(".DSA") || upper.ends_with(".EC"))
+}
+
+fn is_signature_related(name: &str) -> bool {
+    name == MANIFEST
+        || is_signature_block(name)
+        || (name.starts_with("META-INF/")
+            && !name["META-INF/".len()..].contains('/')
+            && name.to_ascii_uppercase().ends_with(".SF"))
+}
+
+/// Verifies the PKCS#7 `SignedData` over a `.SF` file and returns the signer
+/// certificate.
+fn verify_signature_block(block: &[u8], signature_file: &[u8]) -> Result<Vec<u8>> {
+    let invalid =
+        |e: der::Error| ArtifactError::Signature(format!("invalid v1 signature block: {e}"));
+
+    let content_info = ContentInfo::from_der(block).map_err(invalid)?;
+    let signed_data =
+        SignedData::from_der(&content_info.content.to_der().map_err(invalid)?).map_err(invalid)?;
+    let signer = signed_data
+        .signer_infos
+        .0
+        .iter()
+        .next()
+        .ok_or_else(|| ArtifactError::Signature("v1 signature block has no signer".into()))?;
+    let SignerIdentifier::IssuerAndSerialNumber(signer_id) = &signer.sid else {
+        return Err(ArtifactError::Signature(
+            "v1 signer is not identified by issuer and serial number".into(),
+        ));
+    };
+    let certificate = signed_data
+        .certificates
+        .iter()
+        .flat_map(|set| set.0.iter())
+        .find_map(|choice| match choice {
+            CertificateChoices::Certificate(c)
+                if c.tbs_certificate.serial_number == signer_id.serial_number
+                    && c.tbs_certificate.issuer == signer_id.issuer =>
+            {
+                Some(c)
+            }
+            _ => None,
+        })
+        .ok_or_else(|| ArtifactError::Signature("v1 signer certificate not found".into()))?;
+
+    let digest = hash_from_oid(&signer.digest_alg.oid.to_string())?;
+    let (key, hash) = key_from_oid(&signer.signature_algorithm.oid.to_string())?;
+    let message = match &signer.signed_attrs {
+        Some(attributes) => {
+            let expected = attributes
+                .iter()
+                .find(|a| a.oid.to_string() == MESSAGE_DIGEST_OID)
+                .and_then(|a| a.values.iter().next())
+                .map(der::asn1::Any::value)
+                .ok_or_else(|| {
+                    ArtifactError::Signature("v1 signer lacks the message digest".into())
+                })?;
+            if expected != digest.digest(signature_file).as_slice() {
+                return Err(ArtifactError::Signature(
+                    "v1 signature file does not match its signature".into(),
+                ));
+            }
+            attributes.to_der().map_err(invalid)?
+        }
+        None => signature_file.to_vec(),
+    };
+
+    let public_key = certificate
+        .tbs_certificate
+        .subject_public_key_info
+        .to_der()
+        .map_err(invalid)?;
+    verify_signature(
+        key,
+        hash.unwrap_or(digest),
+        &public_key,
+        &message,
+        signer.signature.as_bytes(),
+    )?;
+
+    certificate.to_der().map_err(invalid)
+}
+
+/// Checks the digest of the whole manifest recorded in a `.SF` file.
+fn verify_signature_file(signature_file: &[u8], manifest: &[u8]) -> Result<()> {
+    let sections = parse_sections(signature_file);
+    let (hash, expected) = sections
+        .first()
+        .and_then(|main| {
+            main.iter().find_map(|(k, v)| {
+                k.strip_suffix("-Digest-Manifest")
+                    .and_then(hash_from_name)
+                    .map(|h| (h, v))
+            })
+        })
+        .ok_or_else(|| {
+            ArtifactError::Signature("v1 signature file lacks a manifest digest".into())
+        })?;
+    if decode_digest(expected)? != hash.digest(manifest) {
+        return Err(ArtifactError::Signature(
+            "v1 manifest does not match its signature file".into(),
+        ));
+    }
+    Ok(())
+}
+
+/// Checks every entry of the archive against its digest in the manifest.
+fn verify_entries<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    names: &[String],
+    manifest: &[u8],
+) -> Result<()> {
+    let sections = parse_sections(manifest);
+    let digests: HashMap<&str, &[(String, String)]> = sections
+        .iter()
+        .filter_map(|section| {
+            section
+                .iter()
+                .find(|(k, _)| k == "Name")
+                .map(|(_, name)| (name.as_str(), section.as_slice()))
+        })
+        .collect();
+
+    for name in names {
+        if name.ends_with('/') || is_signature_related(name) {
+            continue;
+        }
+        let (hash, expected) = digests
+            .get(name.as_str())
+            .and_then(|section| {
+                section.iter().find_map(|(k, v)| {
+                    k.strip_suffix("-Digest")
+                        .and_then(hash_from_name)
+                        .map(|h| (h, v))
+                })
+            })
+            .ok_or_else(|| {
+                ArtifactError::Signature(format!("{name} is not covered by the v1 signature"))
+            })?;
+        if decode_digest(expected)? != hash.digest(&read_entry(archive, name)?) {
+            return Err(ArtifactError::Signature(format!(
+                "{name} was modified after v1 signing"
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Splits a manifest style file into sections of `Key: value` attributes,
+/// joining continuation lines. The main section comes first.
+fn parse_sections(data: &[u8]) -> Vec<Vec<(String, String)>> {
+    let text = String::from_utf8_lossy(data);
+    let mut sections = vec![];
+    let mut current: Vec<(String, String)> = vec![];
+    for line in text.split('\n').map(|l| l.strip_suffix('\r').unwrap_or(l)) {
+        if line.is_empty() {
+            if !current.is_empty() {
+                sections.push(std::mem::take(&mut current));
+            }
+        } else if let Some(continuation) = line.strip_prefix(' ') {
+            if let Some((_, value)) = current.last_mut() {
+                value.push_str(continuation);
+            }
+        } else if let Some((key, value)) = line.split_once(": ") {
+            current.push((key.to_owned(), value.to_owned()));
+        }
+    }
+    if !current.is_empty() {
+        sections.push(current);
+    }
+    sections
+}
+
+fn decode_digest(value: &str) -> Result<Vec<u8>> {
+    STANDARD
+        .decode(value.trim())
+        .map_err(|e| ArtifactError::Signature(format!("invalid v1 digest: {e}")))
+}
+
+fn hash_from_name(name: &str) -> Option<HashAlgorithm> {
+    match name.to_ascii_uppercase().as_str() {
+        "SHA1" | "SHA-1" => Some(HashAlgorithm::Sha1),
+        "SHA-256" | "SHA256" => Some(HashAlgorithm::Sha256),
+        "SHA-512" | "SHA512" => Some(HashAlgorithm::Sha512),
+        _ => None,
+    }
+}
+
+fn hash_from_oid(oid: &str) -> Result<HashAlgorithm> {
+    match oid {
+        "1.3.14.3.2.26" => Ok(HashAlgorithm::Sha1),
+        "2.16.840.1.101.3.4.2.1" => Ok(HashAlgorithm::Sha256),
+        "2.16.840.1.101.3.4.2.3" => Ok(HashAlgorithm::Sha512),
+        oid => Err(ArtifactError::Signature(format!(
+            "unsupported v1 digest algorithm {oid}"
+        ))),
+    }
+}
+
+/// Maps a signature algorithm oid to the key type and, for combined
+/// algorithms like `sha256WithRSAEncryption`, the hash it implies.
+fn key_from_oid(oid: &str) -> Result<(KeyAlgorithm, Option<HashAlgorithm>)> {
+    match oid {
+        "1.2.840.113549.1.1.1" => Ok((KeyAlgorithm::RsaPkcs1, None)),
+        "1.2.840.113549.1.1.5" => Ok((KeyAlgorithm::RsaPkcs1, Some(HashAlgorithm::Sha1))),
+        "1.2.840.113549.1.1.11" => Ok((KeyAlgorithm::RsaPkcs1, Some(HashAlgorithm::Sha256))),
+        "1.2.840.113549.1.1.13" => Ok((KeyAlgorithm::RsaPkcs1, Some(HashAlgorithm::Sha512))),
+        "1.2.840.10045.2.1" => Ok((KeyAlgorithm::Ecdsa, None)),
+        "1.2.840.10045.4.1" => Ok((KeyAlgorithm::Ecdsa, Some(HashAlgorithm::Sha1))),
+        "1.2.840.10045.4.3.2" => Ok((KeyAlgorithm::Ecdsa, Some(HashAlgorithm::Sha256))),
+        "1.2.840.10045.4.3.4" => Ok((KeyAlgorithm::Ecdsa, Some(HashAlgorithm::Sha512))),
+        "1.2.840.10040.4.1" | "1.2.840.10040.4.3" => Ok((KeyAlgorithm::Dsa, None)),
+        oid => Err(ArtifactError::Signature(format!(
+            "unsupported v1 signature algorithm {oid}"
+        ))),
+    }
+}
@@ -0,0 +1,230 @@
+//! APK signature verification for the v1 (JAR), v2 and v3 signature schemes.
+//!
+//! Every scheme present in an APK has to verify. Signers are reported as the
+//! SHA-256 fingerprints of their certificates, the same value `apksigner
+//! verify --print-certs` prints.
+mod block;
+mod jar;
+
+use std::io::Cursor;
+
+use p256::ecdsa::signature::hazmat::PrehashVerifier;
+use rsa::{pkcs1v15, pkcs8::DecodePublicKey, pss, signature::Verifier, RsaPublicKey};
+use serde::Serialize;
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha512};
+use zip::ZipArchive;
+
+use super::{ArtifactError, Result};
+
+/// Outcome of a successful verification.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SigningInfo {
+    /// Signature schemes that were present and verified, e.g. `[1, 2, 3]`
+    pub schemes: Vec<u8>,
+    /// Certificate fingerprints of the signers of the strongest scheme
+    pub signers: Vec<String>,
+    /// Certificate fingerprints of the v3 proof-of-rotation lineage, oldest
+    /// first. Empty when the signing key was never rotated.
+    pub lineage: Vec<String>,
+}
+
+/// Verifies all signature schemes present in the APK.
+pub fn verify(bytes: &[u8]) -> Result<SigningInfo> {
+    let mut info = SigningInfo::default();
+
+    let zip = block::ZipSections::locate(bytes)?;
+    if let Some(signing_block) = block::SigningBlock::find(bytes, &zip)? {
+        if let Some(value) = signing_block.get(block::V3_BLOCK_ID) {
+            let signers = block::verify_scheme(bytes, &zip, &signing_block, value, true)?;
+            info.schemes.push(3);
+            info.signers = fingerprints(signers.iter().map(|s| s.certificate.as_slice()));
+            if let Some(signer) = signers.iter().find(|s| !s.lineage.is_empty()) {
+                info.lineage = fingerprints(signer.lineage.iter().map(Vec::as_slice));
+            }
+        }
+        if let Some(value) = signing_block.get(block::V2_BLOCK_ID) {
+            let signers = block::verify_scheme(bytes, &zip, &signing_block, value, false)?;
+            info.schemes.push(2);
+            if info.signers.is_empty() {
+                info.signers = fingerprints(signers.iter().map(|s| s.certificate.as_slice()));
+            }
+        }
+    }
+
+    let mut archive = ZipArchive::new(Cursor::new(bytes))?;
+    if let Some(certificates) = jar::verify(&mut archive)? {
+        info.schemes.push(1);
+        if info.signers.is_empty() {
+            info.signers = fingerprints(certificates.iter().map(Vec::as_slice));
+        }
+    }
+
+    if info.schemes.is_empty() {
+        return Err(ArtifactError::Signature("apk is not signed".into()));
+    }
+    info.schemes.sort_unstable();
+    Ok(info)
+}
+
+/// SHA-256 fingerprint of a DER encoded certificate.
+pub fn fingerprint(certificate: &[u8]) -> String {
+    hex::encode(Sha256::digest(certificate))
+}
+
+fn fingerprints<'a>(certificates: impl Iterator<Item = &'a [u8]>) -> Vec<String> {
+    let mut fingerprints: Vec<String> = certificates.map(fingerprint).collect();
+    fingerprints.dedup();
+    fingerprints
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum HashAlgorithm {
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+impl HashAlgorithm {
+    pub(crate) fn digest(self, data: &[u8]) -> Vec<u8> {
+        match self {
+            Self::Sha1 => Sha1::digest(data).to_vec(),
+            Self::Sha256 => Sha256::digest(data).to_vec(),
+            Self::Sha512 => Sha512::digest(data).to_vec(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum KeyAlgorithm {
+    RsaPkcs1,
+    RsaPss,
+    Ecdsa,
+    Dsa,
+}
+
+/// Verifies `signature` over `message` with a DER encoded
+/// `SubjectPublicKeyInfo`.
+pub(crate) fn verify_signature(
+    key: KeyAlgorithm,
+    hash: HashAlgorithm,
+    public_key: &[u8],
+    message: &[u8],
+    signature: &[u8],
+) -> Result<()> {
+    let invalid = |err: &dyn std::fmt::Display| ArtifactError::Signature(err.to_string());
+    match key {
+        KeyAlgorithm::RsaPkcs1 | KeyAlgorithm::RsaPss => {
+            let public_key =
+                RsaPublicKey::from_public_key_der(public_key).map_err(|e| invalid(&e))?;
+            let result = match (key, hash) {
+                (KeyAlgorithm::RsaPss, HashAlgorithm::Sha256) => {
+                    pss::VerifyingKey::<Sha256>::new(public_key).verify(
+                        message,
+                        &pss::Signature::try_from(signature).map_err(|e| invalid(&e))?,
+                    )
+                }
+                (KeyAlgorithm::RsaPss, HashAlgorithm::Sha512) => {
+                    pss::VerifyingKey::<Sha512>::new(public_key).verify(
+                        message,
+                        &pss::Signature::try_from(signature).map_err(|e| invalid(&e))?,
+                    )
+                }
+                (KeyAlgorithm::RsaPss, HashAlgorithm::Sha1) => {
+                    return Err(ArtifactError::Signature(
+                        "RSA-PSS with SHA-1 is not supported".into(),
+                    ))
+                }
+                (_, HashAlgorithm::Sha1) => pkcs1v15::VerifyingKey::<Sha1>::new(public_key).verify(
+                    message,
+                    &pkcs1v15::Signature::try_from(signature).map_err(|e| invalid(&e))?,
+                ),
+                (_, HashAlgorithm::Sha256) => pkcs1v15::VerifyingKey::<Sha256>::new(public_key)
+                    .verify(
+                        message,
+                        &pkcs1v15::Signature::try_from(signature).map_err(|e| invalid(&e))?,
+                    ),
+                (_, HashAlgorithm::Sha512) => pkcs1v15::VerifyingKey::<Sha512>::new(public_key)
+                    .verify(
+                        message,
+                        &pkcs1v15::Signature::try_from(signature).map_err(|e| invalid(&e))?,
+                    ),
+            };
+            result.map_err(|e| invalid(&e))
+        }
+        KeyAlgorithm::Ecdsa => {
+            let prehash = hash.digest(message);
+            if let Ok(key) = p256::ecdsa::VerifyingKey::from_public_key_der(public_key) {
+                let signature =
+                    p256::ecdsa::Signature::from_der(signature).map_err(|e| invalid(&e))?;
+                return key
+                    .verify_prehash(&prehash, &signature)
+                    .map_err(|e| invalid(&e));
+            }
+            if let Ok(key) = p384::ecdsa::VerifyingKey::from_public_key_der(public_key) {
+                let signature =
+                    p384::ecdsa::Signature::from_der(signature).map_err(|e| invalid(&e))?;
+                return key
+                    .verify_prehash(&prehash, &signature)
+                    .map_err(|e| invalid(&e));
+            }
+            Err(ArtifactError::Signature(
+                "unsupported elliptic curve, only P-256 and P-384 keys are accepted".into(),
+            ))
+        }
+        KeyAlgorithm::Dsa => Err(ArtifactError::Signature(
+            "DSA signatures are not supported".into(),
+        )),
+    }
+}
+
+/// Little endian reader over the length-prefixed structures of the APK
+/// signing block.
+pub(crate) struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub(crate) fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if len > self.data.len() {
+            return Err(ArtifactError::Malformed(format!(
+                "signing data truncated, wanted {len} bytes but {} remain",
+                self.data.len()
+            )));
+        }
+        let (head, tail) = self.data.split_at(len);
+        self.data = tail;
+        Ok(head)
+    }
+
+    pub(crate) fn u32(&mut self) -> Result<u32> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    pub(crate) fn u64(&mut self) -> Result<u64> {
+        let bytes = self.take(8)?;
+        let mut buf = [0; 8];
+        buf.copy_from_slice(bytes);
+        Ok(u64::from_le_bytes(buf))
+    }
+
+    /// Consumes everything that is left.
+    pub(crate) fn rest(&mut self) -> &'a [u8] {
+        std::mem::take(&mut self.data)
+    }
+
+    /// Reads a `u32` length prefixed slice.
+    pub(crate) fn prefixed(&mut self) -> Result<&'a [u8]> {
+        let len = self.u32()? as usize;
+        self.take(len)
+    }
+}
@@ -5,10 +5,15 @@ use axum::{debug_handler, extract::Query};
 use loco_rs::prelude::*;
 
 use crate::{
+    locales::Locales,
     models::{
         _entities::apps::{ActiveModel, Entity, Model},
-        apps::{AppQuery, CreateApp, Revision},
+        apps::{AppQuery, CreateApp, DryRunPayload, Revision},
+        channels::{self, Channels, CreateChannel},
+        downloads::Downloads,
+        update_checks::{self, StatsQuery, UpdateChecks},
     },
+    targeting::Attributes,
     views::api_response::PagedApiResponse,
 };
 
@@ -21,8 +26,14 @@ async fn load_item(ctx: &AppContext, id: i32) -> Result<Model> {
 pub async fn list(
     State(ctx): State<AppContext>,
     Query(query): Query<AppQuery>,
+    locales: Locales,
 ) -> Result<PagedApiResponse<Model>> {
-    let res = Model::query(&ctx.db, &query).await?;
+    let mut res = Model::query(&ctx.db, &query).await?;
+    res.page = res
+        .page
+        .into_iter()
+        .map(|app| app.localize(&locales))
+        .collect();
     Ok(res.into())
 }
 
@@ -43,7 +54,7 @@ pub async fn update(
 ) -> Result<Response> {
     let item = load_item(&ctx, id).await?;
     let mut item = item.into_active_model();
-    params.update(&mut item);
+    params.resolve(&ctx.db).await?.update(&mut item);
     let item = item.update(&ctx.db).await?;
     format::json(item)
 }
@@ -59,14 +70,100 @@ pub async fn check_update(
     State(ctx): State<AppContext>,
     Path(id): Path<i32>,
     Query(revision): Query<Revision>,
+    attributes: Attributes,
+    locales: Locales,
 ) -> Result<Response> {
-    let res = Model::check_update(&ctx.db, id, &revision).await?;
+    let res = Model::check_update(&ctx.db, id, &revision, &attributes, &locales).await?;
+    // telemetry is best effort, the device gets its answer regardless
+    if let Err(err) = update_checks::ActiveModel::record(&ctx.db, id, &revision, &res).await {
+        tracing::warn!(
+            app = id,
+            err = err.to_string(),
+            "failed to record update check"
+        );
+    }
     format::json(res)
 }
 
+/// Devices active in the window by the version they run.
 #[debug_handler]
-pub async fn get_one(Path(id): Path<i32>, State(ctx): State<AppContext>) -> Result<Response> {
-    format::json(load_item(&ctx, id).await?)
+pub async fn active_devices(
+    Path(id): Path<i32>,
+    State(ctx): State<AppContext>,
+    Query(query): Query<StatsQuery>,
+) -> Result<Response> {
+    load_item(&ctx, id).await?;
+    format::json(UpdateChecks::active_devices(&ctx.db, id, query.since()).await?)
+}
+
+/// Daily device counts and shares per version.
+#[debug_handler]
+pub async fn adoption(
+    Path(id): Path<i32>,
+    State(ctx): State<AppContext>,
+    Query(query): Query<StatsQuery>,
+) -> Result<Response> {
+    load_item(&ctx, id).await?;
+    format::json(UpdateChecks::adoption(&ctx.db, id, query.since()).await?)
+}
+
+/// Downloads and bandwidth per version.
+#[debug_handler]
+pub async fn downloads(
+    Path(id): Path<i32>,
+    State(ctx): State<AppContext>,
+    Query(query): Query<StatsQuery>,
+) -> Result<Response> {
+    load_item(&ctx, id).await?;
+    format::json(Downloads::app_stats(&ctx.db, id, query.since()).await?)
+}
+
+#[debug_handler]
+pub async fn dry_run(
+    Path(id): Path<i32>,
+    State(ctx): State<AppContext>,
+    Json(payload): Json<DryRunPayload>,
+) -> Result<Response> {
+    format::json(Model::dry_run(&ctx.db, id, &payload).await?)
+}
+
+#[debug_handler]
+pub async fn list_channels(Path(id): Path<i32>, State(ctx): State<AppContext>) -> Result<Response> {
+    load_item(&ctx, id).await?;
+    format::json(Channels::find_by_app(&ctx.db, id).await?)
+}
+
+#[debug_handler]
+pub async fn add_channel(
+    Path(id): Path<i32>,
+    State(ctx): State<AppContext>,
+    JsonValidateWithMessage(data): JsonValidateWithMessage<CreateChannel>,
+) -> Result<Response> {
+    format::json(channels::ActiveModel::create(&ctx.db, id, &data).await?)
+}
+
+#[debug_handler]
+pub async fn remove_channel(
+    Path((id, channel_id)): Path<(i32, i32)>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    Channels::find_by_id(channel_id)
+        .filter(channels::Column::AppId.eq(id))
+        .one(&ctx.db)
+        .await?
+        .ok_or(Error::NotFound)?
+        .delete(&ctx.db)
+        .await?;
+    format::empty()
+}
+
+#[debug_handler]
+pub async fn get_one(
+    Path(id): Path<i32>,
+    State(ctx): State<AppContext>,
+    locales: Locales,
+) -> Result<Response> {
+    format::json(load_item(&ctx, id).await?.localize(&locales))
 }
 
 pub fn routes() -> Routes {
@@ -79,4 +176,11 @@ pub fn routes() -> Routes {
         .add("{id}", put(update))
         .add("{id}", patch(update))
         .add("{id}/check-update", get(check_update))
+        .add("{id}/targeting/dry-run", post(dry_run))
+        .add("{id}/stats/active-devices", get(active_devices))
+        .add("{id}/stats/adoption", get(adoption))
+        .add("{id}/downloads", get(downloads))
+        .add("{id}/channels", get(list_channels))
+        .add("{id}/channels", post(add_channel))
+        .add("{id}/channels/{channel_id}", delete(remove_channel))
 }
@@ -1,20 +1,30 @@
 #![allow(clippy::missing_errors_doc)]
 #![allow(clippy::unnecessary_struct_initialization)]
 #![allow(clippy::unused_async)]
-use axum::{debug_handler, extract::Query};
+use axum::{body::Body, debug_handler, extract::Query, http::header, response::Redirect};
 use loco_rs::prelude::*;
 
 use crate::{
+    artifacts::ArtifactType,
     models::{
         _entities::app_versions::{Entity, Model},
+        app_version_artifacts::{self, AddArtifact, AppVersionArtifacts},
         app_versions::{
             ActiveModel, AppVersionQuery, CreateAppVersion, PatchAppVersion, PublishPayload,
+            RollbackPayload, RolloutPayload, SchedulePayload, YankPayload,
         },
+        apps::Apps,
+        channels::{self, PromotePayload},
+        downloads::Downloads,
+        files::Files,
+        update_checks::StatsQuery,
     },
     views::{
         api_response::{ApiResponse, PagedApiResponse},
         api_result::AppError,
+        app_versions::InstallManifest,
     },
+    workers::{self, webhook::WebhookWorker},
 };
 
 async fn load_item(ctx: &AppContext, id: i32) -> Result<Model> {
@@ -22,6 +32,15 @@ async fn load_item(ctx: &AppContext, id: i32) -> Result<Model> {
     item.ok_or_else(|| Error::NotFound)
 }
 
+/// Loads a version devices may download, yanked ones are not served again.
+async fn load_servable(ctx: &AppContext, id: i32) -> Result<Model> {
+    let item = load_item(ctx, id).await?;
+    if item.yanked_at.is_some() {
+        return Err(Error::BadRequest(format!("version {id} was yanked")));
+    }
+    Ok(item)
+}
+
 #[debug_handler]
 pub async fn list(
     State(ctx): State<AppContext>,
@@ -37,6 +56,11 @@ pub async fn add(
     axum::Json(data): axum::Json<CreateAppVersion>,
 ) -> std::result::Result<ApiResponse<Model, ()>, AppError> {
     let res = ActiveModel::create(&ctx.db, &data).await?;
+    if res.published_at.is_some() {
+        workers::after_publish(&ctx, res.id, None).await?;
+    } else {
+        WebhookWorker::enqueue(&ctx).await?;
+    }
     Ok(ApiResponse::ok(res, None))
 }
 
@@ -47,6 +71,15 @@ pub async fn update(
     axum::Json(params): axum::Json<PatchAppVersion>,
 ) -> Result<Response> {
     let item = load_item(&ctx, id).await?;
+    if let Some(code) = params
+        .version_code
+        .filter(|&code| code != item.version_code)
+    {
+        return Err(Error::BadRequest(format!(
+            "version_code of version {id} is {} and cannot be changed to {code}",
+            item.version_code
+        )));
+    }
     let mut item = item.into_active_model();
     params.update(&mut item);
     let item = item.update(&ctx.db).await?;
@@ -59,13 +92,169 @@ pub async fn publish(
     Path(id): Path<i32>,
     axum::Json(payload): axum::Json<PublishPayload>,
 ) -> Result<Response> {
-    ActiveModel::publish(&ctx.db, id, payload.publish).await?;
+    ActiveModel::publish(&ctx.db, id, &payload).await?;
+    if payload.publish {
+        workers::after_publish(&ctx, id, None).await?;
+    } else {
+        WebhookWorker::enqueue(&ctx).await?;
+    }
     format::empty()
 }
 
+/// Publishes a version at a later time.
+#[debug_handler]
+pub async fn schedule(
+    State(ctx): State<AppContext>,
+    Path(id): Path<i32>,
+    axum::Json(payload): axum::Json<SchedulePayload>,
+) -> Result<Response> {
+    format::json(ActiveModel::schedule(&ctx.db, id, &payload).await?)
+}
+
+#[debug_handler]
+pub async fn unschedule(State(ctx): State<AppContext>, Path(id): Path<i32>) -> Result<Response> {
+    format::json(ActiveModel::unschedule(&ctx.db, id).await?)
+}
+
+#[debug_handler]
+pub async fn update_rollout(
+    State(ctx): State<AppContext>,
+    Path(id): Path<i32>,
+    axum::Json(payload): axum::Json<RolloutPayload>,
+) -> Result<Response> {
+    let version = ActiveModel::update_rollout(&ctx.db, id, &payload).await?;
+    workers::after_rollout(&ctx, &version).await?;
+    format::json(version)
+}
+
+/// Pulls a published version, the app falls back to the previous release.
+#[debug_handler]
+pub async fn rollback(
+    State(ctx): State<AppContext>,
+    Path(id): Path<i32>,
+    JsonValidateWithMessage(payload): JsonValidateWithMessage<RollbackPayload>,
+) -> Result<Response> {
+    let target = ActiveModel::rollback(&ctx.db, id, &payload).await?;
+    WebhookWorker::enqueue(&ctx).await?;
+    format::json(serde_json::json!({ "target": target }))
+}
+
+/// Yanks a version, devices running it are told to replace it.
+#[debug_handler]
+pub async fn yank(
+    State(ctx): State<AppContext>,
+    Path(id): Path<i32>,
+    JsonValidateWithMessage(payload): JsonValidateWithMessage<YankPayload>,
+) -> Result<Response> {
+    let target = ActiveModel::yank(&ctx.db, id, &payload).await?;
+    WebhookWorker::enqueue(&ctx).await?;
+    format::json(serde_json::json!({ "target": target }))
+}
+
+#[debug_handler]
+pub async fn promote(
+    State(ctx): State<AppContext>,
+    Path(id): Path<i32>,
+    axum::Json(payload): axum::Json<PromotePayload>,
+) -> Result<Response> {
+    let channel = channels::ActiveModel::promote(&ctx.db, id, &payload).await?;
+    workers::after_publish(&ctx, id, Some(&channel)).await?;
+    format::json(serde_json::json!({ "channel": channel }))
+}
+
+/// Downloads of the version and the bandwidth they used.
+#[debug_handler]
+pub async fn downloads(
+    Path(id): Path<i32>,
+    State(ctx): State<AppContext>,
+    Query(query): Query<StatsQuery>,
+) -> Result<Response> {
+    load_item(&ctx, id).await?;
+    format::json(Downloads::version_stats(&ctx.db, id, query.since()).await?)
+}
+
+#[debug_handler]
+pub async fn list_artifacts(
+    Path(id): Path<i32>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    load_item(&ctx, id).await?;
+    format::json(AppVersionArtifacts::find_by_version(&ctx.db, id).await?)
+}
+
+#[debug_handler]
+pub async fn add_artifact(
+    Path(id): Path<i32>,
+    State(ctx): State<AppContext>,
+    JsonValidateWithMessage(data): JsonValidateWithMessage<AddArtifact>,
+) -> std::result::Result<ApiResponse<app_version_artifacts::Model, ()>, AppError> {
+    let res = app_version_artifacts::ActiveModel::create(&ctx.db, id, &data).await?;
+    Ok(ApiResponse::ok(res, None))
+}
+
+#[debug_handler]
+pub async fn remove_artifact(
+    Path((id, artifact_id)): Path<(i32, i32)>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    AppVersionArtifacts::find_by_id(artifact_id)
+        .filter(app_version_artifacts::Column::AppVersionId.eq(id))
+        .one(&ctx.db)
+        .await?
+        .ok_or(Error::NotFound)?
+        .delete(&ctx.db)
+        .await?;
+    format::empty()
+}
+
+/// Serves the over-the-air install manifest of a version built from an ipa.
+#[debug_handler]
+pub async fn install_manifest(
+    Path(id): Path<i32>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    let version = load_servable(&ctx, id).await?;
+    let file = Files::find_by_id(version.apk_file_id.ok_or(Error::NotFound)?)
+        .one(&ctx.db)
+        .await?
+        .ok_or(Error::NotFound)?;
+    if file.artifact_type() != Some(ArtifactType::Ipa) {
+        return Err(Error::BadRequest(format!(
+            "version {id} is not an ipa, only ipas install over the air"
+        )));
+    }
+    let app = Apps::find_by_id(version.app_id)
+        .one(&ctx.db)
+        .await?
+        .ok_or(Error::NotFound)?;
+
+    let ipa_url = format!("{}{}", ctx.config.server.full_url(), file.static_path());
+    let manifest = InstallManifest::new(&app, &version, ipa_url)
+        .to_xml()
+        .map_err(Error::msg)?;
+    Ok(Response::builder()
+        .header(header::CONTENT_TYPE, "text/xml")
+        .body(Body::from(manifest))?)
+}
+
+/// Redirects to the `itms-services://` link installing the version, open it
+/// in Safari on the device.
+#[debug_handler]
+pub async fn install(Path(id): Path<i32>, State(ctx): State<AppContext>) -> Result<Response> {
+    load_servable(&ctx, id).await?;
+    let manifest_url = format!(
+        "{}/api/app-versions/{id}/manifest.plist",
+        ctx.config.server.full_url()
+    );
+    // the manifest url carries no query of its own, so it is passed as is
+    let link = format!("itms-services://?action=download-manifest&url={manifest_url}");
+    Ok(Redirect::to(&link).into_response())
+}
+
 #[debug_handler]
 pub async fn remove(Path(id): Path<i32>, State(ctx): State<AppContext>) -> Result<Response> {
-    load_item(&ctx, id).await?.delete(&ctx.db).await?;
+    ActiveModel::delete_version(&ctx.db, load_item(&ctx, id).await?).await?;
+    WebhookWorker::enqueue(&ctx).await?;
     format::empty()
 }
 
@@ -84,4 +273,16 @@ pub fn routes() -> Routes {
         .add("{id}", put(update))
         .add("{id}", patch(update))
         .add("{id}/publish", post(publish))
+        .add("{id}/schedule", post(schedule))
+        .add("{id}/schedule", delete(unschedule))
+        .add("{id}/rollout", post(update_rollout))
+        .add("{id}/promote", post(promote))
+        .add("{id}/rollback", post(rollback))
+        .add("{id}/yank", post(yank))
+        .add("{id}/downloads", get(downloads))
+        .add("{id}/artifacts", get(list_artifacts))
+        .add("{id}/artifacts", post(add_artifact))
+        .add("{id}/artifacts/{artifact_id}", delete(remove_artifact))
+        .add("{id}/manifest.plist", get(install_manifest))
+        .add("{id}/install", get(install))
 }
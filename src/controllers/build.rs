@@ -0,0 +1,67 @@
+#![allow(clippy::missing_errors_doc)]
+#![allow(clippy::unused_async)]
+use axum::{
+    debug_handler,
+    extract::{DefaultBodyLimit, Multipart},
+};
+use loco_rs::prelude::*;
+
+use crate::{
+    models::{builds::BuildUpload, files::Upload},
+    views::{api_response::ApiResponse, api_result::AppError, builds::BuildResponse},
+};
+
+/// Accepts the multipart fields `file` (the apk, ipa, hap or app pack), `bundle` (the aab
+/// an apk was built from), `release_notes`, `publish` and `mandatory`.
+#[debug_handler]
+pub async fn add(
+    State(ctx): State<AppContext>,
+    mut multipart: Multipart,
+) -> std::result::Result<ApiResponse<BuildResponse, ()>, AppError> {
+    let mut artifact = None;
+    let mut bundle = None;
+    let mut release_notes = None;
+    let mut publish = false;
+    let mut mandatory = false;
+    while let Some(field) = multipart.next_field().await.map_err(Error::msg)? {
+        let name = field.name().map(ToOwned::to_owned);
+        match name.as_deref() {
+            Some("file") => artifact = Some(Upload::read(field).await?),
+            Some("bundle") => bundle = Some(Upload::read(field).await?),
+            Some("release_notes") => {
+                release_notes = Some(field.text().await.map_err(Error::msg)?);
+            }
+            Some("publish") => {
+                let value = field.text().await.map_err(Error::msg)?;
+                publish = value
+                    .parse()
+                    .map_err(|_| Error::BadRequest(format!("invalid publish flag {value}")))?;
+            }
+            Some("mandatory") => {
+                let value = field.text().await.map_err(Error::msg)?;
+                mandatory = value
+                    .parse()
+                    .map_err(|_| Error::BadRequest(format!("invalid mandatory flag {value}")))?;
+            }
+            _ => {}
+        }
+    }
+
+    let upload = BuildUpload {
+        artifact: artifact.ok_or_else(|| Error::BadRequest("file is required".to_string()))?,
+        bundle,
+        release_notes,
+        publish,
+        mandatory,
+    };
+    let build = upload.create(&ctx).await?;
+    Ok(ApiResponse::ok(build.into(), None))
+}
+
+pub fn routes() -> Routes {
+    Routes::new()
+        .prefix("api/builds/")
+        // set max body limit to 200 mb
+        .add("/", post(add))
+        .layer(DefaultBodyLimit::max(200 * 1024 * 1024))
+}
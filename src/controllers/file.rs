@@ -2,17 +2,21 @@
 #![allow(clippy::unnecessary_struct_initialization)]
 #![allow(clippy::unused_async)]
 use axum::{
-    body::Body,
+    body::{Body, Bytes},
     debug_handler,
     extract::{DefaultBodyLimit, Multipart, Query},
     http::{header, StatusCode},
 };
 use loco_rs::prelude::*;
 use serde::{Deserialize, Serialize};
-use sha2::{Digest, Sha256};
 
 use crate::models::_entities::files::{self, ActiveModel, Entity, Model};
+use crate::models::app_versions::{self, AppVersions};
+use crate::models::downloads::{self, Client};
+use crate::models::files::{remove_stored, Upload};
+use crate::models::webhooks::Event;
 use crate::views::api_response::PagedApiResponse;
+use crate::workers::webhook::WebhookWorker;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Params {
@@ -51,34 +55,32 @@ pub async fn add(
     query.update(&mut item);
 
     // we only care the first file
-    if let Some(field) = multipart.next_field().await.map_err(Error::msg)? {
-        item.name = Set(field
-            .file_name()
-            .ok_or(Error::string("empty file name"))?
-            .to_owned());
-        item.mime = Set(field
-            .content_type()
-            .ok_or(Error::string("content type is null"))?
-            .to_owned());
-
-        // consume the fileds bytes  and save the file using storage
-        let bytes = field.bytes().await.map_err(Error::msg)?;
-        item.size_bytes = Set(bytes.len() as i64);
-
-        let path = Uuid::new_v4().to_string();
-        item.path = Set(path.clone());
-        let file_path = std::path::Path::new(&path);
-        ctx.storage.upload(file_path, &bytes).await?;
-
-        // calculate the checksum_sha256
-        let mut hasher = Sha256::new();
-        hasher.update(&bytes);
-        let checksum = hex::encode(hasher.finalize());
-        item.checksum_sha256 = Set(checksum);
-    }
+    let upload = match multipart.next_field().await.map_err(Error::msg)? {
+        Some(field) => Some(Upload::read(field).await?),
+        None => None,
+    };
 
-    let item = item.insert(&ctx.db).await?;
-    format::json(item)
+    // the icon row and the file row go in together, and nothing stays in
+    // storage when either fails
+    let mut written = vec![];
+    let inserted = async {
+        let tx = ctx.db.begin().await?;
+        if let Some(upload) = upload {
+            item.store_upload(&tx, &ctx.storage, upload, &mut written)
+                .await?;
+        }
+        let item = item.insert(&tx).await?;
+        tx.commit().await?;
+        Ok::<_, Error>(item)
+    }
+    .await;
+    match inserted {
+        Ok(item) => format::json(item),
+        Err(err) => {
+            remove_stored(&ctx.storage, &written).await;
+            Err(err)
+        }
+    }
 }
 
 #[debug_handler]
@@ -96,7 +98,18 @@ pub async fn update(
 
 #[debug_handler]
 pub async fn remove(Path(id): Path<i32>, State(ctx): State<AppContext>) -> Result<Response> {
-    load_item(&ctx, id).await?.delete(&ctx.db).await?;
+    let item = load_item(&ctx, id).await?;
+    // versions are deleted along with their file, webhooks hear about them
+    let tx = ctx.db.begin().await?;
+    let versions = AppVersions::find_by_apk_file(&tx, id).await?;
+    for version in &versions {
+        app_versions::ActiveModel::notify(&tx, Event::VersionDeleted, version).await?;
+    }
+    item.delete(&tx).await?;
+    tx.commit().await?;
+    if !versions.is_empty() {
+        WebhookWorker::enqueue(&ctx).await?;
+    }
     format::empty()
 }
 
@@ -109,6 +122,7 @@ pub async fn get_one(Path(id): Path<i32>, State(ctx): State<AppContext>) -> Resu
 pub async fn serve_file(
     Path(key): Path<String>,
     State(ctx): State<AppContext>,
+    client: Client,
 ) -> Result<Response> {
     // Find the file in the database by path
     let file = Entity::find()
@@ -121,7 +135,33 @@ pub async fn serve_file(
     let file_path = std::path::Path::new(&file.path);
 
     // Get the file stream from storage
-    let stream: Vec<u8> = ctx.storage.download(&file_path).await.map_err(Error::msg)?;
+    let stream: Vec<u8> = ctx.storage.download(file_path).await.map_err(Error::msg)?;
+    let len = stream.len();
+
+    let download = downloads::ActiveModel::start(&ctx.db, &file, &client).await?;
+    let log = DownloadLog {
+        db: ctx.db.clone(),
+        id: download.id,
+        sent: 0,
+        // an empty body is never polled
+        completed: len == 0,
+    };
+    // hand the body out in chunks so a client hanging up midway shows in
+    // the log with the bytes handed out to it, at most a chunk more than it
+    // got. With a Content-Length the connection stops polling after the
+    // last byte, so the download is complete once its last chunk is out.
+    let body = futures_util::stream::unfold(
+        (Bytes::from(stream), log),
+        |(mut rest, mut log)| async move {
+            if rest.is_empty() {
+                return None;
+            }
+            let chunk = rest.split_to(rest.len().min(CHUNK_SIZE));
+            log.sent += chunk.len();
+            log.completed = rest.is_empty();
+            Some((Ok::<_, std::io::Error>(chunk), (rest, log)))
+        },
+    );
 
     Ok(Response::builder()
         .status(StatusCode::OK)
@@ -130,8 +170,42 @@ pub async fn serve_file(
             header::CONTENT_DISPOSITION,
             format!("attachment; filename=\"{}\"", file.name),
         )
-        .header(header::CONTENT_LENGTH, stream.len())
-        .body(Body::from(stream))?)
+        .header(header::CONTENT_LENGTH, len)
+        .body(Body::from_stream(body))?)
+}
+
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Records what was sent of a download once its body is dropped, whether
+/// it was sent in full or the client went away.
+struct DownloadLog {
+    db: DatabaseConnection,
+    id: i32,
+    /// Bytes handed out to the connection so far
+    sent: usize,
+    /// Whether the last chunk was handed out
+    completed: bool,
+}
+
+impl Drop for DownloadLog {
+    fn drop(&mut self) {
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let db = self.db.clone();
+        let id = self.id;
+        let sent = i64::try_from(self.sent).unwrap_or(i64::MAX);
+        let completed = self.completed;
+        runtime.spawn(async move {
+            if let Err(err) = downloads::ActiveModel::finish(&db, id, sent, completed).await {
+                tracing::warn!(
+                    download = id,
+                    err = err.to_string(),
+                    "failed to record download"
+                );
+            }
+        });
+    }
 }
 
 pub fn routes() -> Routes {
@@ -17,7 +17,7 @@ pub struct Params {
 impl Params {
     fn update(&self, item: &mut ActiveModel) {
         item.name = Set(self.name.clone());
-        item.code = Set(self.code.clone());
+        item.code = Set(self.code);
         item.icon_url = Set(self.icon_url.clone());
     }
 }
@@ -0,0 +1,82 @@
+#![allow(clippy::missing_errors_doc)]
+#![allow(clippy::unused_async)]
+use axum::debug_handler;
+use loco_rs::prelude::*;
+
+use crate::models::{
+    subscriptions::{self, Subscribe, Subscriptions},
+    users,
+};
+
+async fn current_user(ctx: &AppContext, auth: &auth::JWT) -> Result<users::Model> {
+    Ok(users::Model::find_by_pid(&ctx.db, &auth.claims.pid).await?)
+}
+
+/// Subscriptions of the signed in user.
+#[debug_handler]
+pub async fn list(auth: auth::JWT, State(ctx): State<AppContext>) -> Result<Response> {
+    let user = current_user(&ctx, &auth).await?;
+    format::json(Subscriptions::find_by_user(&ctx.db, user.id).await?)
+}
+
+/// Subscribes the signed in user to an app's releases, or to a channel.
+#[debug_handler]
+pub async fn add(
+    auth: auth::JWT,
+    State(ctx): State<AppContext>,
+    Json(data): Json<Subscribe>,
+) -> Result<Response> {
+    let user = current_user(&ctx, &auth).await?;
+    format::json(subscriptions::ActiveModel::subscribe(&ctx.db, user.id, &data).await?)
+}
+
+#[debug_handler]
+pub async fn remove(
+    auth: auth::JWT,
+    Path(id): Path<i32>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    let user = current_user(&ctx, &auth).await?;
+    let item = Subscriptions::find_by_id(id)
+        .one(&ctx.db)
+        .await?
+        .filter(|item| item.user_id == user.id)
+        .ok_or_else(|| Error::NotFound)?;
+    item.delete(&ctx.db).await?;
+    format::empty()
+}
+
+/// Target of the unsubscribe link in announcements, works signed out. It
+/// only asks for confirmation, links opened by mail scanners and
+/// prefetchers must not end the subscription.
+#[debug_handler]
+pub async fn confirm_unsubscribe(
+    Path(token): Path<String>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    Subscriptions::find_by_unsubscribe_token(&ctx.db, &token).await?;
+    format::html(
+        "<form method=\"post\"><p>Stop getting release emails for this app?</p>\
+         <button type=\"submit\">Unsubscribe</button></form>",
+    )
+}
+
+/// Ends the subscription, posted by the confirmation page.
+#[debug_handler]
+pub async fn unsubscribe(
+    Path(token): Path<String>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    subscriptions::ActiveModel::unsubscribe(&ctx.db, &token).await?;
+    format::html("<p>You are unsubscribed and will not get these emails anymore.</p>")
+}
+
+pub fn routes() -> Routes {
+    Routes::new()
+        .prefix("api/subscriptions/")
+        .add("/", get(list))
+        .add("/", post(add))
+        .add("{id}", delete(remove))
+        .add("unsubscribe/{token}", get(confirm_unsubscribe))
+        .add("unsubscribe/{token}", post(unsubscribe))
+}
@@ -0,0 +1,103 @@
+#![allow(clippy::missing_errors_doc)]
+#![allow(clippy::unnecessary_struct_initialization)]
+#![allow(clippy::unused_async)]
+use axum::{debug_handler, extract::Query};
+use loco_rs::{model::query::PaginationQuery, prelude::*};
+
+use crate::{
+    models::{
+        _entities::webhooks::{ActiveModel, Entity, Model},
+        apps::Apps,
+        webhook_deliveries::{self, WebhookDeliveries},
+        webhooks::{CreateWebhook, WebhookQuery, Webhooks},
+    },
+    views::{api_response::PagedApiResponse, webhooks::CreatedWebhook},
+};
+
+async fn load_item(ctx: &AppContext, id: i32) -> Result<Model> {
+    let item = Entity::find_by_id(id).one(&ctx.db).await?;
+    item.ok_or_else(|| Error::NotFound)
+}
+
+async fn check_app(ctx: &AppContext, params: &CreateWebhook) -> Result<()> {
+    if let Some(app_id) = params.app_id {
+        Apps::find_by_id(app_id)
+            .one(&ctx.db)
+            .await?
+            .ok_or_else(|| Error::BadRequest(format!("app {app_id} does not exist")))?;
+    }
+    Ok(())
+}
+
+/// Webhooks of the app in `app_id`, the global ones without it.
+#[debug_handler]
+pub async fn list(
+    State(ctx): State<AppContext>,
+    Query(query): Query<WebhookQuery>,
+) -> Result<Response> {
+    format::json(Webhooks::find_by_app(&ctx.db, query.app_id).await?)
+}
+
+/// Creates a webhook, the response is the only one to include its secret.
+#[debug_handler]
+pub async fn add(
+    State(ctx): State<AppContext>,
+    JsonValidateWithMessage(params): JsonValidateWithMessage<CreateWebhook>,
+) -> Result<Response> {
+    check_app(&ctx, &params).await?;
+    let mut item = ActiveModel {
+        ..Default::default()
+    };
+    params.update(&mut item)?;
+    let item = item.insert(&ctx.db).await?;
+    format::json(CreatedWebhook::from(item))
+}
+
+#[debug_handler]
+pub async fn update(
+    Path(id): Path<i32>,
+    State(ctx): State<AppContext>,
+    JsonValidateWithMessage(params): JsonValidateWithMessage<CreateWebhook>,
+) -> Result<Response> {
+    check_app(&ctx, &params).await?;
+    let item = load_item(&ctx, id).await?;
+    let mut item = item.into_active_model();
+    params.update(&mut item)?;
+    let item = item.update(&ctx.db).await?;
+    format::json(item)
+}
+
+#[debug_handler]
+pub async fn remove(Path(id): Path<i32>, State(ctx): State<AppContext>) -> Result<Response> {
+    load_item(&ctx, id).await?.delete(&ctx.db).await?;
+    format::empty()
+}
+
+#[debug_handler]
+pub async fn get_one(Path(id): Path<i32>, State(ctx): State<AppContext>) -> Result<Response> {
+    format::json(load_item(&ctx, id).await?)
+}
+
+/// Delivery log of a webhook, most recent first.
+#[debug_handler]
+pub async fn list_deliveries(
+    Path(id): Path<i32>,
+    State(ctx): State<AppContext>,
+    Query(pagination): Query<PaginationQuery>,
+) -> Result<PagedApiResponse<webhook_deliveries::Model>> {
+    load_item(&ctx, id).await?;
+    let res = WebhookDeliveries::find_by_webhook(&ctx.db, id, &pagination).await?;
+    Ok(res.into())
+}
+
+pub fn routes() -> Routes {
+    Routes::new()
+        .prefix("api/webhooks/")
+        .add("/", get(list))
+        .add("/", post(add))
+        .add("{id}", get(get_one))
+        .add("{id}", delete(remove))
+        .add("{id}", put(update))
+        .add("{id}", patch(update))
+        .add("{id}/deliveries", get(list_deliveries))
+}
@@ -0,0 +1,107 @@
+//! Picking the variant of a localized text. Clients ask for a locale with
+//! the `locale` query parameter or `Accept-Language`, and whatever they ask
+//! for is followed by `settings.locales.fallback`.
+use std::collections::BTreeMap;
+
+use axum::{
+    extract::{FromRequestParts, Query},
+    http::{header, request::Parts},
+};
+use loco_rs::{app::AppContext, Error};
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::settings::Settings;
+
+/// Locales to try, most preferred first.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Locales(Vec<String>);
+
+impl Locales {
+    /// The requested locales, each followed by its language (`zh-CN` by
+    /// `zh`), then the fallback chain.
+    pub fn new<I, S>(requested: I, fallback: &[String]) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut locales: Vec<String> = vec![];
+        let mut push = |locale: String| {
+            if !locale.is_empty() && !locales.contains(&locale) {
+                locales.push(locale);
+            }
+        };
+        for locale in requested {
+            let locale = normalize(locale.as_ref());
+            if let Some((language, _)) = locale.split_once('-') {
+                let language = language.to_string();
+                push(locale);
+                push(language);
+            } else {
+                push(locale);
+            }
+        }
+        for locale in fallback {
+            push(normalize(locale));
+        }
+        Self(locales)
+    }
+
+    /// Languages of an `Accept-Language` header by descending quality.
+    pub fn parse_accept_language(header: &str) -> Vec<String> {
+        let mut ranges: Vec<(f32, &str)> = header
+            .split(',')
+            .filter_map(|range| {
+                let mut parts = range.split(';').map(str::trim);
+                let tag = parts.next().filter(|tag| !tag.is_empty() && *tag != "*")?;
+                let quality = parts
+                    .find_map(|param| param.strip_prefix("q="))
+                    .map_or(Some(1.0), |q| q.parse().ok())?;
+                (quality > 0.0).then_some((quality, tag))
+            })
+            .collect();
+        // stable, so equal qualities keep the order of the header
+        ranges.sort_by(|a, b| b.0.total_cmp(&a.0));
+        ranges.into_iter().map(|(_, tag)| tag.to_string()).collect()
+    }
+
+    /// The variant of the first locale `texts`, a JSON object of texts by
+    /// locale, has.
+    pub fn pick<'a>(&self, texts: Option<&'a Value>) -> Option<&'a str> {
+        let texts: BTreeMap<String, &str> = texts?
+            .as_object()?
+            .iter()
+            .filter_map(|(locale, text)| Some((normalize(locale), text.as_str()?)))
+            .collect();
+        self.0.iter().find_map(|locale| texts.get(locale).copied())
+    }
+}
+
+fn normalize(locale: &str) -> String {
+    locale.trim().replace('_', "-").to_ascii_lowercase()
+}
+
+#[derive(Deserialize)]
+struct LocaleQuery {
+    locale: Option<String>,
+}
+
+impl FromRequestParts<AppContext> for Locales {
+    type Rejection = Error;
+
+    async fn from_request_parts(parts: &mut Parts, ctx: &AppContext) -> Result<Self, Error> {
+        let settings = Settings::from_config(&ctx.config)?.locales;
+        let query = Query::<LocaleQuery>::try_from_uri(&parts.uri)
+            .map_err(|err| Error::BadRequest(err.to_string()))?;
+        let requested = match query.0.locale {
+            Some(locale) => vec![locale],
+            None => parts
+                .headers
+                .get(header::ACCEPT_LANGUAGE)
+                .and_then(|value| value.to_str().ok())
+                .map(Self::parse_accept_language)
+                .unwrap_or_default(),
+        };
+        Ok(Self::new(requested, &settings.fallback))
+    }
+}
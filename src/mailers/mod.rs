@@ -1,3 +1,4 @@
 pub mod auth;
+pub mod release;
 
 pub mod test0;
\ No newline at end of file
@@ -0,0 +1,61 @@
+// release mailer
+#![allow(non_upper_case_globals)]
+
+use loco_rs::prelude::*;
+use serde_json::json;
+
+use crate::models::{app_versions, apps, subscriptions, users};
+
+static announcement: Dir<'_> = include_dir!("src/mailers/release/announcement");
+
+#[allow(clippy::module_name_repetitions)]
+pub struct ReleaseMailer {}
+impl Mailer for ReleaseMailer {}
+impl ReleaseMailer {
+    /// Announces a version to a subscriber, with its release notes, a
+    /// download link and a link ending the subscription.
+    ///
+    /// # Errors
+    ///
+    /// When email sending is failed
+    pub async fn send_announcement(
+        ctx: &AppContext,
+        user: &users::Model,
+        subscription: &subscriptions::Model,
+        announced: &Announcement<'_>,
+    ) -> Result<()> {
+        let host = ctx.config.server.full_url();
+        Self::mail_template(
+            ctx,
+            &announcement,
+            mailer::Args {
+                to: user.email.to_string(),
+                locals: json!({
+                  "name": user.name,
+                  "app": announced.app.name,
+                  "channel": announced.channel,
+                  "version_name": announced.version.version_name,
+                  "version_code": announced.version.version_code,
+                  "release_notes": announced.release_notes,
+                  "download_url": format!("{host}{}", announced.download_path),
+                  "unsubscribe_url": format!("{host}{}", subscription.unsubscribe_path()),
+                }),
+                ..Default::default()
+            },
+        )
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// What an announcement is about.
+pub struct Announcement<'a> {
+    pub app: &'a apps::Model,
+    pub version: &'a app_versions::Model,
+    /// The channel the version was promoted to, `None` for a release
+    pub channel: Option<&'a str>,
+    /// In the subscriber's locale where translated
+    pub release_notes: Option<String>,
+    pub download_path: String,
+}
@@ -0,0 +1,12 @@
+<html>
+
+<body>
+  Hey {{name}},
+  <p>{{app}} {{version_name}} ({{version_code}}) is out{% if channel %} on the {{channel}} channel{% endif %}.</p>
+  {% if release_notes %}<p>{{release_notes | escape | linebreaksbr}}</p>{% endif %}
+  <a href="{{download_url}}">Download {{app}} {{version_name}}</a>
+  <p><small>You get this email because you subscribed to {{app}}.
+  <a href="{{unsubscribe_url}}">Unsubscribe</a></small></p>
+</body>
+
+</html>
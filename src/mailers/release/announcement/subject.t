@@ -0,0 +1 @@
+{{app}} {{version_name}} is out
\ No newline at end of file
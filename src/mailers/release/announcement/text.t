@@ -0,0 +1,11 @@
+{{app}} {{version_name}} ({{version_code}}) is out{% if channel %} on the {{channel}} channel{% endif %}.
+{% if release_notes %}
+{{release_notes}}
+{% endif %}
+Download it here:
+
+{{download_url}}
+
+You get this email because you subscribed to {{app}}. Unsubscribe:
+
+{{unsubscribe_url}}
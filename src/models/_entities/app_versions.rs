@@ -10,17 +10,36 @@ pub struct Model {
     pub updated_at: DateTimeWithTimeZone,
     #[sea_orm(primary_key)]
     pub id: i32,
-    pub version_code: String,
+    pub version_code: i64,
     pub version_name: String,
     #[sea_orm(column_type = "Text", nullable)]
     pub release_notes: Option<String>,
     pub apk_file_id: Option<i32>,
     pub published_at: Option<DateTimeWithTimeZone>,
     pub app_id: i32,
+    pub min_supported_version_code: Option<i64>,
+    pub mandatory: bool,
+    pub rollout_percentage: i16,
+    pub rollout_state: Option<String>,
+    pub rolled_back_at: Option<DateTimeWithTimeZone>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub rollback_reason: Option<String>,
+    pub yanked_at: Option<DateTimeWithTimeZone>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub yank_reason: Option<String>,
+    pub yank_forced: bool,
+    #[sea_orm(column_type = "JsonBinary", nullable)]
+    pub localized_release_notes: Option<Json>,
+    #[sea_orm(column_type = "JsonBinary", nullable)]
+    pub targeting: Option<Json>,
+    pub scheduled_publish_at: Option<DateTimeWithTimeZone>,
+    pub scheduled_rollout_percentage: Option<i16>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::app_version_artifacts::Entity")]
+    AppVersionArtifacts,
     #[sea_orm(
         belongs_to = "super::apps::Entity",
         from = "Column::AppId",
@@ -29,6 +48,10 @@ pub enum Relation {
         on_delete = "Cascade"
     )]
     Apps,
+    #[sea_orm(has_many = "super::channels::Entity")]
+    Channels,
+    #[sea_orm(has_many = "super::downloads::Entity")]
+    Downloads,
     #[sea_orm(
         belongs_to = "super::files::Entity",
         from = "Column::ApkFileId",
@@ -39,12 +62,30 @@ pub enum Relation {
     Files,
 }
 
+impl Related<super::app_version_artifacts::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::AppVersionArtifacts.def()
+    }
+}
+
 impl Related<super::apps::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Apps.def()
     }
 }
 
+impl Related<super::channels::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Channels.def()
+    }
+}
+
+impl Related<super::downloads::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Downloads.def()
+    }
+}
+
 impl Related<super::files::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Files.def()
@@ -18,6 +18,11 @@ pub struct Model {
     #[sea_orm(column_type = "Text", nullable)]
     pub description: Option<String>,
     pub platform_id: i32,
+    #[sea_orm(column_type = "JsonBinary", nullable)]
+    pub signer_fingerprints: Option<Json>,
+    pub min_supported_version_code: Option<i64>,
+    #[sea_orm(column_type = "JsonBinary", nullable)]
+    pub localized_descriptions: Option<Json>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -30,6 +35,10 @@ pub enum Relation {
         on_delete = "Cascade"
     )]
     AppVersions,
+    #[sea_orm(has_many = "super::channels::Entity")]
+    Channels,
+    #[sea_orm(has_many = "super::downloads::Entity")]
+    Downloads,
     #[sea_orm(
         belongs_to = "super::files::Entity",
         from = "Column::IconFileId",
@@ -46,6 +55,12 @@ pub enum Relation {
         on_delete = "Cascade"
     )]
     Platforms,
+    #[sea_orm(has_many = "super::subscriptions::Entity")]
+    Subscriptions,
+    #[sea_orm(has_many = "super::update_checks::Entity")]
+    UpdateChecks,
+    #[sea_orm(has_many = "super::webhooks::Entity")]
+    Webhooks,
 }
 
 impl Related<super::app_versions::Entity> for Entity {
@@ -54,6 +69,18 @@ impl Related<super::app_versions::Entity> for Entity {
     }
 }
 
+impl Related<super::channels::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Channels.def()
+    }
+}
+
+impl Related<super::downloads::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Downloads.def()
+    }
+}
+
 impl Related<super::files::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Files.def()
@@ -65,3 +92,21 @@ impl Related<super::platforms::Entity> for Entity {
         Relation::Platforms.def()
     }
 }
+
+impl Related<super::subscriptions::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Subscriptions.def()
+    }
+}
+
+impl Related<super::update_checks::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::UpdateChecks.def()
+    }
+}
+
+impl Related<super::webhooks::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Webhooks.def()
+    }
+}
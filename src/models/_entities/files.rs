@@ -17,14 +17,53 @@ pub struct Model {
     pub checksum_sha256: String,
     #[sea_orm(column_type = "Text", nullable)]
     pub description: Option<String>,
+    pub package_name: Option<String>,
+    pub version_code: Option<i64>,
+    pub version_name: Option<String>,
+    pub min_sdk_version: Option<i32>,
+    pub target_sdk_version: Option<i32>,
+    #[sea_orm(column_type = "JsonBinary", nullable)]
+    pub permissions: Option<Json>,
+    #[sea_orm(column_type = "JsonBinary", nullable)]
+    pub signer_fingerprints: Option<Json>,
+    #[sea_orm(column_type = "JsonBinary", nullable)]
+    pub signing_lineage: Option<Json>,
+    pub label: Option<String>,
+    pub icon_file_id: Option<i32>,
+    pub artifact_type: Option<String>,
+    pub max_sdk_version: Option<i32>,
+    #[sea_orm(column_type = "JsonBinary", nullable)]
+    pub native_abis: Option<Json>,
+    #[sea_orm(column_type = "JsonBinary", nullable)]
+    pub required_features: Option<Json>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::app_version_artifacts::Entity")]
+    AppVersionArtifacts,
     #[sea_orm(has_many = "super::app_versions::Entity")]
     AppVersions,
     #[sea_orm(has_many = "super::apps::Entity")]
     Apps,
+    #[sea_orm(has_many = "super::downloads::Entity")]
+    Downloads,
+    #[sea_orm(
+        belongs_to = "Entity",
+        from = "Column::IconFileId",
+        to = "Column::Id",
+        on_update = "Cascade",
+        on_delete = "SetNull"
+    )]
+    SelfRef,
+    #[sea_orm(has_many = "super::version_patches::Entity")]
+    VersionPatches,
+}
+
+impl Related<super::app_version_artifacts::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::AppVersionArtifacts.def()
+    }
 }
 
 impl Related<super::app_versions::Entity> for Entity {
@@ -38,3 +77,15 @@ impl Related<super::apps::Entity> for Entity {
         Relation::Apps.def()
     }
 }
+
+impl Related<super::downloads::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Downloads.def()
+    }
+}
+
+impl Related<super::version_patches::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::VersionPatches.def()
+    }
+}
@@ -2,8 +2,16 @@
 
 pub mod prelude;
 
+pub mod app_version_artifacts;
 pub mod app_versions;
 pub mod apps;
+pub mod channels;
+pub mod downloads;
 pub mod files;
 pub mod platforms;
+pub mod subscriptions;
+pub mod update_checks;
 pub mod users;
+pub mod version_patches;
+pub mod webhook_deliveries;
+pub mod webhooks;
@@ -1,7 +1,15 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.9
 
+pub use super::app_version_artifacts::Entity as AppVersionArtifacts;
 pub use super::app_versions::Entity as AppVersions;
 pub use super::apps::Entity as Apps;
+pub use super::channels::Entity as Channels;
+pub use super::downloads::Entity as Downloads;
 pub use super::files::Entity as Files;
 pub use super::platforms::Entity as Platforms;
+pub use super::subscriptions::Entity as Subscriptions;
+pub use super::update_checks::Entity as UpdateChecks;
 pub use super::users::Entity as Users;
+pub use super::version_patches::Entity as VersionPatches;
+pub use super::webhook_deliveries::Entity as WebhookDeliveries;
+pub use super::webhooks::Entity as Webhooks;
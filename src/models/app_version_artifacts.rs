@@ -0,0 +1,217 @@
+use loco_rs::{Error, Result};
+use sea_orm::{entity::prelude::*, ActiveValue::Set, QueryOrder};
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+pub use super::_entities::app_version_artifacts::{ActiveModel, Column, Entity, Model};
+use super::_entities::{app_versions, apps, files, platforms};
+use super::app_versions::signer_pin;
+use crate::artifacts::{inspector, ArtifactType};
+pub type AppVersionArtifacts = Entity;
+
+/// What an artifact is to its version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ArtifactKind {
+    /// Installs on any device
+    Universal,
+    /// Built for one ABI and/or screen density
+    Split,
+    /// Archival source of the installable apks, never served to devices
+    Bundle,
+}
+
+impl ArtifactKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Universal => "universal",
+            Self::Split => "split",
+            Self::Bundle => "bundle",
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(self, _db: &C, insert: bool) -> std::result::Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        if !insert && self.updated_at.is_unchanged() {
+            let mut this = self;
+            this.updated_at = sea_orm::ActiveValue::Set(chrono::Utc::now().into());
+            Ok(this)
+        } else {
+            Ok(self)
+        }
+    }
+}
+
+// implement your read-oriented logic here
+impl Model {
+    pub fn is_installable(&self) -> bool {
+        self.kind != ArtifactKind::Bundle.as_str()
+    }
+
+    /// Position of the artifact's ABI in the device's preference list, with
+    /// ABI independent artifacts ranking after every match. `None` when the
+    /// device cannot run it.
+    fn abi_rank(&self, abis: &[&str]) -> Option<usize> {
+        match &self.abi {
+            Some(abi) => abis.iter().position(|a| a == abi),
+            None => Some(abis.len()),
+        }
+    }
+
+    /// Distance to the device density. Android scales down better than up,
+    /// so denser artifacts rank before sparser ones at the same distance.
+    fn density_rank(&self, density: Option<i32>) -> (bool, u32) {
+        match (self.density, density) {
+            (Some(own), Some(device)) if own >= device => (false, own.abs_diff(device)),
+            (Some(own), Some(device)) => (true, own.abs_diff(device)),
+            // without a device density prefer unqualified artifacts
+            (Some(_), None) => (true, 0),
+            (None, _) => (false, u32::MAX),
+        }
+    }
+
+    /// Picks the installable artifact best matching a device, given its ABIs
+    /// in order of preference and its screen density in dpi.
+    pub fn select<'a>(
+        artifacts: &'a [Self],
+        abis: &[&str],
+        density: Option<i32>,
+    ) -> Option<&'a Self> {
+        artifacts
+            .iter()
+            .filter(|a| a.is_installable())
+            .filter_map(|a| a.abi_rank(abis).map(|rank| (rank, a)))
+            .min_by_key(|(rank, a)| (*rank, a.density_rank(density)))
+            .map(|(_, a)| a)
+    }
+}
+
+// implement your write-oriented logic here
+impl ActiveModel {
+    pub async fn create<C>(db: &C, version_id: i32, data: &AddArtifact) -> Result<Model>
+    where
+        C: ConnectionTrait,
+    {
+        let version = app_versions::Entity::find_by_id(version_id)
+            .one(db)
+            .await?
+            .ok_or(Error::NotFound)?;
+        let app = apps::Entity::find_by_id(version.app_id)
+            .one(db)
+            .await?
+            .ok_or(Error::NotFound)?;
+        let platform = platforms::Entity::find_by_id(app.platform_id)
+            .one(db)
+            .await?
+            .ok_or(Error::NotFound)?;
+        let file = files::Entity::find_by_id(data.file_id)
+            .one(db)
+            .await?
+            .ok_or(Error::NotFound)?;
+        data.check(&platform, &app, &version, &file)?;
+
+        let mut item = ActiveModel {
+            ..Default::default()
+        };
+        item.app_version_id = Set(version_id);
+        data.update(&mut item);
+        Ok(item.insert(db).await?)
+    }
+}
+
+// implement your custom finders, selectors oriented logic here
+impl Entity {
+    pub async fn find_by_version<C>(db: &C, version_id: i32) -> Result<Vec<Model>>
+    where
+        C: ConnectionTrait,
+    {
+        Ok(Self::find()
+            .filter(Column::AppVersionId.eq(version_id))
+            .order_by_asc(Column::Id)
+            .all(db)
+            .await?)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Validate)]
+pub struct AddArtifact {
+    pub file_id: i32,
+    pub kind: ArtifactKind,
+    /// e.g. `arm64-v8a`, omitted for ABI independent artifacts
+    pub abi: Option<String>,
+    /// Screen density in dpi, omitted for density independent artifacts
+    pub density: Option<i32>,
+}
+
+impl AddArtifact {
+    /// Rejects a file built for another platform, app or version, or signed
+    /// by a key the app is not pinned to, and splits that name neither an ABI
+    /// nor a density.
+    pub fn check(
+        &self,
+        platform: &platforms::Model,
+        app: &apps::Model,
+        version: &app_versions::Model,
+        file: &files::Model,
+    ) -> Result<()> {
+        if let Some(artifact_type) = file.artifact_type() {
+            let accepted = inspector::for_platform(platform.code)
+                .is_some_and(|inspector| inspector.artifact_types().contains(&artifact_type));
+            if !accepted {
+                return Err(Error::BadRequest(format!(
+                    "file {} ({}) cannot be released on platform {}",
+                    file.id,
+                    artifact_type.as_str(),
+                    platform.name
+                )));
+            }
+        }
+        if self.kind == ArtifactKind::Split && self.abi.is_none() && self.density.is_none() {
+            return Err(Error::BadRequest(
+                "a split artifact needs an abi or a density".to_string(),
+            ));
+        }
+        let is_bundle = file.artifact_type() == Some(ArtifactType::Aab);
+        if is_bundle != (self.kind == ArtifactKind::Bundle) {
+            return Err(Error::BadRequest(format!(
+                "file {} cannot be a {} artifact",
+                file.id,
+                self.kind.as_str()
+            )));
+        }
+        if let Some(package_name) = &file.package_name {
+            if package_name != &app.bundle_id {
+                return Err(Error::BadRequest(format!(
+                    "artifact package {} does not match bundle_id {} of app {}",
+                    package_name, app.bundle_id, app.id
+                )));
+            }
+        }
+        if let Some(code) = file.version_code {
+            if code != version.version_code {
+                return Err(Error::BadRequest(format!(
+                    "artifact versionCode {} does not match version {}",
+                    code, version.version_code
+                )));
+            }
+        }
+        // bundles are signed with an upload key, only apks are pinned, and
+        // only the version's main apk moves the pin
+        if !is_bundle {
+            signer_pin(app, file)?;
+        }
+        Ok(())
+    }
+
+    pub fn update(&self, item: &mut ActiveModel) {
+        item.file_id = Set(self.file_id);
+        item.kind = Set(self.kind.as_str().to_string());
+        item.abi = Set(self.abi.clone());
+        item.density = Set(self.density);
+    }
+}
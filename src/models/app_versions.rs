@@ -6,14 +6,77 @@ use sea_orm::ActiveValue::Set;
 use sea_orm::TransactionTrait;
 use sea_orm::{entity::prelude::*, Condition, QueryOrder};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
 use validator::Validate;
 
 pub use super::_entities::app_versions::{ActiveModel, Entity, Model};
-use super::_entities::{app_versions, apps};
+use super::_entities::{app_versions, apps, channels, files};
 use super::apps::Apps;
+use super::files::Files;
+use super::platforms::Platforms;
+use super::webhook_deliveries;
+use super::webhooks::Event;
 use super::{_entities::app_versions::Column, common::ToCondition};
+use crate::artifacts::inspector;
+use crate::targeting::{Attributes, Rule};
 pub type AppVersions = Entity;
 
+/// Progress of a staged rollout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RolloutState {
+    /// Offered to the devices whose bucket is below the rollout percentage
+    Active,
+    /// Offered to no further device until resumed
+    Paused,
+    /// Stopped for good, devices stay on the current version
+    Halted,
+    /// Offered to every device, the version is the current one of its app
+    Completed,
+}
+
+impl RolloutState {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Active => "active",
+            Self::Paused => "paused",
+            Self::Halted => "halted",
+            Self::Completed => "completed",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        [Self::Active, Self::Paused, Self::Halted, Self::Completed]
+            .into_iter()
+            .find(|state| state.as_str() == s)
+    }
+}
+
+/// Bucket in `0..100` a device falls into for the rollout of a version. It
+/// only depends on the two ids, so a device keeps its place while the
+/// rollout is raised, and different rollouts reach different devices first.
+pub fn rollout_bucket(version_id: i32, device_id: &str) -> i16 {
+    let digest = Sha256::digest(format!("{version_id}:{device_id}"));
+    let mut prefix = [0; 8];
+    prefix.copy_from_slice(&digest[..8]);
+    // always below 100
+    #[allow(clippy::cast_possible_truncation)]
+    let bucket = (u64::from_be_bytes(prefix) % 100) as i16;
+    bucket
+}
+
+/// The rollout percentage a version is published with, 100 when omitted.
+fn rollout_percentage(percentage: Option<i16>) -> Result<i16> {
+    let percentage = percentage.unwrap_or(100);
+    if !(1..=100).contains(&percentage) {
+        return Err(Error::BadRequest(format!(
+            "rollout percentage {percentage} is not within 1 to 100"
+        )));
+    }
+    Ok(percentage)
+}
+
 #[async_trait::async_trait]
 impl ActiveModelBehavior for ActiveModel {
     async fn before_save<C>(self, _db: &C, insert: bool) -> std::result::Result<Self, DbErr>
@@ -45,24 +108,71 @@ impl Model {
         )
         .await
     }
+
+    /// Whether devices must leave this version: it was rolled back or
+    /// yanked.
+    pub fn is_pulled(&self) -> bool {
+        self.rolled_back_at.is_some() || self.yanked_at.is_some()
+    }
+
+    pub fn rollout_state(&self) -> Option<RolloutState> {
+        self.rollout_state.as_deref().and_then(RolloutState::parse)
+    }
+
+    /// Whether the targeting rule of the version lets a device in.
+    pub fn is_targeted_at(&self, attributes: &Attributes) -> bool {
+        Rule::admits(self.targeting.as_ref(), attributes)
+    }
 }
 
 // implement your write-oriented logic here
 impl ActiveModel {
-    pub async fn create(db: &DatabaseConnection, data: &CreateAppVersion) -> Result<Model> {
-        // check if bundle_id already exists in db
-        if Entity::find()
-            .filter(Column::AppId.eq(data.app_id))
-            .filter(Column::VersionName.eq(&data.version_name))
-            .filter(Column::VersionCode.eq(&data.version_code))
+    pub async fn create<C>(db: &C, data: &CreateAppVersion) -> Result<Model>
+    where
+        C: ConnectionTrait + TransactionTrait,
+    {
+        let app = Apps::find_by_id(data.app_id)
             .one(db)
             .await?
-            .is_some()
-        {
-            return Err(loco_rs::Error::BadRequest(format!(
-                "app {} with version {} and build number {} already exists!",
-                data.app_id, data.version_name, data.version_code
-            )));
+            .ok_or(Error::NotFound)?;
+        let file = Files::find_by_id(data.apk_file_id)
+            .one(db)
+            .await?
+            .ok_or(Error::NotFound)?;
+        if let Some(artifact_type) = file.artifact_type() {
+            let platform = Platforms::find_by_id(app.platform_id)
+                .one(db)
+                .await?
+                .ok_or(Error::NotFound)?;
+            let inspector = inspector::for_platform(platform.code)
+                .filter(|inspector| inspector.is_installable(artifact_type))
+                .ok_or_else(|| {
+                    Error::BadRequest(format!(
+                        "file {} ({}) cannot be released on platform {}",
+                        file.id,
+                        artifact_type.as_str(),
+                        platform.name
+                    ))
+                })?;
+            inspector.validate(&app, &file)?;
+        }
+        let data = &data.resolve(&file)?;
+        let pin = signer_pin(&app, &file)?;
+        let (Some(version_code), Some(_)) = (data.version_code, &data.version_name) else {
+            return Err(Error::BadRequest(
+                "version_code and version_name are required when the file carries no manifest"
+                    .to_string(),
+            ));
+        };
+
+        // version codes order the versions of an app, they only go up
+        if let Some(latest) = Entity::find_latest(db, data.app_id).await? {
+            if version_code <= latest.version_code {
+                return Err(loco_rs::Error::BadRequest(format!(
+                    "version_code {} of app {} must be greater than {} of version {}",
+                    version_code, data.app_id, latest.version_code, latest.id
+                )));
+            }
         }
 
         let mut item = ActiveModel {
@@ -72,40 +182,346 @@ impl ActiveModel {
 
         let tx = db.begin().await?;
         let version = item.insert(&tx).await?;
+        if version.published_at.is_some() {
+            Self::halt_rollouts(&tx, version.app_id, version.id).await?;
+        }
+
+        if let Some(fingerprints) = pin {
+            let mut app: apps::ActiveModel = app.into();
+            app.signer_fingerprints = Set(Some(serde_json::json!(fingerprints)));
+            app.update(&tx).await?;
+        }
 
         // If publish_immediately is true, update the app's current version ID
         if data.publish_immediately.unwrap_or(false) {
             Self::update_current_app_version_id(&tx, version.app_id, Some(version.id)).await?
         }
 
+        Self::notify(&tx, Event::VersionCreated, &version).await?;
+        if version.published_at.is_some() {
+            Self::notify(&tx, Event::VersionPublished, &version).await?;
+        }
         tx.commit().await?;
         Ok(version)
     }
 
-    pub async fn publish(db: &DatabaseConnection, id: i32, publish: bool) -> Result<()> {
+    /// Publishes a version to `rollout_percentage` of the devices, all of
+    /// them by default, or withdraws it. A version only becomes the current
+    /// one of its app once it reaches every device, and publishing ends any
+    /// other rollout of the app.
+    pub async fn publish(db: &DatabaseConnection, id: i32, payload: &PublishPayload) -> Result<()> {
         let version = Entity::find_by_id(id)
             .one(db)
             .await?
             .ok_or(Error::NotFound)?;
+        if payload.publish && version.yanked_at.is_some() {
+            return Err(Error::BadRequest(format!(
+                "version {id} was yanked and cannot be published again"
+            )));
+        }
         let app_id = version.app_id; // Store app_id before converting to ActiveModel
+        let was_published = version.published_at.is_some();
         let mut version: app_versions::ActiveModel = version.into();
 
         // start a transaction
         let tx = db.begin().await?;
 
-        if publish {
+        if payload.publish {
+            let percentage = rollout_percentage(payload.rollout_percentage)?;
+            Self::halt_rollouts(&tx, app_id, id).await?;
+            let state = if percentage == 100 {
+                RolloutState::Completed
+            } else {
+                RolloutState::Active
+            };
             version.published_at = Set(Some(Utc::now().fixed_offset()));
-            version.update(&tx).await?;
-            Self::update_current_app_version_id(&tx, app_id, Some(id)).await?
+            version.rolled_back_at = Set(None);
+            version.rollback_reason = Set(None);
+            version.rollout_percentage = Set(percentage);
+            version.rollout_state = Set(Some(state.as_str().to_string()));
+            version.scheduled_publish_at = Set(None);
+            version.scheduled_rollout_percentage = Set(None);
+            let version = version.update(&tx).await?;
+            if state == RolloutState::Completed {
+                Self::update_current_app_version_id(&tx, app_id, Some(id)).await?
+            }
+            Self::notify(&tx, Event::VersionPublished, &version).await?;
         } else {
             version.published_at = Set(None);
-            version.update(&tx).await?;
-            Self::update_current_app_version_id(&tx, app_id, None).await?
+            version.rollout_state = Set(None);
+            let version = version.update(&tx).await?;
+            if was_published {
+                Self::notify(&tx, Event::VersionUnpublished, &version).await?;
+            }
+            let app = Apps::find_by_id(app_id)
+                .one(&tx)
+                .await?
+                .ok_or(Error::NotFound)?;
+            if app.current_version_id == Some(id) {
+                Self::update_current_app_version_id(&tx, app_id, None).await?
+            }
         }
 
         Ok(tx.commit().await?)
     }
 
+    /// Pulls a published version: it is unpublished with the reason recorded,
+    /// and the app and any channel on it fall back to the most recent older
+    /// version that is still fully published. Returns that version.
+    pub async fn rollback(
+        db: &DatabaseConnection,
+        id: i32,
+        payload: &RollbackPayload,
+    ) -> Result<Option<Model>> {
+        let version = Entity::find_by_id(id)
+            .one(db)
+            .await?
+            .ok_or(Error::NotFound)?;
+        if version.published_at.is_none() {
+            return Err(Error::BadRequest(format!("version {id} is not published")));
+        }
+        Self::withdraw(db, version, |version| {
+            version.rolled_back_at = Set(Some(Utc::now().fixed_offset()));
+            version.rollback_reason = Set(Some(payload.reason.clone()));
+        })
+        .await
+    }
+
+    /// Yanks a version: it is withdrawn like a rollback and never served
+    /// again, and devices running it are told to replace it, downgrading if
+    /// need be. Returns the version the app falls back to.
+    pub async fn yank(
+        db: &DatabaseConnection,
+        id: i32,
+        payload: &YankPayload,
+    ) -> Result<Option<Model>> {
+        let version = Entity::find_by_id(id)
+            .one(db)
+            .await?
+            .ok_or(Error::NotFound)?;
+        if version.yanked_at.is_some() {
+            return Err(Error::BadRequest(format!("version {id} is already yanked")));
+        }
+        Self::withdraw(db, version, |version| {
+            version.yanked_at = Set(Some(Utc::now().fixed_offset()));
+            version.yank_reason = Set(Some(payload.reason.clone()));
+            version.yank_forced = Set(payload.force);
+        })
+        .await
+    }
+
+    /// Unpublishes a version, marked by `mark`, and moves the app and the
+    /// channels on it to the rollback target, which is returned.
+    async fn withdraw(
+        db: &DatabaseConnection,
+        version: Model,
+        mark: impl FnOnce(&mut ActiveModel),
+    ) -> Result<Option<Model>> {
+        let id = version.id;
+        let target = Entity::find_rollback_target(db, &version).await?;
+        let app = Apps::find_by_id(version.app_id)
+            .one(db)
+            .await?
+            .ok_or(Error::NotFound)?;
+        let target_id = target.as_ref().map(|target| target.id);
+        let rolling_out = matches!(
+            version.rollout_state(),
+            Some(RolloutState::Active | RolloutState::Paused)
+        );
+        let mut version: app_versions::ActiveModel = version.into();
+        version.published_at = Set(None);
+        // a withdrawn version is not published again behind the caller's back
+        version.scheduled_publish_at = Set(None);
+        version.scheduled_rollout_percentage = Set(None);
+        if rolling_out {
+            version.rollout_state = Set(Some(RolloutState::Halted.as_str().to_string()));
+        }
+        mark(&mut version);
+
+        let tx = db.begin().await?;
+        let version = version.update(&tx).await?;
+        Self::notify(&tx, Event::VersionUnpublished, &version).await?;
+        if app.current_version_id == Some(id) {
+            Self::update_current_app_version_id(&tx, app.id, target_id).await?;
+        }
+        channels::Entity::update_many()
+            .col_expr(channels::Column::CurrentVersionId, Expr::value(target_id))
+            .filter(channels::Column::CurrentVersionId.eq(id))
+            .exec(&tx)
+            .await?;
+        tx.commit().await?;
+        Ok(target)
+    }
+
+    /// Publishes an unpublished version at `payload.publish_at`, replacing
+    /// any earlier schedule. The publisher worker publishes it once due.
+    pub async fn schedule(
+        db: &DatabaseConnection,
+        id: i32,
+        payload: &SchedulePayload,
+    ) -> Result<Model> {
+        let version = Entity::find_by_id(id)
+            .one(db)
+            .await?
+            .ok_or(Error::NotFound)?;
+        if version.published_at.is_some() {
+            return Err(Error::BadRequest(format!(
+                "version {id} is already published"
+            )));
+        }
+        if version.yanked_at.is_some() {
+            return Err(Error::BadRequest(format!(
+                "version {id} was yanked and cannot be published again"
+            )));
+        }
+        if payload.publish_at <= Utc::now() {
+            return Err(Error::BadRequest(format!(
+                "publish time {} is not in the future",
+                payload.publish_at
+            )));
+        }
+        rollout_percentage(payload.rollout_percentage)?;
+
+        let mut version: ActiveModel = version.into();
+        version.scheduled_publish_at = Set(Some(payload.publish_at));
+        version.scheduled_rollout_percentage = Set(payload.rollout_percentage);
+        Ok(version.update(db).await?)
+    }
+
+    /// Cancels the scheduled publication of a version.
+    pub async fn unschedule(db: &DatabaseConnection, id: i32) -> Result<Model> {
+        let version = Entity::find_by_id(id)
+            .one(db)
+            .await?
+            .ok_or(Error::NotFound)?;
+        if version.scheduled_publish_at.is_none() {
+            return Err(Error::BadRequest(format!(
+                "version {id} is not scheduled for publication"
+            )));
+        }
+        let mut version: ActiveModel = version.into();
+        version.scheduled_publish_at = Set(None);
+        version.scheduled_rollout_percentage = Set(None);
+        Ok(version.update(db).await?)
+    }
+
+    /// Takes a due version for publication by clearing its schedule, as long
+    /// as nobody changed it since `version` was read. `false` when another
+    /// run got to it first or the schedule was changed or cancelled.
+    pub async fn claim_scheduled<C>(db: &C, version: &Model) -> Result<bool>
+    where
+        C: ConnectionTrait,
+    {
+        let res = Entity::update_many()
+            .col_expr(
+                Column::ScheduledPublishAt,
+                Expr::value(Option::<DateTimeWithTimeZone>::None),
+            )
+            .filter(Column::Id.eq(version.id))
+            .filter(Column::ScheduledPublishAt.eq(version.scheduled_publish_at))
+            .filter(Column::PublishedAt.is_null())
+            .exec(db)
+            .await?;
+        Ok(res.rows_affected == 1)
+    }
+
+    /// Raises, pauses, resumes or halts the rollout of a version. Raising it
+    /// to 100% completes it and makes the version the current one.
+    pub async fn update_rollout(
+        db: &DatabaseConnection,
+        id: i32,
+        payload: &RolloutPayload,
+    ) -> Result<Model> {
+        let version = Entity::find_by_id(id)
+            .one(db)
+            .await?
+            .ok_or(Error::NotFound)?;
+        let current = version.rollout_state();
+        if !matches!(current, Some(RolloutState::Active | RolloutState::Paused)) {
+            return Err(Error::BadRequest(format!(
+                "version {id} is not rolling out"
+            )));
+        }
+        let mut state = payload.state.or(current).unwrap_or(RolloutState::Active);
+        if state == RolloutState::Completed {
+            return Err(Error::BadRequest(
+                "raise the rollout percentage to 100 to complete it".to_string(),
+            ));
+        }
+        let percentage = payload.percentage.unwrap_or(version.rollout_percentage);
+        if percentage != version.rollout_percentage {
+            if state != RolloutState::Active {
+                return Err(Error::BadRequest(format!(
+                    "rollout of version {id} is {}, resume it before raising it",
+                    state.as_str()
+                )));
+            }
+            if !(version.rollout_percentage..=100).contains(&percentage) {
+                return Err(Error::BadRequest(format!(
+                    "rollout of version {id} can only be raised from {}% up to 100%",
+                    version.rollout_percentage
+                )));
+            }
+        }
+        if state == RolloutState::Active && percentage == 100 {
+            state = RolloutState::Completed;
+        }
+
+        let app_id = version.app_id;
+        let mut version: app_versions::ActiveModel = version.into();
+        version.rollout_percentage = Set(percentage);
+        version.rollout_state = Set(Some(state.as_str().to_string()));
+
+        let tx = db.begin().await?;
+        let version = version.update(&tx).await?;
+        if state == RolloutState::Completed {
+            Self::update_current_app_version_id(&tx, app_id, Some(id)).await?
+        }
+        tx.commit().await?;
+        Ok(version)
+    }
+
+    /// Deletes a version, announcing it to webhooks.
+    pub async fn delete_version(db: &DatabaseConnection, version: Model) -> Result<()> {
+        let tx = db.begin().await?;
+        Self::notify(&tx, Event::VersionDeleted, &version).await?;
+        version.delete(&tx).await?;
+        Ok(tx.commit().await?)
+    }
+
+    /// Queues delivery of `event` about `version` to the webhooks
+    /// subscribed to it, in the transaction making the change.
+    pub async fn notify<C>(db: &C, event: Event, version: &Model) -> Result<()>
+    where
+        C: ConnectionTrait,
+    {
+        let data = serde_json::json!({ "version": version });
+        webhook_deliveries::ActiveModel::dispatch(db, event, version.app_id, data).await?;
+        Ok(())
+    }
+
+    /// Halts the unfinished rollouts of an app other than the one of
+    /// `version_id`.
+    async fn halt_rollouts<C>(db: &C, app_id: i32, version_id: i32) -> Result<()>
+    where
+        C: ConnectionTrait,
+    {
+        Entity::update_many()
+            .col_expr(
+                Column::RolloutState,
+                Expr::value(RolloutState::Halted.as_str()),
+            )
+            .filter(Column::AppId.eq(app_id))
+            .filter(Column::Id.ne(version_id))
+            .filter(
+                Column::RolloutState
+                    .is_in([RolloutState::Active.as_str(), RolloutState::Paused.as_str()]),
+            )
+            .exec(db)
+            .await?;
+        Ok(())
+    }
+
     pub async fn update_current_app_version_id<C>(
         db: &C,
         app_id: i32,
@@ -126,13 +542,175 @@ impl ActiveModel {
     }
 }
 
+/// Checks the apk signers against the fingerprints pinned on the app and
+/// returns the fingerprints the app should be pinned to from now on, if they
+/// change.
+///
+/// The first signed version pins its signers. Later versions must be signed
+/// by the pinned certificates, or by a rotated key whose v3 lineage contains
+/// one of them, in which case the pin moves to the new signers.
+pub(crate) fn signer_pin(app: &apps::Model, file: &files::Model) -> Result<Option<Vec<String>>> {
+    let fingerprints = |json: &Option<serde_json::Value>| -> Vec<String> {
+        json.clone()
+            .and_then(|v| serde_json::from_value(v).ok())
+            .unwrap_or_default()
+    };
+    let pinned = fingerprints(&app.signer_fingerprints);
+    let signers = fingerprints(&file.signer_fingerprints);
+
+    if pinned.is_empty() {
+        return Ok((!signers.is_empty()).then_some(signers));
+    }
+    if signers.is_empty() {
+        return Err(Error::BadRequest(format!(
+            "app {} only accepts apks signed by its pinned certificates",
+            app.id
+        )));
+    }
+    if signers.iter().all(|s| pinned.contains(s)) {
+        return Ok(None);
+    }
+    let lineage = fingerprints(&file.signing_lineage);
+    if lineage.iter().any(|l| pinned.contains(l)) {
+        return Ok(Some(signers));
+    }
+    Err(Error::BadRequest(format!(
+        "apk signer {} does not match the certificates pinned on app {}",
+        signers.join(", "),
+        app.id
+    )))
+}
+
 // implement your custom finders, selectors oriented logic here
-impl Entity {}
+impl Entity {
+    /// The version of an app with the highest version code.
+    pub async fn find_latest<C>(db: &C, app_id: i32) -> Result<Option<Model>>
+    where
+        C: ConnectionTrait,
+    {
+        Ok(Self::find()
+            .filter(Column::AppId.eq(app_id))
+            .order_by_desc(Column::VersionCode)
+            .one(db)
+            .await?)
+    }
+
+    /// The most recent version older than `version` that is published to
+    /// every device, what a rollback of `version` falls back to.
+    pub async fn find_rollback_target<C>(db: &C, version: &Model) -> Result<Option<Model>>
+    where
+        C: ConnectionTrait,
+    {
+        Ok(Self::find_releases_before(version).one(db).await?)
+    }
+
+    /// The most recent version older than `version` that is published to
+    /// every device and targets a device with `attributes`, what the device
+    /// is offered when `version` does not target it.
+    pub async fn find_targeted_release<C>(
+        db: &C,
+        version: &Model,
+        attributes: &Attributes,
+    ) -> Result<Option<Model>>
+    where
+        C: ConnectionTrait,
+    {
+        Ok(Self::find_targeted_releases(db, version, attributes)
+            .await?
+            .into_iter()
+            .next())
+    }
+
+    /// Versions older than `version` published to every device and
+    /// targeting a device with `attributes`, newest first.
+    pub async fn find_targeted_releases<C>(
+        db: &C,
+        version: &Model,
+        attributes: &Attributes,
+    ) -> Result<Vec<Model>>
+    where
+        C: ConnectionTrait,
+    {
+        Ok(Self::find_releases_before(version)
+            .all(db)
+            .await?
+            .into_iter()
+            .filter(|release| release.is_targeted_at(attributes))
+            .collect())
+    }
+
+    /// Versions older than `version` published to every device, newest first.
+    fn find_releases_before(version: &Model) -> Select<Self> {
+        Self::find()
+            .filter(Column::AppId.eq(version.app_id))
+            .filter(Column::VersionCode.lt(version.version_code))
+            .filter(Column::PublishedAt.is_not_null())
+            .filter(
+                Condition::any()
+                    .add(Column::RolloutState.is_null())
+                    .add(Column::RolloutState.eq(RolloutState::Completed.as_str())),
+            )
+            .order_by_desc(Column::VersionCode)
+    }
+
+    /// Versions whose main file is `file_id`.
+    pub async fn find_by_apk_file<C>(db: &C, file_id: i32) -> Result<Vec<Model>>
+    where
+        C: ConnectionTrait,
+    {
+        Ok(Self::find()
+            .filter(Column::ApkFileId.eq(file_id))
+            .all(db)
+            .await?)
+    }
+
+    /// The version of an app with a version code, what a device reporting
+    /// it as its build number runs.
+    pub async fn find_by_code<C>(db: &C, app_id: i32, version_code: i64) -> Result<Option<Model>>
+    where
+        C: ConnectionTrait,
+    {
+        Ok(Self::find()
+            .filter(Column::AppId.eq(app_id))
+            .filter(Column::VersionCode.eq(version_code))
+            .one(db)
+            .await?)
+    }
+
+    /// Unpublished versions whose scheduled publish time has come, earliest
+    /// first. Yanked versions are never due.
+    pub async fn find_due<C>(db: &C, now: DateTimeWithTimeZone) -> Result<Vec<Model>>
+    where
+        C: ConnectionTrait,
+    {
+        Ok(Self::find()
+            .filter(Column::ScheduledPublishAt.lte(now))
+            .filter(Column::PublishedAt.is_null())
+            .filter(Column::YankedAt.is_null())
+            .order_by_asc(Column::ScheduledPublishAt)
+            .all(db)
+            .await?)
+    }
+
+    /// The version of an app whose rollout is in progress, if any.
+    pub async fn find_rolling_out<C>(db: &C, app_id: i32) -> Result<Option<Model>>
+    where
+        C: ConnectionTrait,
+    {
+        Ok(Self::find()
+            .filter(Column::AppId.eq(app_id))
+            .filter(Column::PublishedAt.is_not_null())
+            .filter(Column::RolloutState.eq(RolloutState::Active.as_str()))
+            .order_by_desc(Column::PublishedAt)
+            .one(db)
+            .await?)
+    }
+}
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct AppVersionQuery {
     pub version_name: Option<String>,
-    pub version_code: Option<String>,
+    pub version_code: Option<i64>,
     #[serde(flatten)]
     pub pagination: PaginationQuery,
 }
@@ -140,11 +718,11 @@ pub struct AppVersionQuery {
 impl ToCondition for AppVersionQuery {
     fn to_condition(&self) -> Condition {
         query::condition()
-            .tap_if_some(&self.version_code, |c, name| {
+            .tap_if_some(&self.version_name, |c, name| {
                 c.contains(Column::VersionName, name)
             })
             .tap_if_some(&self.version_code, |c, code| {
-                c.contains(Column::VersionCode, code)
+                c.eq(Column::VersionCode, *code)
             })
             .build()
     }
@@ -153,49 +731,152 @@ impl ToCondition for AppVersionQuery {
 #[derive(Clone, Debug, Serialize, Deserialize, Validate)]
 pub struct CreateAppVersion {
     pub app_id: i32,
-    pub version_code: String,
-    pub version_name: String,
+    /// Taken from the apk manifest when omitted, greater than the code of
+    /// every other version of the app
+    pub version_code: Option<i64>,
+    /// Taken from the apk manifest when omitted
+    pub version_name: Option<String>,
     pub release_notes: Option<String>,
+    /// Translations of `release_notes` by locale, e.g. `zh-CN`
+    pub localized_release_notes: Option<BTreeMap<String, String>>,
     pub apk_file_id: i32,
     pub publish_immediately: Option<bool>,
+    /// Devices below this version have to update to it
+    pub mandatory: Option<bool>,
+    /// Builds below this version code have to update once this version is out
+    pub min_supported_version_code: Option<i64>,
+    /// Devices the version is offered to, every device when omitted
+    pub targeting: Option<Rule>,
 }
 
 impl CreateAppVersion {
+    /// Fills the version fields from the metadata stored on the artifact and
+    /// rejects a payload that disagrees with it.
+    pub fn resolve(&self, file: &files::Model) -> Result<Self> {
+        let mut data = self.clone();
+        if let Some(code) = file.version_code {
+            match data.version_code {
+                Some(given) if given != code => {
+                    return Err(Error::BadRequest(format!(
+                        "version_code {given} does not match versionCode {code} of the apk"
+                    )));
+                }
+                _ => data.version_code = Some(code),
+            }
+        }
+        if let Some(name) = &file.version_name {
+            match &data.version_name {
+                Some(given) if given != name => {
+                    return Err(Error::BadRequest(format!(
+                        "version_name {given} does not match versionName {name} of the apk"
+                    )));
+                }
+                _ => data.version_name = Some(name.clone()),
+            }
+        }
+        Ok(data)
+    }
+
     pub fn update(&self, item: &mut ActiveModel) {
         item.app_id = Set(self.app_id);
-        item.version_code = Set(self.version_code.clone());
-        item.version_name = Set(self.version_name.clone());
+        self.version_code
+            .inspect(|&code| item.version_code = Set(code));
+        self.version_name
+            .as_ref()
+            .inspect(|&name| item.version_name = Set(name.clone()));
         item.release_notes = Set(self.release_notes.clone());
-        item.apk_file_id = Set(Some(self.apk_file_id.clone()));
+        item.localized_release_notes = Set(self
+            .localized_release_notes
+            .as_ref()
+            .map(|texts| serde_json::json!(texts)));
+        item.apk_file_id = Set(Some(self.apk_file_id));
+        item.mandatory = Set(self.mandatory.unwrap_or(false));
+        item.min_supported_version_code = Set(self.min_supported_version_code);
+        item.targeting = Set(self.targeting.as_ref().map(|rule| serde_json::json!(rule)));
         item.published_at = Set(self
             .publish_immediately
             .filter(|&is_pub| is_pub)
             .map(|_| Utc::now().fixed_offset()));
+        item.rollout_state = Set(self
+            .publish_immediately
+            .filter(|&is_pub| is_pub)
+            .map(|_| RolloutState::Completed.as_str().to_string()));
     }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, Validate)]
 pub struct PatchAppVersion {
-    pub version_code: Option<String>,
+    /// Only accepted when unchanged, version codes order the versions of an
+    /// app and are fixed once created
+    pub version_code: Option<i64>,
     pub version_name: Option<String>,
     pub release_notes: Option<String>,
+    pub localized_release_notes: Option<BTreeMap<String, String>>,
+    pub mandatory: Option<bool>,
+    pub min_supported_version_code: Option<i64>,
+    /// Replaces the targeting rule, `{"all": []}` offers the version to
+    /// every device again
+    pub targeting: Option<Rule>,
 }
 
 impl PatchAppVersion {
     pub fn update(&self, item: &mut ActiveModel) {
-        self.version_code
-            .as_ref()
-            .inspect(|&code| item.version_code = Set(code.clone()));
         self.version_name
             .as_ref()
             .inspect(|&name| item.version_name = Set(name.clone()));
         self.release_notes
             .as_ref()
             .inspect(|&notes| item.release_notes = Set(Some(notes.clone())));
+        self.localized_release_notes
+            .as_ref()
+            .inspect(|&texts| item.localized_release_notes = Set(Some(serde_json::json!(texts))));
+        self.mandatory
+            .inspect(|&mandatory| item.mandatory = Set(mandatory));
+        self.min_supported_version_code
+            .inspect(|&code| item.min_supported_version_code = Set(Some(code)));
+        self.targeting
+            .as_ref()
+            .inspect(|&rule| item.targeting = Set(Some(serde_json::json!(rule))));
     }
 }
 
 #[derive(Debug, Deserialize)]
 pub struct PublishPayload {
     pub publish: bool,
+    /// Share of devices offered the version, 100 when omitted
+    pub rollout_percentage: Option<i16>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SchedulePayload {
+    /// When to publish, with the offset of the time zone it is meant in,
+    /// e.g. `2025-06-02T09:00:00+08:00`
+    pub publish_at: DateTimeWithTimeZone,
+    /// Share of devices offered the version once published, 100 when omitted
+    pub rollout_percentage: Option<i16>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct RollbackPayload {
+    /// Why the version was pulled, kept on the version
+    #[validate(length(min = 1))]
+    pub reason: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct YankPayload {
+    /// Why the version was yanked, shown to the devices running it
+    #[validate(length(min = 1))]
+    pub reason: String,
+    /// Whether devices running it are forced to replace it
+    #[serde(default)]
+    pub force: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RolloutPayload {
+    /// New rollout percentage, it can only be raised
+    pub percentage: Option<i16>,
+    /// `active` to resume, `paused` or `halted`
+    pub state: Option<RolloutState>,
 }
@@ -1,11 +1,24 @@
-use crate::utils::ConditionBuilderExt;
+use crate::{
+    locales::Locales,
+    targeting::{Attributes, Rule, Trace},
+    utils::ConditionBuilderExt,
+};
 use loco_rs::{model::query::paginate, Error, Result};
+use std::collections::BTreeMap;
 use validator::Validate;
 
 pub use super::_entities::apps::{ActiveModel, Entity, Model};
-use super::{_entities::apps, app_versions, common::ToCondition, files};
+use super::{
+    _entities::{app_versions as version_entity, apps},
+    app_version_artifacts::{self, AppVersionArtifacts},
+    app_versions,
+    channels::{Channels, STABLE},
+    common::ToCondition,
+    files,
+    version_patches::VersionPatches,
+};
 use loco_rs::model::query::{self, PageResponse, PaginationQuery};
-use sea_orm::{entity::prelude::*, ActiveValue::Set, Condition, QueryOrder};
+use sea_orm::{entity::prelude::*, ActiveValue::Set, Condition, QueryOrder, QuerySelect};
 use serde::{Deserialize, Serialize};
 pub type Apps = Entity;
 
@@ -38,10 +51,21 @@ impl Model {
         .await
     }
 
+    /// The app with its description in the first of `locales` it is
+    /// translated to.
+    pub fn localize(mut self, locales: &Locales) -> Self {
+        if let Some(description) = locales.pick(self.localized_descriptions.as_ref()) {
+            self.description = Some(description.to_string());
+        }
+        self
+    }
+
     pub async fn check_update(
         db: &DatabaseConnection,
         id: i32,
         revision: &Revision,
+        attributes: &Attributes,
+        locales: &Locales,
     ) -> Result<UpdateInfo> {
         // find current app version id
         let app = Entity::find_by_id(id)
@@ -50,38 +74,288 @@ impl Model {
             .await?
             .ok_or(Error::NotFound)?;
 
+        // devices inside a staged rollout get its version, the others the current one
+        let staged = app_versions::Entity::find_rolling_out(db, id)
+            .await?
+            .filter(|version| {
+                revision.is_in_rollout(version) && version.is_targeted_at(attributes)
+            });
+        let stable = staged.or(app.1);
+        let version = match revision.channel.as_deref() {
+            None | Some(STABLE) => stable,
+            Some(name) => newest(
+                Self::channel_version(db, id, name, attributes).await?,
+                stable,
+            ),
+        };
+        // devices a version does not target get the newest release that does
+        let version = match version {
+            Some(version) if !version.is_targeted_at(attributes) => {
+                app_versions::Entity::find_targeted_release(db, &version, attributes).await?
+            }
+            version => version,
+        };
         // if version is not found which means there is no current version we just return no updates
-        if app.1.is_none() {
+        let Some(version) = version else {
             return Ok(UpdateInfo::default());
-        }
-        let version = app.1.unwrap();
-        if revision.version_name != version.version_name
-            || revision.build_number.to_string() != version.version_code
-        {
-            let file = version
-                .find_related(files::Entity)
-                .one(db)
-                .await?
-                .ok_or(Error::NotFound)?;
+        };
+        let Some((version, file, has_artifacts)) =
+            Self::installable_version(db, version, revision, attributes).await?
+        else {
+            return Ok(UpdateInfo::default());
+        };
+        // devices on a pulled version go back to the version it was pulled for
+        let running = app_versions::Entity::find_by_code(db, id, revision.build_number).await?;
+        let pulled = running.as_ref().is_some_and(app_versions::Model::is_pulled);
+        let reinstall = pulled && version.version_code < revision.build_number;
+        if version.version_code > revision.build_number || reinstall {
+            // patches are made between the main files of versions
+            let patch = if has_artifacts {
+                None
+            } else {
+                Self::find_patch(db, &version, running.as_ref()).await?
+            };
+
+            let yanked = running.filter(|running| running.yanked_at.is_some());
+            let force_update = yanked.as_ref().is_some_and(|yanked| yanked.yank_forced)
+                || revision.must_update(
+                    app.0
+                        .min_supported_version_code
+                        .max(version.min_supported_version_code),
+                    version.version_code,
+                    &Self::mandatory_codes(db, &version).await?,
+                );
 
             return Ok(UpdateInfo::new(
                 true,
+                force_update,
+                reinstall,
+                yanked.and_then(|yanked| yanked.yank_reason),
                 Some(LatestVersionInfo {
+                    changelog: Self::changelog(db, &version, revision, locales).await?,
                     id: version.id,
                     name: version.version_name,
                     build_number: version.version_code,
                     file_url: file.path,
+                    size: file.size_bytes,
+                    checksum: file.checksum_sha256,
+                    patch,
                 }),
             ));
         }
 
         Ok(UpdateInfo::default())
     }
+
+    /// The newest of `version` and the releases before it targeting the
+    /// device that it can install, with the file it installs and whether
+    /// that file is one of the version's artifacts.
+    async fn installable_version(
+        db: &DatabaseConnection,
+        version: app_versions::Model,
+        revision: &Revision,
+        attributes: &Attributes,
+    ) -> Result<Option<(app_versions::Model, files::Model, bool)>> {
+        if let Some((file, has_artifacts)) = Self::installable_file(db, &version, revision).await? {
+            return Ok(Some((version, file, has_artifacts)));
+        }
+        for older in app_versions::Entity::find_targeted_releases(db, &version, attributes).await? {
+            if let Some((file, has_artifacts)) =
+                Self::installable_file(db, &older, revision).await?
+            {
+                return Ok(Some((older, file, has_artifacts)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// The file of `version` the device would install, the artifact best
+    /// matching it when the version has some, `None` when it cannot install
+    /// any.
+    async fn installable_file(
+        db: &DatabaseConnection,
+        version: &app_versions::Model,
+        revision: &Revision,
+    ) -> Result<Option<(files::Model, bool)>> {
+        let artifacts = AppVersionArtifacts::find_by_version(db, version.id).await?;
+        let has_artifacts = artifacts
+            .iter()
+            .any(app_version_artifacts::Model::is_installable);
+        let file_id = if has_artifacts {
+            match app_version_artifacts::Model::select(
+                &artifacts,
+                &revision.abis(),
+                revision.density,
+            ) {
+                Some(artifact) => artifact.file_id,
+                None => return Ok(None),
+            }
+        } else {
+            version.apk_file_id.ok_or(Error::NotFound)?
+        };
+        let file = files::Entity::find_by_id(file_id)
+            .one(db)
+            .await?
+            .ok_or(Error::NotFound)?;
+        Ok(revision.can_install(&file).then_some((file, has_artifacts)))
+    }
+
+    /// Evaluates the targeting rules of every version and channel of an app
+    /// for a sample device, and what it would be offered from `revision`.
+    pub async fn dry_run(
+        db: &DatabaseConnection,
+        id: i32,
+        payload: &DryRunPayload,
+    ) -> Result<DryRun> {
+        Entity::find_by_id(id)
+            .one(db)
+            .await?
+            .ok_or(Error::NotFound)?;
+        let attributes = &payload.attributes;
+        let versions = app_versions::Entity::find()
+            .filter(version_entity::Column::AppId.eq(id))
+            .order_by_desc(version_entity::Column::VersionCode)
+            .all(db)
+            .await?;
+        let channels = Channels::find_by_app(db, id).await?;
+        let update = match &payload.revision {
+            Some(revision) => {
+                Some(Self::check_update(db, id, revision, attributes, &Locales::default()).await?)
+            }
+            None => None,
+        };
+        Ok(DryRun {
+            versions: versions
+                .into_iter()
+                .map(|v| {
+                    TargetingResult::new(v.id, v.version_name, v.targeting.as_ref(), attributes)
+                })
+                .collect(),
+            channels: channels
+                .into_iter()
+                .map(|c| TargetingResult::new(c.id, c.name, c.targeting.as_ref(), attributes))
+                .collect(),
+            update,
+        })
+    }
+
+    /// The current version of a channel other than [`STABLE`], `None` when
+    /// the app has no such channel or the channel or its version does not
+    /// target the device, which leaves the device on [`STABLE`].
+    async fn channel_version(
+        db: &DatabaseConnection,
+        app_id: i32,
+        name: &str,
+        attributes: &Attributes,
+    ) -> Result<Option<app_versions::Model>> {
+        let Some(channel) = Channels::find_by_name(db, app_id, name).await? else {
+            return Ok(None);
+        };
+        if !channel.is_targeted_at(attributes) {
+            return Ok(None);
+        }
+        let Some(version_id) = channel.current_version_id else {
+            return Ok(None);
+        };
+        Ok(app_versions::Entity::find_by_id(version_id)
+            .one(db)
+            .await?
+            .filter(|version| version.yanked_at.is_none() && version.is_targeted_at(attributes)))
+    }
+
+    /// Version codes of the mandatory versions of an app, `latest` included
+    /// whether it is published or not.
+    async fn mandatory_codes(
+        db: &DatabaseConnection,
+        latest: &app_versions::Model,
+    ) -> Result<Vec<i64>> {
+        let versions = app_versions::Entity::find()
+            .filter(version_entity::Column::AppId.eq(latest.app_id))
+            .filter(version_entity::Column::Mandatory.eq(true))
+            .filter(version_entity::Column::PublishedAt.is_not_null())
+            .all(db)
+            .await?;
+        Ok(versions
+            .iter()
+            .chain(Some(latest).filter(|v| v.mandatory))
+            .map(|v| v.version_code)
+            .collect())
+    }
+
+    /// Release notes of the published versions after the device's build up
+    /// to `version`, newest first.
+    async fn changelog(
+        db: &DatabaseConnection,
+        version: &app_versions::Model,
+        revision: &Revision,
+        locales: &Locales,
+    ) -> Result<Vec<ChangelogEntry>> {
+        let mut query = app_versions::Entity::find()
+            .filter(version_entity::Column::AppId.eq(version.app_id))
+            .filter(version_entity::Column::Id.ne(version.id))
+            .filter(version_entity::Column::PublishedAt.is_not_null())
+            .filter(version_entity::Column::VersionCode.gt(revision.build_number))
+            .filter(version_entity::Column::VersionCode.lt(version.version_code))
+            .order_by_desc(version_entity::Column::VersionCode);
+        if let Some(limit) = revision.changelog_limit {
+            if limit == 0 {
+                return Ok(vec![]);
+            }
+            query = query.limit(limit - 1);
+        }
+        let older = query.all(db).await?;
+        Ok(std::iter::once(version)
+            .chain(&older)
+            .filter(|v| v.version_code > revision.build_number)
+            .map(|v| ChangelogEntry::new(v, locales))
+            .collect())
+    }
+
+    /// The patch from the version the device runs to `version`, if one was
+    /// generated.
+    async fn find_patch(
+        db: &DatabaseConnection,
+        version: &app_versions::Model,
+        running: Option<&app_versions::Model>,
+    ) -> Result<Option<PatchInfo>> {
+        let Some(current) = running else {
+            return Ok(None);
+        };
+        let Some(patch) = VersionPatches::find_patch(db, current.id, version.id).await? else {
+            return Ok(None);
+        };
+        let file = files::Entity::find_by_id(patch.file_id)
+            .one(db)
+            .await?
+            .ok_or(Error::NotFound)?;
+        Ok(Some(PatchInfo {
+            algorithm: patch.algorithm,
+            file_url: file.path,
+            size: file.size_bytes,
+            checksum: file.checksum_sha256,
+        }))
+    }
+}
+
+/// The version with the higher version code. Channel users move on to
+/// stable once it overtakes their channel.
+fn newest(
+    a: Option<app_versions::Model>,
+    b: Option<app_versions::Model>,
+) -> Option<app_versions::Model> {
+    match (a, b) {
+        (Some(a), Some(b)) if b.version_code > a.version_code => Some(b),
+        (a, b) => a.or(b),
+    }
 }
 
 // implement your write-oriented logic here
 impl ActiveModel {
-    pub async fn create(db: &DatabaseConnection, data: &CreateApp) -> Result<Model> {
+    pub async fn create<C>(db: &C, data: &CreateApp) -> Result<Model>
+    where
+        C: ConnectionTrait,
+    {
+        let data = &data.resolve(db).await?;
         // check if bundle_id already exists in db
         if Entity::find()
             .filter(apps::Column::BundleId.eq(&data.bundle_id))
@@ -136,34 +410,160 @@ impl ToCondition for AppQuery {
 
 #[derive(Clone, Debug, Serialize, Deserialize, Validate)]
 pub struct CreateApp {
+    /// Taken from the label of `source_file_id` when empty
+    #[serde(default)]
     pub name: String,
     pub bundle_id: String,
+    /// Taken from the icon of `source_file_id` when omitted
     pub icon_file_id: Option<i32>,
     pub current_version_id: Option<i32>,
     pub description: Option<String>,
     pub platform_id: i32,
+    /// Translations of `description` by locale, e.g. `zh-CN`
+    pub localized_descriptions: Option<BTreeMap<String, String>>,
+    /// Builds below this version code are told to update before running
+    pub min_supported_version_code: Option<i64>,
+    /// An uploaded apk offering the default name and icon
+    pub source_file_id: Option<i32>,
 }
 
 impl CreateApp {
+    /// Fills the name and icon left out of the payload from the label and
+    /// icon extracted from `source_file_id`.
+    pub async fn resolve<C>(&self, db: &C) -> Result<Self>
+    where
+        C: ConnectionTrait,
+    {
+        let mut data = self.clone();
+        if let Some(id) = self.source_file_id {
+            let file = files::Entity::find_by_id(id)
+                .one(db)
+                .await?
+                .ok_or(Error::NotFound)?;
+            if data.name.is_empty() {
+                data.name = file.label.unwrap_or_default();
+            }
+            data.icon_file_id = data.icon_file_id.or(file.icon_file_id);
+        }
+        if data.name.is_empty() {
+            return Err(Error::BadRequest(
+                "name is required when the source file carries no label".to_string(),
+            ));
+        }
+        Ok(data)
+    }
+
     pub fn update(&self, item: &mut ActiveModel) {
         item.name = Set(self.name.clone());
         item.bundle_id = Set(self.bundle_id.clone());
-        item.icon_file_id = Set(self.icon_file_id.clone());
-        item.current_version_id = Set(self.current_version_id.clone());
+        item.icon_file_id = Set(self.icon_file_id);
+        item.current_version_id = Set(self.current_version_id);
         item.description = Set(self.description.clone());
         item.platform_id = Set(self.platform_id);
+        item.min_supported_version_code = Set(self.min_supported_version_code);
+        item.localized_descriptions = Set(self
+            .localized_descriptions
+            .as_ref()
+            .map(|texts| serde_json::json!(texts)));
     }
 }
 
 #[derive(Deserialize)]
 pub struct Revision {
     pub version_name: String,
-    pub build_number: usize,
+    pub build_number: i64,
+    /// Supported ABIs in order of preference, comma separated
+    pub abis: Option<String>,
+    /// Screen density in dpi
+    pub density: Option<i32>,
+    /// Release channel the device follows, [`STABLE`] when omitted
+    pub channel: Option<String>,
+    /// Most release notes to return, all of them when omitted
+    pub changelog_limit: Option<u64>,
+    /// Stable identifier of the device, places it inside or outside staged
+    /// rollouts
+    pub device_id: Option<String>,
+    /// API level of the device, `Build.VERSION.SDK_INT` on Android
+    pub sdk_int: Option<i32>,
+    /// System features of the device, comma separated. Required features
+    /// are not checked when omitted
+    pub features: Option<String>,
+}
+
+impl Revision {
+    pub fn abis(&self) -> Vec<&str> {
+        self.abis
+            .as_deref()
+            .map(|abis| {
+                abis.split(',')
+                    .map(str::trim)
+                    .filter(|a| !a.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Whether the device can install `file`, judged by the API levels,
+    /// native ABIs and features the artifact requires. Requirements the
+    /// device reports nothing about are taken as met.
+    pub fn can_install(&self, file: &files::Model) -> bool {
+        if let Some(sdk_int) = self.sdk_int {
+            if file.min_sdk_version.is_some_and(|min| sdk_int < min)
+                || file.max_sdk_version.is_some_and(|max| sdk_int > max)
+            {
+                return false;
+            }
+        }
+        let abis = self.abis();
+        let native_abis = file.native_abis();
+        if !abis.is_empty()
+            && !native_abis.is_empty()
+            && !native_abis.iter().any(|abi| abis.contains(&abi.as_str()))
+        {
+            return false;
+        }
+        match &self.features {
+            Some(features) => {
+                let features: Vec<&str> = features.split(',').map(str::trim).collect();
+                file.required_features()
+                    .iter()
+                    .all(|feature| features.contains(&feature.as_str()))
+            }
+            None => true,
+        }
+    }
+
+    /// Whether the device falls inside the rollout of `version`. Devices
+    /// without an identifier are left out.
+    pub fn is_in_rollout(&self, version: &app_versions::Model) -> bool {
+        self.device_id.as_deref().is_some_and(|device_id| {
+            app_versions::rollout_bucket(version.id, device_id) < version.rollout_percentage
+        })
+    }
+
+    /// Whether the device has to update before it keeps running: its build is
+    /// below `minimum`, or one of the `mandatory` version codes lies between
+    /// it and `latest`.
+    pub fn must_update(&self, minimum: Option<i64>, latest: i64, mandatory: &[i64]) -> bool {
+        let current = self.build_number;
+        minimum.is_some_and(|minimum| current < minimum)
+            || mandatory
+                .iter()
+                .any(|&code| current < code && code <= latest)
+    }
 }
 
 #[derive(Serialize, Default)]
 pub struct UpdateInfo {
     pub update_available: bool,
+    /// The running build is no longer supported and must not be skipped
+    pub force_update: bool,
+    /// The running build was rolled back or yanked, the older latest version
+    /// has to replace it, which needs a reinstall on Android
+    pub reinstall: bool,
+    /// Why the running build was yanked, it has to be replaced by the latest
+    /// version even if that is older
+    pub yank_reason: Option<String>,
     pub latest_version: Option<LatestVersionInfo>,
 }
 
@@ -171,15 +571,102 @@ pub struct UpdateInfo {
 pub struct LatestVersionInfo {
     pub id: i32,
     pub name: String,
-    pub build_number: String,
+    pub build_number: i64,
     pub file_url: String,
+    pub size: i64,
+    /// sha256 of the full file, also what applying the patch must produce
+    pub checksum: String,
+    /// Patch from the version the device runs, when one is available
+    pub patch: Option<PatchInfo>,
+    /// Release notes of every version since the device's build, newest first
+    pub changelog: Vec<ChangelogEntry>,
+}
+
+#[derive(Serialize)]
+pub struct ChangelogEntry {
+    pub name: String,
+    pub build_number: i64,
+    pub published_at: Option<DateTimeWithTimeZone>,
+    pub release_notes: Option<String>,
+}
+
+impl ChangelogEntry {
+    /// Entry of a version with its notes in the first of `locales` they are
+    /// translated to.
+    pub fn new(version: &app_versions::Model, locales: &Locales) -> Self {
+        Self {
+            name: version.version_name.clone(),
+            build_number: version.version_code,
+            published_at: version.published_at,
+            release_notes: locales
+                .pick(version.localized_release_notes.as_ref())
+                .map(ToOwned::to_owned)
+                .or_else(|| version.release_notes.clone()),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct PatchInfo {
+    pub algorithm: String,
+    pub file_url: String,
+    pub size: i64,
+    pub checksum: String,
 }
 
 impl UpdateInfo {
-    pub fn new(update_available: bool, latest_version: Option<LatestVersionInfo>) -> Self {
+    pub fn new(
+        update_available: bool,
+        force_update: bool,
+        reinstall: bool,
+        yank_reason: Option<String>,
+        latest_version: Option<LatestVersionInfo>,
+    ) -> Self {
         Self {
             update_available,
+            force_update,
+            reinstall,
+            yank_reason,
             latest_version,
         }
     }
 }
+
+#[derive(Deserialize)]
+pub struct DryRunPayload {
+    /// Attributes of the sample device, as it would send them to
+    /// `check-update`
+    pub attributes: Attributes,
+    /// The build the device runs, to also return what it would be offered
+    pub revision: Option<Revision>,
+}
+
+#[derive(Serialize)]
+pub struct DryRun {
+    /// Every version of the app, newest first
+    pub versions: Vec<TargetingResult>,
+    /// Every channel of the app, in promotion order
+    pub channels: Vec<TargetingResult>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub update: Option<UpdateInfo>,
+}
+
+#[derive(Serialize)]
+pub struct TargetingResult {
+    pub id: i32,
+    pub name: String,
+    pub targeted: bool,
+    /// How the targeting rule was evaluated, `None` without one
+    pub trace: Option<Trace>,
+}
+
+impl TargetingResult {
+    fn new(id: i32, name: String, targeting: Option<&Json>, attributes: &Attributes) -> Self {
+        Self {
+            id,
+            name,
+            targeted: Rule::admits(targeting, attributes),
+            trace: Rule::trace_stored(targeting, attributes),
+        }
+    }
+}
@@ -0,0 +1,177 @@
+//! Releasing a build in one step: the artifact, its app and its version are
+//! written in a single transaction.
+use loco_rs::{app::AppContext, Error, Result};
+use sea_orm::{entity::prelude::*, TransactionTrait};
+
+use super::{
+    _entities::{app_version_artifacts, app_versions, apps, files},
+    app_version_artifacts::{AddArtifact, ArtifactKind},
+    app_versions::CreateAppVersion,
+    apps::CreateApp,
+    files::{remove_stored, Upload},
+    platforms::Platforms,
+};
+use crate::{
+    artifacts::{inspector, ArtifactType},
+    workers::{self, webhook::WebhookWorker},
+};
+
+pub struct BuildUpload {
+    /// The installable apk, ipa, hap or app pack
+    pub artifact: Upload,
+    /// The app bundle the apk was built from, kept for archival
+    pub bundle: Option<Upload>,
+    pub release_notes: Option<String>,
+    pub publish: bool,
+    pub mandatory: bool,
+}
+
+/// Rows written for an uploaded build.
+pub struct Build {
+    pub app: apps::Model,
+    pub file: files::Model,
+    pub version: app_versions::Model,
+    pub artifacts: Vec<app_version_artifacts::Model>,
+}
+
+impl BuildUpload {
+    /// Stores the artifact, finds or creates its app by `bundle_id` and
+    /// platform and creates the version. Nothing is left behind on failure,
+    /// storage objects included.
+    pub async fn create(self, ctx: &AppContext) -> Result<Build> {
+        let artifact_type = ArtifactType::detect(&self.artifact.bytes);
+        if artifact_type.and_then(inspector::installed_on).is_none() {
+            return Err(Error::BadRequest(format!(
+                "{} is not an installable artifact",
+                self.artifact.name
+            )));
+        }
+        if let Some(bundle) = &self.bundle {
+            if artifact_type != Some(ArtifactType::Apk) {
+                return Err(Error::BadRequest(
+                    "bundles can only accompany an apk".to_string(),
+                ));
+            }
+            expect_type(bundle, ArtifactType::Aab)?;
+        }
+
+        let mut written = vec![];
+        let build = self.insert(ctx, &mut written).await;
+        match &build {
+            Ok(build) => {
+                if build.version.published_at.is_some() {
+                    workers::after_publish(ctx, build.version.id, None).await?;
+                } else {
+                    WebhookWorker::enqueue(ctx).await?;
+                }
+            }
+            Err(_) => remove_stored(&ctx.storage, &written).await,
+        }
+        build
+    }
+
+    async fn insert(self, ctx: &AppContext, written: &mut Vec<String>) -> Result<Build> {
+        let tx = ctx.db.begin().await?;
+
+        let mut file = files::ActiveModel {
+            ..Default::default()
+        };
+        let meta = file
+            .store_upload(&tx, &ctx.storage, self.artifact, written)
+            .await?
+            .ok_or_else(|| Error::BadRequest("build artifact carries no manifest".to_string()))?;
+        let file = file.insert(&tx).await?;
+
+        let code = inspector::installed_on(meta.artifact_type)
+            .ok_or_else(|| Error::BadRequest("build artifact is not installable".to_string()))?;
+        let platform = Platforms::find_by_code(&tx, code).await?;
+        let app = match apps::Entity::find()
+            .filter(apps::Column::BundleId.eq(&meta.package_name))
+            .filter(apps::Column::PlatformId.eq(platform.id))
+            .one(&tx)
+            .await?
+        {
+            Some(app) => app,
+            None => {
+                let data = CreateApp {
+                    // fall back to the package name for artifacts without a label
+                    name: file
+                        .label
+                        .clone()
+                        .unwrap_or_else(|| meta.package_name.clone()),
+                    bundle_id: meta.package_name.clone(),
+                    icon_file_id: None,
+                    current_version_id: None,
+                    description: None,
+                    platform_id: platform.id,
+                    localized_descriptions: None,
+                    min_supported_version_code: None,
+                    source_file_id: Some(file.id),
+                };
+                apps::ActiveModel::create(&tx, &data).await?
+            }
+        };
+
+        let version = app_versions::ActiveModel::create(
+            &tx,
+            &CreateAppVersion {
+                app_id: app.id,
+                version_code: None,
+                version_name: None,
+                release_notes: self.release_notes,
+                localized_release_notes: None,
+                apk_file_id: file.id,
+                publish_immediately: Some(self.publish),
+                mandatory: Some(self.mandatory),
+                min_supported_version_code: None,
+                targeting: None,
+            },
+        )
+        .await?;
+
+        let mut artifacts = vec![];
+        if let Some(bundle) = self.bundle {
+            let mut bundle_file = files::ActiveModel {
+                ..Default::default()
+            };
+            bundle_file
+                .store_upload(&tx, &ctx.storage, bundle, written)
+                .await?;
+            let bundle_file = bundle_file.insert(&tx).await?;
+            let data = AddArtifact {
+                file_id: bundle_file.id,
+                kind: ArtifactKind::Bundle,
+                abi: None,
+                density: None,
+            };
+            artifacts
+                .push(app_version_artifacts::ActiveModel::create(&tx, version.id, &data).await?);
+        }
+
+        // publishing moved the app's current version
+        let app = apps::Entity::find_by_id(app.id)
+            .one(&tx)
+            .await?
+            .ok_or(Error::NotFound)?;
+
+        tx.commit().await?;
+        Ok(Build {
+            app,
+            file,
+            version,
+            artifacts,
+        })
+    }
+}
+
+fn expect_type(upload: &Upload, expected: ArtifactType) -> Result<()> {
+    if ArtifactType::detect(&upload.bytes) == Some(expected) {
+        Ok(())
+    } else {
+        Err(Error::BadRequest(format!(
+            "{} is not an {}",
+            upload.name,
+            expected.as_str()
+        )))
+    }
+}
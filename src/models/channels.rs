@@ -0,0 +1,207 @@
+use loco_rs::{Error, Result};
+use sea_orm::{entity::prelude::*, ActiveValue::Set, QueryOrder};
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+pub use super::_entities::channels::{ActiveModel, Column, Entity, Model};
+use super::_entities::{app_versions, apps};
+use super::app_versions::PublishPayload;
+use crate::targeting::{Attributes, Rule};
+pub type Channels = Entity;
+
+/// The channel every device is on unless it asks for another one. It is not
+/// stored, its version is the current version of the app.
+pub const STABLE: &str = "stable";
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(self, _db: &C, insert: bool) -> std::result::Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        if !insert && self.updated_at.is_unchanged() {
+            let mut this = self;
+            this.updated_at = sea_orm::ActiveValue::Set(chrono::Utc::now().into());
+            Ok(this)
+        } else {
+            Ok(self)
+        }
+    }
+}
+
+// implement your read-oriented logic here
+impl Model {
+    /// Whether the targeting rule of the channel lets a device in, devices
+    /// it does not are on [`STABLE`].
+    pub fn is_targeted_at(&self, attributes: &Attributes) -> bool {
+        Rule::admits(self.targeting.as_ref(), attributes)
+    }
+}
+
+// implement your write-oriented logic here
+impl ActiveModel {
+    pub async fn create<C>(db: &C, app_id: i32, data: &CreateChannel) -> Result<Model>
+    where
+        C: ConnectionTrait,
+    {
+        apps::Entity::find_by_id(app_id)
+            .one(db)
+            .await?
+            .ok_or(Error::NotFound)?;
+        if data.name == STABLE {
+            return Err(Error::BadRequest(format!(
+                "{STABLE} is the current version of the app and cannot be created"
+            )));
+        }
+        if Entity::find_by_name(db, app_id, &data.name)
+            .await?
+            .is_some()
+        {
+            return Err(Error::BadRequest(format!(
+                "app {app_id} already has a channel {}",
+                data.name
+            )));
+        }
+
+        let mut item = ActiveModel {
+            app_id: Set(app_id),
+            ..Default::default()
+        };
+        data.update(&mut item);
+        Ok(item.insert(db).await?)
+    }
+
+    /// Makes a version the current one of a channel, the next channel after
+    /// the one it is on when `to` is omitted. Promoting to [`STABLE`]
+    /// publishes the version. A channel never moves to an older version,
+    /// [`STABLE`] goes back through a rollback. Returns the channel promoted
+    /// to.
+    pub async fn promote(
+        db: &DatabaseConnection,
+        version_id: i32,
+        payload: &PromotePayload,
+    ) -> Result<String> {
+        let version = app_versions::Entity::find_by_id(version_id)
+            .one(db)
+            .await?
+            .ok_or(Error::NotFound)?;
+        if version.yanked_at.is_some() {
+            return Err(Error::BadRequest(format!(
+                "version {version_id} was yanked and cannot be promoted"
+            )));
+        }
+        let channels = Entity::find_by_app(db, version.app_id).await?;
+        let to = match &payload.to {
+            Some(to) => to.clone(),
+            None => {
+                let from = channels
+                    .iter()
+                    .filter(|c| c.current_version_id == Some(version_id))
+                    .max_by_key(|c| c.position)
+                    .ok_or_else(|| {
+                        Error::BadRequest(format!(
+                            "version {version_id} is on no channel, name the channel to promote it to"
+                        ))
+                    })?;
+                channels
+                    .iter()
+                    .filter(|c| c.position > from.position)
+                    .min_by_key(|c| c.position)
+                    .map_or_else(|| STABLE.to_string(), |c| c.name.clone())
+            }
+        };
+
+        let channel = if to == STABLE {
+            None
+        } else {
+            Some(channels.into_iter().find(|c| c.name == to).ok_or_else(|| {
+                Error::BadRequest(format!("app {} has no channel {to}", version.app_id))
+            })?)
+        };
+        let current_id = match &channel {
+            Some(channel) => channel.current_version_id,
+            None => {
+                apps::Entity::find_by_id(version.app_id)
+                    .one(db)
+                    .await?
+                    .ok_or(Error::NotFound)?
+                    .current_version_id
+            }
+        };
+        if let Some(current_id) = current_id.filter(|&id| id != version_id) {
+            let current = app_versions::Entity::find_by_id(current_id).one(db).await?;
+            if let Some(current) = current.filter(|c| c.version_code > version.version_code) {
+                return Err(Error::BadRequest(format!(
+                    "{to} is on version {} with a higher version_code than version {version_id}",
+                    current.id
+                )));
+            }
+        }
+
+        let Some(channel) = channel else {
+            let payload = PublishPayload {
+                publish: true,
+                rollout_percentage: payload.rollout_percentage,
+            };
+            app_versions::ActiveModel::publish(db, version_id, &payload).await?;
+            return Ok(to);
+        };
+        let mut channel: ActiveModel = channel.into();
+        channel.current_version_id = Set(Some(version_id));
+        channel.update(db).await?;
+        Ok(to)
+    }
+}
+
+// implement your custom finders, selectors oriented logic here
+impl Entity {
+    /// Channels of an app, in promotion order.
+    pub async fn find_by_app<C>(db: &C, app_id: i32) -> Result<Vec<Model>>
+    where
+        C: ConnectionTrait,
+    {
+        Ok(Self::find()
+            .filter(Column::AppId.eq(app_id))
+            .order_by_asc(Column::Position)
+            .all(db)
+            .await?)
+    }
+
+    pub async fn find_by_name<C>(db: &C, app_id: i32, name: &str) -> Result<Option<Model>>
+    where
+        C: ConnectionTrait,
+    {
+        Ok(Self::find()
+            .filter(Column::AppId.eq(app_id))
+            .filter(Column::Name.eq(name))
+            .one(db)
+            .await?)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Validate)]
+pub struct CreateChannel {
+    #[validate(length(min = 1, max = 64))]
+    pub name: String,
+    /// Versions are promoted from lower to higher positions, and from the
+    /// last channel to [`STABLE`]
+    pub position: i32,
+    /// Devices that may follow the channel, every device when omitted
+    pub targeting: Option<Rule>,
+}
+
+impl CreateChannel {
+    pub fn update(&self, item: &mut ActiveModel) {
+        item.name = Set(self.name.clone());
+        item.position = Set(self.position);
+        item.targeting = Set(self.targeting.as_ref().map(|rule| serde_json::json!(rule)));
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PromotePayload {
+    /// Channel to promote to, the one after the version's channel when omitted
+    pub to: Option<String>,
+    /// Rollout percentage when promoting to stable
+    pub rollout_percentage: Option<i16>,
+}
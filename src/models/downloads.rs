@@ -0,0 +1,241 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+use axum::{
+    extract::{ConnectInfo, FromRequestParts},
+    http::{header, request::Parts},
+};
+use chrono::{NaiveDate, NaiveTime};
+use loco_rs::{controller::middleware::remote_ip::RemoteIP, prelude::AppContext, Error, Result};
+use sea_orm::{
+    entity::prelude::*, sea_query::Alias, ActiveValue::Set, FromQueryResult, IntoActiveModel,
+    QueryOrder, QuerySelect,
+};
+use serde::Serialize;
+
+pub use super::_entities::downloads::{ActiveModel, Column, Entity, Model};
+use super::_entities::{app_version_artifacts, app_versions, apps, files, version_patches};
+use crate::settings::Settings;
+pub type Downloads = Entity;
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(self, _db: &C, insert: bool) -> std::result::Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        if !insert && self.updated_at.is_unchanged() {
+            let mut this = self;
+            this.updated_at = sea_orm::ActiveValue::Set(chrono::Utc::now().into());
+            Ok(this)
+        } else {
+            Ok(self)
+        }
+    }
+}
+
+/// Drops the host part of an address: the last octet of an IPv4 address,
+/// all but the first 48 bits of an IPv6 one.
+pub fn anonymize(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, c, _] = ip.octets();
+            IpAddr::V4(Ipv4Addr::new(a, b, c, 0))
+        }
+        IpAddr::V6(ip) => {
+            let [a, b, c, ..] = ip.segments();
+            IpAddr::V6(Ipv6Addr::new(a, b, c, 0, 0, 0, 0, 0))
+        }
+    }
+}
+
+/// Who is downloading, as far as the request tells.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Client {
+    /// Anonymized when `settings.downloads.anonymize_ip` is on
+    pub ip: Option<IpAddr>,
+    pub user_agent: Option<String>,
+}
+
+impl FromRequestParts<AppContext> for Client {
+    type Rejection = Error;
+
+    async fn from_request_parts(parts: &mut Parts, ctx: &AppContext) -> Result<Self, Error> {
+        let settings = Settings::from_config(&ctx.config)?.downloads;
+        // the remote_ip middleware knows about trusted proxies, the socket
+        // address is all there is without it
+        let ip = match parts.extensions.get::<RemoteIP>() {
+            Some(RemoteIP::Forwarded(ip) | RemoteIP::Socket(ip)) => Some(*ip),
+            Some(RemoteIP::None) | None => parts
+                .extensions
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|info| info.0.ip()),
+        };
+        let ip = if settings.anonymize_ip {
+            ip.map(anonymize)
+        } else {
+            ip
+        };
+        let user_agent = parts
+            .headers
+            .get(header::USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .map(ToOwned::to_owned);
+        Ok(Self { ip, user_agent })
+    }
+}
+
+// implement your read-oriented logic here
+impl Model {}
+
+// implement your write-oriented logic here
+impl ActiveModel {
+    /// Logs the start of a download of `file`, attributed to the version
+    /// and app it belongs to. Nothing has been sent yet.
+    pub async fn start<C>(db: &C, file: &files::Model, client: &Client) -> Result<Model>
+    where
+        C: ConnectionTrait,
+    {
+        let (app_id, app_version_id) = owner(db, file.id).await?;
+        let item = Self {
+            file_id: Set(Some(file.id)),
+            app_id: Set(app_id),
+            app_version_id: Set(app_version_id),
+            client_ip: Set(client.ip.map(|ip| ip.to_string())),
+            user_agent: Set(client.user_agent.clone()),
+            size_bytes: Set(file.size_bytes),
+            bytes_sent: Set(0),
+            completed: Set(false),
+            ..Default::default()
+        };
+        Ok(item.insert(db).await?)
+    }
+
+    /// Records how much of the file was sent once the response is over,
+    /// `completed` when the last of it was handed to the connection.
+    pub async fn finish<C>(db: &C, id: i32, bytes_sent: i64, completed: bool) -> Result<()>
+    where
+        C: ConnectionTrait,
+    {
+        let item = Entity::find_by_id(id)
+            .one(db)
+            .await?
+            .ok_or_else(|| Error::NotFound)?;
+        let mut item = item.into_active_model();
+        item.bytes_sent = Set(bytes_sent);
+        item.completed = Set(completed);
+        item.update(db).await?;
+        Ok(())
+    }
+}
+
+/// The app and version a file is served for: the version's main file, one
+/// of its split or density artifacts, or a patch to it, else an app icon.
+async fn owner<C>(db: &C, file_id: i32) -> Result<(Option<i32>, Option<i32>)>
+where
+    C: ConnectionTrait,
+{
+    let mut version_id = app_versions::Entity::find()
+        .filter(app_versions::Column::ApkFileId.eq(file_id))
+        .one(db)
+        .await?
+        .map(|version| version.id);
+    if version_id.is_none() {
+        version_id = app_version_artifacts::Entity::find()
+            .filter(app_version_artifacts::Column::FileId.eq(file_id))
+            .one(db)
+            .await?
+            .map(|artifact| artifact.app_version_id);
+    }
+    if version_id.is_none() {
+        version_id = version_patches::Entity::find()
+            .filter(version_patches::Column::FileId.eq(file_id))
+            .one(db)
+            .await?
+            .map(|patch| patch.app_version_id);
+    }
+    if let Some(version_id) = version_id {
+        let version = app_versions::Entity::find_by_id(version_id).one(db).await?;
+        return Ok((version.map(|version| version.app_id), Some(version_id)));
+    }
+    let app = apps::Entity::find()
+        .filter(apps::Column::IconFileId.eq(file_id))
+        .one(db)
+        .await?;
+    Ok((app.map(|app| app.id), None))
+}
+
+// implement your custom finders, selectors oriented logic here
+impl Entity {
+    /// Downloads of a version since the start of `since`.
+    pub async fn version_stats<C>(
+        db: &C,
+        app_version_id: i32,
+        since: NaiveDate,
+    ) -> Result<DownloadStats>
+    where
+        C: ConnectionTrait,
+    {
+        let stats = stats_query(since)
+            .filter(Column::AppVersionId.eq(app_version_id))
+            .into_model::<DownloadStats>()
+            .one(db)
+            .await?;
+        Ok(stats.unwrap_or_default())
+    }
+
+    /// Downloads of an app since the start of `since` by version, newest
+    /// version first. Icons and files of deleted versions are counted
+    /// without one.
+    pub async fn app_stats<C>(
+        db: &C,
+        app_id: i32,
+        since: NaiveDate,
+    ) -> Result<Vec<VersionDownloads>>
+    where
+        C: ConnectionTrait,
+    {
+        Ok(stats_query(since)
+            .column(Column::AppVersionId)
+            .filter(Column::AppId.eq(app_id))
+            .group_by(Column::AppVersionId)
+            .order_by_desc(Column::AppVersionId)
+            .into_model::<VersionDownloads>()
+            .all(db)
+            .await?)
+    }
+}
+
+fn stats_query(since: NaiveDate) -> Select<Entity> {
+    let since = since.and_time(NaiveTime::MIN).and_utc();
+    Entity::find()
+        .select_only()
+        .column_as(Expr::col(Column::Id).count(), "downloads")
+        .column_as(
+            Expr::cust("COALESCE(SUM(CASE WHEN completed THEN 1 ELSE 0 END), 0)")
+                .cast_as(Alias::new("bigint")),
+            "completed",
+        )
+        .column_as(
+            Expr::cust("COALESCE(SUM(bytes_sent), 0)").cast_as(Alias::new("bigint")),
+            "bytes_sent",
+        )
+        .filter(Column::CreatedAt.gte(since))
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Serialize, FromQueryResult)]
+pub struct DownloadStats {
+    pub downloads: i64,
+    /// Downloads that delivered the whole file
+    pub completed: i64,
+    /// Bandwidth used, partial downloads included
+    pub bytes_sent: i64,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, FromQueryResult)]
+pub struct VersionDownloads {
+    /// `None` for icons and files of deleted versions
+    pub app_version_id: Option<i32>,
+    pub downloads: i64,
+    pub completed: i64,
+    pub bytes_sent: i64,
+}
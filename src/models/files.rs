@@ -1,12 +1,16 @@
 use crate::utils::ConditionBuilderExt;
+use axum::{body::Bytes, extract::multipart::Field};
 use loco_rs::model::query::{self, paginate, PageResponse, PaginationQuery};
-use loco_rs::Result;
+use loco_rs::storage::Storage;
+use loco_rs::{Error, Result};
 use sea_orm::entity::prelude::*;
-use sea_orm::{Condition, QueryOrder};
+use sea_orm::{ActiveValue::Set, Condition, QueryOrder};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 
 pub use super::_entities::files::{ActiveModel, Column, Entity, Model};
 use super::common::ToCondition;
+use crate::artifacts::{inspector, ArtifactMetadata, ArtifactType};
 pub type Files = Entity;
 
 #[async_trait::async_trait]
@@ -40,14 +44,159 @@ impl Model {
         )
         .await
     }
+
+    /// The recognised artifact type, `None` for plain files.
+    pub fn artifact_type(&self) -> Option<ArtifactType> {
+        self.artifact_type.as_deref().and_then(ArtifactType::parse)
+    }
+
+    /// ABIs the artifact ships native libraries for, empty when it runs on
+    /// any.
+    pub fn native_abis(&self) -> Vec<String> {
+        strings(self.native_abis.as_ref())
+    }
+
+    /// Features a device must have to install the artifact.
+    pub fn required_features(&self) -> Vec<String> {
+        strings(self.required_features.as_ref())
+    }
+
+    /// Path the stored object is served under.
+    pub fn static_path(&self) -> String {
+        format!("/api/files/static/{}", self.path)
+    }
 }
 
 // implement your write-oriented logic here
-impl ActiveModel {}
+impl ActiveModel {
+    /// Uploads `bytes` to storage under a fresh key and fills in the columns
+    /// describing the stored object. Returns the storage key.
+    pub async fn store(
+        &mut self,
+        storage: &Storage,
+        name: String,
+        mime: String,
+        bytes: &Bytes,
+    ) -> Result<String> {
+        let path = Uuid::new_v4().to_string();
+        storage.upload(std::path::Path::new(&path), bytes).await?;
+
+        self.name = Set(name);
+        self.mime = Set(mime);
+        self.size_bytes = Set(bytes.len() as i64);
+        self.path = Set(path.clone());
+        self.checksum_sha256 = Set(hex::encode(Sha256::digest(bytes)));
+        Ok(path)
+    }
+
+    /// Stores an upload. Artifacts are recognised by their content and
+    /// inspected by the inspector of their platform before anything is
+    /// written so a broken one is rejected, and
+    /// their launcher icon is inserted as a file of its own. The storage keys
+    /// written are pushed to `written` so a caller can remove them when its
+    /// transaction fails.
+    pub async fn store_upload<C>(
+        &mut self,
+        db: &C,
+        storage: &Storage,
+        upload: Upload,
+        written: &mut Vec<String>,
+    ) -> Result<Option<ArtifactMetadata>>
+    where
+        C: ConnectionTrait,
+    {
+        let mut meta = inspector::inspect(&upload.bytes)?;
+        let claimed = inspector::claimed_type(&upload.name, &upload.mime);
+        if let (None, Some(claimed)) = (&meta, claimed) {
+            return Err(Error::BadRequest(format!(
+                "{} is not a valid {}",
+                upload.name,
+                claimed.as_str()
+            )));
+        }
+        if let Some(meta) = &mut meta {
+            self.set_metadata(meta);
+
+            if let Some(icon) = meta.icon.take() {
+                let mut icon_item = Self {
+                    ..Default::default()
+                };
+                written.push(
+                    icon_item
+                        .store(storage, icon.name, icon.mime.to_owned(), &icon.bytes.into())
+                        .await?,
+                );
+                self.icon_file_id = Set(Some(icon_item.insert(db).await?.id));
+            }
+        }
+        written.push(
+            self.store(storage, upload.name, upload.mime, &upload.bytes)
+                .await?,
+        );
+        Ok(meta)
+    }
+
+    /// Copies the metadata read from the uploaded artifact onto the row.
+    pub fn set_metadata(&mut self, meta: &ArtifactMetadata) {
+        self.artifact_type = Set(Some(meta.artifact_type.as_str().to_string()));
+        self.package_name = Set(Some(meta.package_name.clone()));
+        self.version_code = Set(Some(meta.version_code));
+        self.version_name = Set(meta.version_name.clone());
+        self.min_sdk_version = Set(meta.min_sdk_version);
+        self.target_sdk_version = Set(meta.target_sdk_version);
+        self.max_sdk_version = Set(meta.max_sdk_version);
+        self.permissions = Set(Some(serde_json::json!(meta.permissions)));
+        self.native_abis = Set(Some(serde_json::json!(meta.native_abis)));
+        self.required_features = Set(Some(serde_json::json!(meta.required_features)));
+        if let Some(signing) = &meta.signing {
+            self.signer_fingerprints = Set(Some(serde_json::json!(signing.signers)));
+            self.signing_lineage = Set(Some(serde_json::json!(signing.lineage)));
+        }
+        self.label = Set(meta.label.clone());
+    }
+}
+
+/// The strings of a JSON array column.
+fn strings(json: Option<&serde_json::Value>) -> Vec<String> {
+    json.and_then(|json| serde_json::from_value(json.clone()).ok())
+        .unwrap_or_default()
+}
+
+/// Removes objects written to storage by a request that failed afterwards.
+pub async fn remove_stored(storage: &Storage, keys: &[String]) {
+    for key in keys {
+        if let Err(err) = storage.delete(std::path::Path::new(key)).await {
+            tracing::warn!(key, err = err.to_string(), "failed to remove stored object");
+        }
+    }
+}
 
 // implement your custom finders, selectors oriented logic here
 impl Entity {}
 
+/// A file received in a multipart request.
+pub struct Upload {
+    pub name: String,
+    pub mime: String,
+    pub bytes: Bytes,
+}
+
+impl Upload {
+    /// Reads a multipart field carrying a file.
+    pub async fn read(field: Field<'_>) -> Result<Self> {
+        let name = field
+            .file_name()
+            .ok_or(Error::string("empty file name"))?
+            .to_owned();
+        let mime = field
+            .content_type()
+            .ok_or(Error::string("content type is null"))?
+            .to_owned();
+        let bytes = field.bytes().await.map_err(Error::msg)?;
+        Ok(Self { name, mime, bytes })
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct FileQuery {
     pub name: Option<String>,
@@ -3,6 +3,15 @@ pub mod platforms;
 pub mod users;
 pub mod files;
 pub mod apps;
+pub mod app_version_artifacts;
 pub mod app_versions;
+pub mod builds;
+pub mod version_patches;
+pub mod channels;
+pub mod update_checks;
+pub mod downloads;
+pub mod webhooks;
+pub mod webhook_deliveries;
+pub mod subscriptions;
 
 pub mod common;
@@ -1,7 +1,18 @@
-pub use super::_entities::platforms::{ActiveModel, Entity, Model};
+pub use super::_entities::platforms::{ActiveModel, Column, Entity, Model};
+use loco_rs::{Error, Result};
 use sea_orm::entity::prelude::*;
 pub type Platforms = Entity;
 
+/// Well known values of `platforms.code`, the platforms artifacts are
+/// recognised for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(i16)]
+pub enum PlatformCode {
+    Android = 1,
+    Ios = 2,
+    HarmonyOs = 3,
+}
+
 #[async_trait::async_trait]
 impl ActiveModelBehavior for ActiveModel {
     async fn before_save<C>(self, _db: &C, insert: bool) -> std::result::Result<Self, DbErr>
@@ -25,4 +36,20 @@ impl Model {}
 impl ActiveModel {}
 
 // implement your custom finders, selectors oriented logic here
-impl Entity {}
+impl Entity {
+    pub async fn find_by_code<C>(db: &C, code: PlatformCode) -> Result<Model>
+    where
+        C: ConnectionTrait,
+    {
+        Self::find()
+            .filter(Column::Code.eq(code as i16))
+            .one(db)
+            .await?
+            .ok_or_else(|| {
+                Error::BadRequest(format!(
+                    "no platform with code {} ({code:?}) is registered",
+                    code as i16
+                ))
+            })
+    }
+}
@@ -0,0 +1,154 @@
+use loco_rs::{Error, Result};
+use sea_orm::{entity::prelude::*, ActiveValue::Set, IntoActiveModel, QueryOrder, QuerySelect};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+pub use super::_entities::subscriptions::{ActiveModel, Column, Entity, Model};
+use super::{apps::Apps, channels};
+pub type Subscriptions = Entity;
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(self, _db: &C, insert: bool) -> std::result::Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        if !insert && self.updated_at.is_unchanged() {
+            let mut this = self;
+            this.updated_at = sea_orm::ActiveValue::Set(chrono::Utc::now().into());
+            Ok(this)
+        } else {
+            Ok(self)
+        }
+    }
+}
+
+// implement your read-oriented logic here
+impl Model {
+    /// Path of the link in every announcement that ends the subscription.
+    pub fn unsubscribe_path(&self) -> String {
+        format!("/api/subscriptions/unsubscribe/{}", self.unsubscribe_token)
+    }
+}
+
+// implement your write-oriented logic here
+impl ActiveModel {
+    /// Subscribes a user to the releases of an app, or to the versions
+    /// promoted to one of its channels. Subscribing again returns the
+    /// existing subscription, with the locale updated.
+    pub async fn subscribe(
+        db: &DatabaseConnection,
+        user_id: i32,
+        data: &Subscribe,
+    ) -> Result<Model> {
+        Apps::find_by_id(data.app_id)
+            .one(db)
+            .await?
+            .ok_or_else(|| Error::BadRequest(format!("app {} does not exist", data.app_id)))?;
+        let channel_id = match &data.channel {
+            Some(name) => Some(
+                channels::Entity::find_by_app(db, data.app_id)
+                    .await?
+                    .into_iter()
+                    .find(|channel| &channel.name == name)
+                    .ok_or_else(|| {
+                        Error::BadRequest(format!("app {} has no channel {name}", data.app_id))
+                    })?
+                    .id,
+            ),
+            None => None,
+        };
+        let existing = Entity::find()
+            .filter(Column::UserId.eq(user_id))
+            .filter(Column::AppId.eq(data.app_id))
+            .filter(match channel_id {
+                Some(channel_id) => Column::ChannelId.eq(channel_id),
+                None => Column::ChannelId.is_null(),
+            })
+            .one(db)
+            .await?;
+        if let Some(existing) = existing {
+            let mut item = existing.into_active_model();
+            item.locale = Set(data.locale.clone());
+            return Ok(item.update(db).await?);
+        }
+        let item = Self {
+            user_id: Set(user_id),
+            app_id: Set(data.app_id),
+            channel_id: Set(channel_id),
+            locale: Set(data.locale.clone()),
+            unsubscribe_token: Set(Uuid::new_v4().simple().to_string()),
+            ..Default::default()
+        };
+        Ok(item.insert(db).await?)
+    }
+
+    /// Ends the subscription an unsubscribe link was sent for.
+    pub async fn unsubscribe(db: &DatabaseConnection, token: &str) -> Result<Model> {
+        let item = Entity::find_by_unsubscribe_token(db, token).await?;
+        item.clone().delete(db).await?;
+        Ok(item)
+    }
+}
+
+// implement your custom finders, selectors oriented logic here
+impl Entity {
+    /// Subscriptions of a user, oldest first.
+    pub async fn find_by_user<C>(db: &C, user_id: i32) -> Result<Vec<Model>>
+    where
+        C: ConnectionTrait,
+    {
+        Ok(Self::find()
+            .filter(Column::UserId.eq(user_id))
+            .order_by_asc(Column::Id)
+            .all(db)
+            .await?)
+    }
+
+    /// The subscription an unsubscribe link was sent for.
+    pub async fn find_by_unsubscribe_token<C>(db: &C, token: &str) -> Result<Model>
+    where
+        C: ConnectionTrait,
+    {
+        Self::find()
+            .filter(Column::UnsubscribeToken.eq(token))
+            .one(db)
+            .await?
+            .ok_or_else(|| Error::NotFound)
+    }
+
+    /// Up to `limit` subscribers to an app's releases, or to one of its
+    /// channels, with an id above `after`, by id.
+    pub async fn find_batch<C>(
+        db: &C,
+        app_id: i32,
+        channel_id: Option<i32>,
+        after: i32,
+        limit: u64,
+    ) -> Result<Vec<Model>>
+    where
+        C: ConnectionTrait,
+    {
+        Ok(Self::find()
+            .filter(Column::AppId.eq(app_id))
+            .filter(match channel_id {
+                Some(channel_id) => Column::ChannelId.eq(channel_id),
+                None => Column::ChannelId.is_null(),
+            })
+            .filter(Column::Id.gt(after))
+            .order_by_asc(Column::Id)
+            .limit(limit)
+            .all(db)
+            .await?)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Subscribe {
+    pub app_id: i32,
+    /// Channel whose promotions are announced, the app's releases when
+    /// omitted
+    pub channel: Option<String>,
+    /// Locale release notes are sent in, see `settings.locales`
+    pub locale: Option<String>,
+}
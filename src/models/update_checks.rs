@@ -0,0 +1,179 @@
+use chrono::{Days, NaiveDate, Utc};
+use loco_rs::Result;
+use sea_orm::{
+    entity::prelude::*, sea_query::Query, ActiveValue::Set, FromQueryResult, QueryOrder,
+    QuerySelect,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+pub use super::_entities::update_checks::{ActiveModel, Column, Entity, Model};
+use super::apps::{Revision, UpdateInfo};
+pub type UpdateChecks = Entity;
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(self, _db: &C, insert: bool) -> std::result::Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        if !insert && self.updated_at.is_unchanged() {
+            let mut this = self;
+            this.updated_at = sea_orm::ActiveValue::Set(chrono::Utc::now().into());
+            Ok(this)
+        } else {
+            Ok(self)
+        }
+    }
+}
+
+/// Hash a device is counted by, raw device ids are not kept.
+pub fn device_hash(device_id: &str) -> String {
+    hex::encode(Sha256::digest(device_id))
+}
+
+// implement your read-oriented logic here
+impl Model {}
+
+// implement your write-oriented logic here
+impl ActiveModel {
+    /// Records that a device running `revision` checked for updates and was
+    /// answered `info`.
+    pub async fn record<C>(
+        db: &C,
+        app_id: i32,
+        revision: &Revision,
+        info: &UpdateInfo,
+    ) -> Result<Model>
+    where
+        C: ConnectionTrait,
+    {
+        let item = Self {
+            app_id: Set(app_id),
+            version_code: Set(revision.build_number),
+            offered_version_code: Set(info.latest_version.as_ref().map(|v| v.build_number)),
+            channel: Set(revision.channel.clone()),
+            device_hash: Set(revision.device_id.as_deref().map(device_hash)),
+            sdk_int: Set(revision.sdk_int),
+            abi: Set(revision.abis().first().map(|abi| (*abi).to_string())),
+            checked_on: Set(Utc::now().date_naive()),
+            ..Default::default()
+        };
+        Ok(item.insert(db).await?)
+    }
+}
+
+// implement your custom finders, selectors oriented logic here
+impl Entity {
+    /// Devices that checked in since `since` by the version they reported
+    /// last, newest version first. Devices without an id cannot be told
+    /// apart and are left out.
+    pub async fn active_devices<C>(
+        db: &C,
+        app_id: i32,
+        since: NaiveDate,
+    ) -> Result<Vec<VersionDevices>>
+    where
+        C: ConnectionTrait,
+    {
+        let last_checks = Query::select()
+            .expr(Expr::col(Column::Id).max())
+            .from(Self)
+            .and_where(Column::AppId.eq(app_id))
+            .and_where(Column::CheckedOn.gte(since))
+            .and_where(Column::DeviceHash.is_not_null())
+            .group_by_col(Column::DeviceHash)
+            .to_owned();
+        Ok(Self::find()
+            .select_only()
+            .column(Column::VersionCode)
+            .column_as(Expr::col(Column::Id).count(), "devices")
+            .filter(Column::Id.in_subquery(last_checks))
+            .group_by(Column::VersionCode)
+            .order_by_desc(Column::VersionCode)
+            .into_model::<VersionDevices>()
+            .all(db)
+            .await?)
+    }
+
+    /// Devices that checked in from each version, day by day since `since`,
+    /// with their share of the devices that checked in that day.
+    pub async fn adoption<C>(db: &C, app_id: i32, since: NaiveDate) -> Result<Vec<AdoptionPoint>>
+    where
+        C: ConnectionTrait,
+    {
+        let rows = Self::find()
+            .select_only()
+            .column(Column::CheckedOn)
+            .column(Column::VersionCode)
+            .column_as(Expr::col(Column::DeviceHash).count_distinct(), "devices")
+            .filter(Column::AppId.eq(app_id))
+            .filter(Column::CheckedOn.gte(since))
+            .filter(Column::DeviceHash.is_not_null())
+            .group_by(Column::CheckedOn)
+            .group_by(Column::VersionCode)
+            .order_by_asc(Column::CheckedOn)
+            .order_by_desc(Column::VersionCode)
+            .into_model::<DailyDevices>()
+            .all(db)
+            .await?;
+        Ok(rows
+            .iter()
+            .map(|row| {
+                let total: i64 = rows
+                    .iter()
+                    .filter(|other| other.checked_on == row.checked_on)
+                    .map(|other| other.devices)
+                    .sum();
+                #[allow(clippy::cast_precision_loss)]
+                let share = row.devices as f64 / total as f64;
+                AdoptionPoint {
+                    day: row.checked_on,
+                    version_code: row.version_code,
+                    devices: row.devices,
+                    share,
+                }
+            })
+            .collect())
+    }
+}
+
+#[derive(Debug, Serialize, FromQueryResult)]
+pub struct VersionDevices {
+    pub version_code: i64,
+    pub devices: i64,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct DailyDevices {
+    checked_on: NaiveDate,
+    version_code: i64,
+    devices: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AdoptionPoint {
+    pub day: NaiveDate,
+    pub version_code: i64,
+    pub devices: i64,
+    /// Share of the devices that checked in that day, a device that updated
+    /// during the day counts for both versions
+    pub share: f64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StatsQuery {
+    /// Days to look back, today included, 30 when omitted
+    pub days: Option<u32>,
+}
+
+impl StatsQuery {
+    /// The first day of the window.
+    pub fn since(&self) -> NaiveDate {
+        let days = self.days.unwrap_or(30).max(1);
+        Utc::now()
+            .date_naive()
+            .checked_sub_days(Days::new(u64::from(days - 1)))
+            .unwrap_or(NaiveDate::MIN)
+    }
+}
@@ -0,0 +1,50 @@
+use loco_rs::Result;
+use sea_orm::entity::prelude::*;
+
+pub use super::_entities::version_patches::{ActiveModel, Column, Entity, Model};
+pub type VersionPatches = Entity;
+
+/// Format of the patches we generate, classic `BSDIFF40` as read by
+/// `bspatch`.
+pub const BSDIFF: &str = "bsdiff";
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(self, _db: &C, insert: bool) -> std::result::Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        if !insert && self.updated_at.is_unchanged() {
+            let mut this = self;
+            this.updated_at = sea_orm::ActiveValue::Set(chrono::Utc::now().into());
+            Ok(this)
+        } else {
+            Ok(self)
+        }
+    }
+}
+
+// implement your read-oriented logic here
+impl Model {}
+
+// implement your write-oriented logic here
+impl ActiveModel {}
+
+// implement your custom finders, selectors oriented logic here
+impl Entity {
+    /// The patch turning `from_version_id` into `app_version_id`.
+    pub async fn find_patch<C>(
+        db: &C,
+        from_version_id: i32,
+        app_version_id: i32,
+    ) -> Result<Option<Model>>
+    where
+        C: ConnectionTrait,
+    {
+        Ok(Self::find()
+            .filter(Column::FromVersionId.eq(from_version_id))
+            .filter(Column::AppVersionId.eq(app_version_id))
+            .one(db)
+            .await?)
+    }
+}
@@ -0,0 +1,192 @@
+use chrono::{TimeDelta, Utc};
+use loco_rs::model::query::{paginate, PageResponse, PaginationQuery};
+use loco_rs::Result;
+use sea_orm::{entity::prelude::*, ActiveValue::Set, IntoActiveModel, QueryOrder};
+use serde::{Deserialize, Serialize};
+
+pub use super::_entities::webhook_deliveries::{ActiveModel, Column, Entity, Model};
+use super::webhooks::{Event, Webhooks};
+use crate::settings::WebhookSettings;
+pub type WebhookDeliveries = Entity;
+
+/// Where a delivery stands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeliveryStatus {
+    /// Waiting for its first or next attempt
+    Pending,
+    /// The receiver answered with a 2xx status
+    Delivered,
+    /// Every attempt failed, no more are made
+    Failed,
+}
+
+impl DeliveryStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Delivered => "delivered",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(self, _db: &C, insert: bool) -> std::result::Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        if !insert && self.updated_at.is_unchanged() {
+            let mut this = self;
+            this.updated_at = sea_orm::ActiveValue::Set(chrono::Utc::now().into());
+            Ok(this)
+        } else {
+            Ok(self)
+        }
+    }
+}
+
+/// Answer of the receiver to an attempt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Attempt {
+    /// The receiver answered, successfully or not
+    Responded { status: u16, body: String },
+    /// No answer: the connection failed or timed out
+    Failed(String),
+}
+
+// implement your read-oriented logic here
+impl Model {}
+
+// implement your write-oriented logic here
+impl ActiveModel {
+    /// Queues a delivery of `event` to every webhook subscribed to it for
+    /// the app. Called in the transaction making the change, so nothing is
+    /// announced that did not happen.
+    pub async fn dispatch<C>(
+        db: &C,
+        event: Event,
+        app_id: i32,
+        data: serde_json::Value,
+    ) -> Result<Vec<Model>>
+    where
+        C: ConnectionTrait,
+    {
+        let now = Utc::now().fixed_offset();
+        let payload = serde_json::json!({
+            "event": event,
+            "occurred_at": now,
+            "app_id": app_id,
+            "data": data,
+        });
+        let mut deliveries = vec![];
+        for webhook in Webhooks::find_subscribed(db, app_id, event).await? {
+            let item = Self {
+                webhook_id: Set(webhook.id),
+                event: Set(event.as_str().to_string()),
+                payload: Set(payload.clone()),
+                status: Set(DeliveryStatus::Pending.as_str().to_string()),
+                attempts: Set(0),
+                next_attempt_at: Set(Some(now)),
+                ..Default::default()
+            };
+            deliveries.push(item.insert(db).await?);
+        }
+        Ok(deliveries)
+    }
+
+    /// Takes a due delivery for one attempt, pushing its next attempt past
+    /// `lease` so no other worker picks it up meanwhile. `false` when
+    /// another worker got to it first.
+    pub async fn claim<C>(db: &C, delivery: &Model, lease: TimeDelta) -> Result<bool>
+    where
+        C: ConnectionTrait,
+    {
+        let res = Entity::update_many()
+            .col_expr(
+                Column::NextAttemptAt,
+                Expr::value(Utc::now().fixed_offset() + lease),
+            )
+            .filter(Column::Id.eq(delivery.id))
+            .filter(Column::NextAttemptAt.eq(delivery.next_attempt_at))
+            .exec(db)
+            .await?;
+        Ok(res.rows_affected == 1)
+    }
+
+    /// Records an attempt. Failed attempts are retried with exponential
+    /// backoff until `settings.max_attempts` were made.
+    pub async fn record_attempt<C>(
+        db: &C,
+        delivery: Model,
+        attempt: &Attempt,
+        settings: &WebhookSettings,
+    ) -> Result<Model>
+    where
+        C: ConnectionTrait,
+    {
+        let attempts = delivery.attempts + 1;
+        let now = Utc::now().fixed_offset();
+        let mut item = delivery.into_active_model();
+        item.attempts = Set(attempts);
+        let error = match attempt {
+            Attempt::Responded { status, body } => {
+                item.response_status = Set(i16::try_from(*status).ok());
+                (!(200..300).contains(status)).then(|| body.clone())
+            }
+            Attempt::Failed(err) => {
+                item.response_status = Set(None);
+                Some(err.clone())
+            }
+        };
+        if let Some(error) = error {
+            item.error = Set(Some(error));
+            if attempts >= settings.max_attempts {
+                item.status = Set(DeliveryStatus::Failed.as_str().to_string());
+                item.next_attempt_at = Set(None);
+            } else {
+                item.next_attempt_at = Set(Some(now + settings.backoff(attempts)));
+            }
+        } else {
+            item.status = Set(DeliveryStatus::Delivered.as_str().to_string());
+            item.error = Set(None);
+            item.next_attempt_at = Set(None);
+            item.delivered_at = Set(Some(now));
+        }
+        Ok(item.update(db).await?)
+    }
+}
+
+// implement your custom finders, selectors oriented logic here
+impl Entity {
+    /// Pending deliveries whose next attempt is due at `now`, oldest first.
+    pub async fn find_due<C>(db: &C, now: DateTimeWithTimeZone) -> Result<Vec<Model>>
+    where
+        C: ConnectionTrait,
+    {
+        Ok(Self::find()
+            .filter(Column::Status.eq(DeliveryStatus::Pending.as_str()))
+            .filter(Column::NextAttemptAt.lte(now))
+            .order_by_asc(Column::Id)
+            .all(db)
+            .await?)
+    }
+
+    /// Deliveries of a webhook, most recent first.
+    pub async fn find_by_webhook(
+        db: &DatabaseConnection,
+        webhook_id: i32,
+        pagination: &PaginationQuery,
+    ) -> Result<PageResponse<Model>> {
+        paginate(
+            db,
+            Self::find()
+                .filter(Column::WebhookId.eq(webhook_id))
+                .order_by_desc(Column::Id),
+            None,
+            pagination,
+        )
+        .await
+    }
+}
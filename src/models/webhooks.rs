@@ -0,0 +1,153 @@
+use hmac::{Hmac, Mac};
+use loco_rs::{Error, Result};
+use sea_orm::{entity::prelude::*, ActiveValue::Set, Condition, QueryOrder};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use uuid::Uuid;
+use validator::Validate;
+
+pub use super::_entities::webhooks::{ActiveModel, Column, Entity, Model};
+pub type Webhooks = Entity;
+
+/// Release lifecycle events webhooks subscribe to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Event {
+    #[serde(rename = "version.created")]
+    VersionCreated,
+    /// Published to some or all devices, including by the scheduler
+    #[serde(rename = "version.published")]
+    VersionPublished,
+    /// Withdrawn, rolled back or yanked
+    #[serde(rename = "version.unpublished")]
+    VersionUnpublished,
+    #[serde(rename = "version.deleted")]
+    VersionDeleted,
+}
+
+impl Event {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::VersionCreated => "version.created",
+            Self::VersionPublished => "version.published",
+            Self::VersionUnpublished => "version.unpublished",
+            Self::VersionDeleted => "version.deleted",
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(self, _db: &C, insert: bool) -> std::result::Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        if !insert && self.updated_at.is_unchanged() {
+            let mut this = self;
+            this.updated_at = sea_orm::ActiveValue::Set(chrono::Utc::now().into());
+            Ok(this)
+        } else {
+            Ok(self)
+        }
+    }
+}
+
+/// Signature of a payload, sent as `X-Apkraft-Signature-256: sha256=<hex>`.
+/// Receivers recompute it over the raw body with the webhook's secret.
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("hmac takes keys of any size");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+// implement your read-oriented logic here
+impl Model {
+    /// The events the webhook wants, every event when it lists none.
+    pub fn subscribes_to(&self, event: Event) -> bool {
+        let events: Vec<Event> = serde_json::from_value(self.events.clone()).unwrap_or_default();
+        events.is_empty() || events.contains(&event)
+    }
+}
+
+// implement your write-oriented logic here
+impl ActiveModel {}
+
+// implement your custom finders, selectors oriented logic here
+impl Entity {
+    /// Active webhooks of an app, global ones included, that want `event`.
+    pub async fn find_subscribed<C>(db: &C, app_id: i32, event: Event) -> Result<Vec<Model>>
+    where
+        C: ConnectionTrait,
+    {
+        let webhooks = Self::find()
+            .filter(Column::Active.eq(true))
+            .filter(
+                Condition::any()
+                    .add(Column::AppId.is_null())
+                    .add(Column::AppId.eq(app_id)),
+            )
+            .order_by_asc(Column::Id)
+            .all(db)
+            .await?;
+        Ok(webhooks
+            .into_iter()
+            .filter(|webhook| webhook.subscribes_to(event))
+            .collect())
+    }
+
+    /// Webhooks of an app, or the global ones when `app_id` is `None`.
+    pub async fn find_by_app<C>(db: &C, app_id: Option<i32>) -> Result<Vec<Model>>
+    where
+        C: ConnectionTrait,
+    {
+        let app = match app_id {
+            Some(app_id) => Column::AppId.eq(app_id),
+            None => Column::AppId.is_null(),
+        };
+        Ok(Self::find()
+            .filter(app)
+            .order_by_asc(Column::Id)
+            .all(db)
+            .await?)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Validate)]
+pub struct CreateWebhook {
+    #[validate(url)]
+    pub url: String,
+    /// App whose versions are reported, every app when omitted
+    pub app_id: Option<i32>,
+    /// Events to deliver, every event when empty
+    #[serde(default)]
+    pub events: Vec<Event>,
+    /// Key payloads are signed with, generated when omitted
+    pub secret: Option<String>,
+    pub active: Option<bool>,
+}
+
+impl CreateWebhook {
+    pub fn update(&self, item: &mut ActiveModel) -> Result<()> {
+        if self.secret.as_ref().is_some_and(String::is_empty) {
+            return Err(Error::BadRequest("secret must not be empty".to_string()));
+        }
+        item.url = Set(self.url.clone());
+        item.app_id = Set(self.app_id);
+        item.events = Set(serde_json::json!(self.events));
+        if let Some(secret) = &self.secret {
+            item.secret = Set(secret.clone());
+        } else if item.secret.is_not_set() {
+            item.secret = Set(Uuid::new_v4().simple().to_string());
+        }
+        if let Some(active) = self.active {
+            item.active = Set(active);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WebhookQuery {
+    /// Webhooks of this app, the global ones when omitted
+    pub app_id: Option<i32>,
+}
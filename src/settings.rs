@@ -0,0 +1,112 @@
+//! Typed view of the `settings` section of the configuration.
+use chrono::TimeDelta;
+use loco_rs::{config::Config, Error, Result};
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub patches: PatchSettings,
+    pub locales: LocaleSettings,
+    pub downloads: DownloadSettings,
+    pub webhooks: WebhookSettings,
+    pub announcements: AnnouncementSettings,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct PatchSettings {
+    /// Previously published versions a newly published one is diffed
+    /// against, `0` turns patch generation off
+    pub sources: u64,
+    /// Patches larger than this share of the full file are not worth
+    /// downloading and are dropped
+    pub max_ratio: f64,
+}
+
+impl Default for PatchSettings {
+    fn default() -> Self {
+        Self {
+            sources: 3,
+            max_ratio: 0.8,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct LocaleSettings {
+    /// Locales tried after the ones a client asks for, the untranslated
+    /// text is the last resort
+    pub fallback: Vec<String>,
+}
+
+impl Default for LocaleSettings {
+    fn default() -> Self {
+        Self {
+            fallback: vec!["en".to_string()],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct DownloadSettings {
+    /// Log the network a download came from rather than the client's
+    /// address, see `models::downloads::anonymize`
+    pub anonymize_ip: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct WebhookSettings {
+    /// Attempts made at a delivery before it is given up on
+    pub max_attempts: i32,
+    /// Wait after the first failed attempt, doubled after each further one
+    pub backoff_secs: i64,
+    /// How long a receiver has to answer
+    pub timeout_secs: u64,
+}
+
+impl Default for WebhookSettings {
+    fn default() -> Self {
+        Self {
+            max_attempts: 6,
+            backoff_secs: 30,
+            timeout_secs: 10,
+        }
+    }
+}
+
+impl WebhookSettings {
+    /// Wait before the attempt following the `attempts`th.
+    pub fn backoff(&self, attempts: i32) -> TimeDelta {
+        let exponent = u32::try_from(attempts - 1).unwrap_or(0).min(16);
+        TimeDelta::seconds(self.backoff_secs.saturating_mul(1 << exponent))
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct AnnouncementSettings {
+    /// Subscribers mailed per announcer job
+    pub batch_size: u64,
+}
+
+impl Default for AnnouncementSettings {
+    fn default() -> Self {
+        Self { batch_size: 100 }
+    }
+}
+
+impl Settings {
+    pub fn from_config(config: &Config) -> Result<Self> {
+        config
+            .settings
+            .clone()
+            .map(serde_json::from_value)
+            .transpose()
+            .map_err(|err| Error::Message(format!("invalid settings: {err}")))
+            .map(Option::unwrap_or_default)
+    }
+}
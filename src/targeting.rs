@@ -0,0 +1,216 @@
+//! Targeting rules restricting a version or channel to some devices. Rules
+//! are evaluated against the attributes a client sends with `check-update`,
+//! which are all of its query parameters: `model`, `os_version`, `country`
+//! or whatever the app reports, e.g. a `tenant_id`.
+//!
+//! A rule is JSON with a single key naming the operator:
+//!
+//! ```json
+//! { "all": [
+//!     { "in": { "attribute": "country", "values": ["CN", "HK"] } },
+//!     { "gte": { "attribute": "os_version", "value": "13" } },
+//!     { "not": { "in": { "attribute": "model", "values": ["SM-G960F"] } } }
+//! ] }
+//! ```
+use std::{cmp::Ordering, collections::BTreeMap, convert::Infallible};
+
+use axum::{
+    extract::{FromRequestParts, Query},
+    http::request::Parts,
+};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rule {
+    /// Every rule matches, `{"all": []}` matches any device
+    All(Vec<Rule>),
+    /// At least one rule matches
+    Any(Vec<Rule>),
+    Not(Box<Rule>),
+    /// The attribute is one of `values`, ignoring ASCII case
+    In {
+        attribute: String,
+        values: Vec<Scalar>,
+    },
+    /// The attribute is at least `value`
+    Gte {
+        attribute: String,
+        value: Scalar,
+    },
+    /// The attribute is below `value`
+    Lt {
+        attribute: String,
+        value: Scalar,
+    },
+}
+
+/// A string, number or boolean in a rule, compared as text.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(transparent)]
+pub struct Scalar(pub String);
+
+impl<'de> Deserialize<'de> for Scalar {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match Value::deserialize(deserializer)? {
+            Value::String(s) => Ok(Self(s)),
+            Value::Number(n) => Ok(Self(n.to_string())),
+            Value::Bool(b) => Ok(Self(b.to_string())),
+            other => Err(serde::de::Error::custom(format!(
+                "expected a string, number or boolean, found {other}"
+            ))),
+        }
+    }
+}
+
+/// Attributes of a device, what rules are evaluated against.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "BTreeMap<String, Scalar>")]
+pub struct Attributes(BTreeMap<String, String>);
+
+impl From<BTreeMap<String, Scalar>> for Attributes {
+    fn from(attributes: BTreeMap<String, Scalar>) -> Self {
+        Self(attributes.into_iter().map(|(k, v)| (k, v.0)).collect())
+    }
+}
+
+impl<K: Into<String>, V: Into<String>> FromIterator<(K, V)> for Attributes {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        Self(
+            iter.into_iter()
+                .map(|(k, v)| (k.into(), v.into()))
+                .collect(),
+        )
+    }
+}
+
+impl Attributes {
+    pub fn get(&self, attribute: &str) -> Option<&str> {
+        self.0.get(attribute).map(String::as_str)
+    }
+}
+
+/// How a rule was evaluated, for dry runs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Trace {
+    /// The operator, with its attribute and operands for comparisons
+    pub rule: String,
+    /// The value of the attribute a comparison was made against
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    pub matched: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<Trace>,
+}
+
+impl Rule {
+    /// Whether a device with `attributes` is targeted. Comparisons of an
+    /// attribute the device did not send never match.
+    pub fn matches(&self, attributes: &Attributes) -> bool {
+        match self {
+            Self::All(rules) => rules.iter().all(|rule| rule.matches(attributes)),
+            Self::Any(rules) => rules.iter().any(|rule| rule.matches(attributes)),
+            Self::Not(rule) => !rule.matches(attributes),
+            Self::In { attribute, values } => attributes
+                .get(attribute)
+                .is_some_and(|value| values.iter().any(|v| v.0.eq_ignore_ascii_case(value))),
+            Self::Gte { attribute, value } => attributes
+                .get(attribute)
+                .is_some_and(|actual| compare(actual, &value.0) != Ordering::Less),
+            Self::Lt { attribute, value } => attributes
+                .get(attribute)
+                .is_some_and(|actual| compare(actual, &value.0) == Ordering::Less),
+        }
+    }
+
+    /// Evaluates every rule, without short circuiting, and records how.
+    pub fn trace(&self, attributes: &Attributes) -> Trace {
+        let (rule, value, children) = match self {
+            Self::All(rules) | Self::Any(rules) => {
+                let name = if matches!(self, Self::All(_)) {
+                    "all"
+                } else {
+                    "any"
+                };
+                let children = rules.iter().map(|rule| rule.trace(attributes)).collect();
+                (name.to_string(), None, children)
+            }
+            Self::Not(rule) => ("not".to_string(), None, vec![rule.trace(attributes)]),
+            Self::In { attribute, values } => {
+                let values: Vec<&str> = values.iter().map(|v| v.0.as_str()).collect();
+                let rule = format!("{attribute} in [{}]", values.join(", "));
+                (rule, attributes.get(attribute), vec![])
+            }
+            Self::Gte { attribute, value } => (
+                format!("{attribute} >= {}", value.0),
+                attributes.get(attribute),
+                vec![],
+            ),
+            Self::Lt { attribute, value } => (
+                format!("{attribute} < {}", value.0),
+                attributes.get(attribute),
+                vec![],
+            ),
+        };
+        Trace {
+            rule,
+            value: value.map(ToOwned::to_owned),
+            matched: self.matches(attributes),
+            children,
+        }
+    }
+
+    /// Whether the rule stored in a `targeting` column lets a device in.
+    /// Versions and channels without one target every device, a rule that
+    /// no longer parses none.
+    pub fn admits(targeting: Option<&Value>, attributes: &Attributes) -> bool {
+        targeting.is_none_or(|targeting| {
+            Self::deserialize(targeting).is_ok_and(|rule| rule.matches(attributes))
+        })
+    }
+
+    /// Traces the rule stored in a `targeting` column, `None` when there is
+    /// none.
+    pub fn trace_stored(targeting: Option<&Value>, attributes: &Attributes) -> Option<Trace> {
+        let targeting = targeting?;
+        Some(match Self::deserialize(targeting) {
+            Ok(rule) => rule.trace(attributes),
+            Err(err) => Trace {
+                rule: format!("invalid rule: {err}"),
+                value: None,
+                matched: false,
+                children: vec![],
+            },
+        })
+    }
+}
+
+/// Compares dotted version numbers (`13.4` < `13.10`) numerically, anything
+/// else as text.
+fn compare(a: &str, b: &str) -> Ordering {
+    let parse = |s: &str| {
+        s.split('.')
+            .map(|part| part.trim().parse::<u64>())
+            .collect::<Result<Vec<_>, _>>()
+    };
+    match (parse(a), parse(b)) {
+        (Ok(mut a), Ok(mut b)) => {
+            let len = a.len().max(b.len());
+            a.resize(len, 0);
+            b.resize(len, 0);
+            a.cmp(&b)
+        }
+        _ => a.cmp(b),
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for Attributes {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Infallible> {
+        Ok(Query::<BTreeMap<String, String>>::try_from_uri(&parts.uri)
+            .map(|query| Self(query.0))
+            .unwrap_or_default())
+    }
+}
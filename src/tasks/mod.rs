@@ -1 +1 @@
-
+pub mod publish_scheduled;
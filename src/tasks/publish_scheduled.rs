@@ -0,0 +1,20 @@
+use loco_rs::prelude::*;
+
+use crate::workers::publisher::{PublishWorker, PublishWorkerArgs};
+
+/// Publishes the versions that are due, run every minute by the scheduler.
+pub struct PublishScheduled;
+#[async_trait]
+impl Task for PublishScheduled {
+    fn task(&self) -> TaskInfo {
+        TaskInfo {
+            name: "publish_scheduled".to_string(),
+            detail: "Publish the versions whose scheduled publish time has come".to_string(),
+        }
+    }
+    async fn run(&self, app_context: &AppContext, _vars: &task::Vars) -> Result<()> {
+        PublishWorker::build(app_context)
+            .perform(PublishWorkerArgs {})
+            .await
+    }
+}
@@ -0,0 +1,57 @@
+use serde::Serialize;
+
+use crate::models::_entities::{app_versions, apps};
+
+/// The `manifest.plist` iOS downloads when following an
+/// `itms-services://?action=download-manifest` link.
+#[derive(Debug, Serialize)]
+pub struct InstallManifest {
+    pub items: Vec<InstallItem>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct InstallItem {
+    pub assets: Vec<InstallAsset>,
+    pub metadata: InstallMetadata,
+}
+
+#[derive(Debug, Serialize)]
+pub struct InstallAsset {
+    pub kind: &'static str,
+    pub url: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct InstallMetadata {
+    pub bundle_identifier: String,
+    pub bundle_version: String,
+    pub kind: &'static str,
+    pub title: String,
+}
+
+impl InstallManifest {
+    /// Manifest installing the ipa served at `ipa_url`.
+    pub fn new(app: &apps::Model, version: &app_versions::Model, ipa_url: String) -> Self {
+        Self {
+            items: vec![InstallItem {
+                assets: vec![InstallAsset {
+                    kind: "software-package",
+                    url: ipa_url,
+                }],
+                metadata: InstallMetadata {
+                    bundle_identifier: app.bundle_id.clone(),
+                    bundle_version: version.version_name.clone(),
+                    kind: "software",
+                    title: app.name.clone(),
+                },
+            }],
+        }
+    }
+
+    pub fn to_xml(&self) -> Result<Vec<u8>, plist::Error> {
+        let mut xml = vec![];
+        plist::to_writer_xml(&mut xml, self)?;
+        Ok(xml)
+    }
+}
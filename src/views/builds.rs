@@ -0,0 +1,25 @@
+use serde::Serialize;
+
+use crate::models::{
+    _entities::{app_version_artifacts, app_versions, apps, files},
+    builds::Build,
+};
+
+#[derive(Debug, Serialize)]
+pub struct BuildResponse {
+    pub app: apps::Model,
+    pub file: files::Model,
+    pub version: app_versions::Model,
+    pub artifacts: Vec<app_version_artifacts::Model>,
+}
+
+impl From<Build> for BuildResponse {
+    fn from(build: Build) -> Self {
+        Self {
+            app: build.app,
+            file: build.file,
+            version: build.version,
+            artifacts: build.artifacts,
+        }
+    }
+}
@@ -1,5 +1,8 @@
+pub mod app_versions;
 pub mod apps;
 pub mod auth;
+pub mod builds;
+pub mod webhooks;
 
 pub mod api_response;
 pub mod api_result;
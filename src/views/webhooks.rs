@@ -0,0 +1,21 @@
+use serde::Serialize;
+
+use crate::models::_entities::webhooks;
+
+/// A webhook as returned by its creation, the only response carrying its
+/// secret.
+#[derive(Debug, Serialize)]
+pub struct CreatedWebhook {
+    #[serde(flatten)]
+    pub webhook: webhooks::Model,
+    pub secret: String,
+}
+
+impl From<webhooks::Model> for CreatedWebhook {
+    fn from(webhook: webhooks::Model) -> Self {
+        Self {
+            secret: webhook.secret.clone(),
+            webhook,
+        }
+    }
+}
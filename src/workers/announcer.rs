@@ -0,0 +1,146 @@
+use std::collections::HashMap;
+
+use loco_rs::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    artifacts::ArtifactType,
+    locales::Locales,
+    mailers::release::{Announcement, ReleaseMailer},
+    models::{
+        app_versions::{self, AppVersions},
+        apps::{Apps, ChangelogEntry},
+        channels,
+        files::Files,
+        subscriptions::Subscriptions,
+        users,
+    },
+    settings::Settings,
+};
+
+/// Emails the subscribers of an app when a version is released, or of a
+/// channel when a version is promoted to it, one batch of subscribers per
+/// job.
+pub struct AnnounceWorker {
+    pub ctx: AppContext,
+}
+
+#[derive(Deserialize, Debug, Serialize)]
+pub struct AnnounceWorkerArgs {
+    pub version_id: i32,
+    /// Channel the version was promoted to, `None` for a release
+    pub channel: Option<String>,
+    /// Subscriptions up to this id were mailed by earlier batches
+    #[serde(default)]
+    pub after: i32,
+}
+
+#[async_trait]
+impl BackgroundWorker<AnnounceWorkerArgs> for AnnounceWorker {
+    fn build(ctx: &AppContext) -> Self {
+        Self { ctx: ctx.clone() }
+    }
+
+    async fn perform(&self, args: AnnounceWorkerArgs) -> Result<()> {
+        let db = &self.ctx.db;
+        let settings = Settings::from_config(&self.ctx.config)?;
+        let Some(version) = AppVersions::find_by_id(args.version_id).one(db).await? else {
+            return Ok(());
+        };
+        // withdrawn before the announcement went out
+        if version.yanked_at.is_some() || (args.channel.is_none() && version.published_at.is_none())
+        {
+            return Ok(());
+        }
+        let app = Apps::find_by_id(version.app_id)
+            .one(db)
+            .await?
+            .ok_or_else(|| Error::NotFound)?;
+        let channel_id = match &args.channel {
+            Some(name) => {
+                let channel = channels::Entity::find_by_app(db, app.id)
+                    .await?
+                    .into_iter()
+                    .find(|channel| &channel.name == name);
+                let Some(channel) = channel else {
+                    return Ok(());
+                };
+                Some(channel.id)
+            }
+            None => None,
+        };
+
+        let batch_size = settings.announcements.batch_size;
+        let batch =
+            Subscriptions::find_batch(db, app.id, channel_id, args.after, batch_size).await?;
+        let user_ids: Vec<i32> = batch.iter().map(|s| s.user_id).collect();
+        let users: HashMap<i32, users::Model> = users::Entity::find()
+            .filter(users::users::Column::Id.is_in(user_ids))
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|user| (user.id, user))
+            .collect();
+        let download_path = download_path(&self.ctx, &version).await?;
+        for subscription in &batch {
+            let Some(user) = users.get(&subscription.user_id) else {
+                continue;
+            };
+            let locales = Locales::new(subscription.locale.iter(), &settings.locales.fallback);
+            let announced = Announcement {
+                app: &app,
+                version: &version,
+                channel: args.channel.as_deref(),
+                release_notes: ChangelogEntry::new(&version, &locales).release_notes,
+                download_path: download_path.clone(),
+            };
+            // one bad address must not keep the others from hearing about it
+            if let Err(err) =
+                ReleaseMailer::send_announcement(&self.ctx, user, subscription, &announced).await
+            {
+                tracing::warn!(
+                    subscription = subscription.id,
+                    err = err.to_string(),
+                    "failed to send release announcement"
+                );
+            }
+        }
+
+        match batch.last() {
+            Some(last) if batch.len() as u64 == batch_size => {
+                let next = AnnounceWorkerArgs {
+                    after: last.id,
+                    ..args
+                };
+                Self::perform_later(&self.ctx, next).await
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl AnnounceWorker {
+    /// Queues the announcement of a version released, or promoted to
+    /// `channel`.
+    pub async fn enqueue(ctx: &AppContext, version_id: i32, channel: Option<String>) -> Result<()> {
+        let args = AnnounceWorkerArgs {
+            version_id,
+            channel,
+            after: 0,
+        };
+        Self::perform_later(ctx, args).await
+    }
+}
+
+/// Where the version is downloaded: the file itself, or the installation
+/// link for an ipa, which cannot be installed from a plain download.
+async fn download_path(ctx: &AppContext, version: &app_versions::Model) -> Result<String> {
+    let file = match version.apk_file_id {
+        Some(file_id) => Files::find_by_id(file_id).one(&ctx.db).await?,
+        None => None,
+    };
+    Ok(match file {
+        Some(file) if file.artifact_type() != Some(ArtifactType::Ipa) => file.static_path(),
+        _ => format!("/api/app-versions/{}/install", version.id),
+    })
+}
@@ -1 +1,44 @@
+pub mod announcer;
 pub mod downloader;
+pub mod patcher;
+pub mod publisher;
+pub mod webhook;
+
+use loco_rs::{app::AppContext, Error, Result};
+use sea_orm::EntityTrait;
+
+use self::{announcer::AnnounceWorker, patcher::PatchWorker, webhook::WebhookWorker};
+use crate::models::{
+    app_versions::{self, AppVersions, RolloutState},
+    channels::STABLE,
+};
+
+/// Queues what follows a version going live: patches when it reached
+/// stable, announced to everyone once its rollout is complete, an
+/// announcement to the subscribers of `channel` otherwise, and delivery of
+/// the events just dispatched. Every publish path goes through here.
+pub async fn after_publish(ctx: &AppContext, version_id: i32, channel: Option<&str>) -> Result<()> {
+    match channel.filter(|&channel| channel != STABLE) {
+        None => {
+            PatchWorker::enqueue(ctx, version_id).await?;
+            let version = AppVersions::find_by_id(version_id)
+                .one(&ctx.db)
+                .await?
+                .ok_or(Error::NotFound)?;
+            after_rollout(ctx, &version).await?;
+        }
+        Some(channel) => {
+            AnnounceWorker::enqueue(ctx, version_id, Some(channel.to_string())).await?;
+        }
+    }
+    WebhookWorker::enqueue(ctx).await
+}
+
+/// Queues the announcement of a version to everyone once its rollout
+/// reached every device, whether it was published at 100% or raised to it.
+pub async fn after_rollout(ctx: &AppContext, version: &app_versions::Model) -> Result<()> {
+    if version.rollout_state() == Some(RolloutState::Completed) {
+        AnnounceWorker::enqueue(ctx, version.id, None).await?;
+    }
+    Ok(())
+}
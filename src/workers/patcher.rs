@@ -0,0 +1,167 @@
+use std::{path::Path, sync::Arc};
+
+use loco_rs::prelude::*;
+use qbsdiff::Bsdiff;
+use sea_orm::{QueryOrder, QuerySelect};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    models::{
+        _entities::{app_versions, files},
+        app_versions::RolloutState,
+        files::remove_stored,
+        version_patches::{self, VersionPatches, BSDIFF},
+    },
+    settings::Settings,
+};
+
+/// Generates patches from the previously published versions of an app to a
+/// newly published one.
+pub struct PatchWorker {
+    pub ctx: AppContext,
+}
+
+#[derive(Deserialize, Debug, Serialize)]
+pub struct PatchWorkerArgs {
+    pub version_id: i32,
+}
+
+#[async_trait]
+impl BackgroundWorker<PatchWorkerArgs> for PatchWorker {
+    fn build(ctx: &AppContext) -> Self {
+        Self { ctx: ctx.clone() }
+    }
+
+    async fn perform(&self, args: PatchWorkerArgs) -> Result<()> {
+        let settings = Settings::from_config(&self.ctx.config)?.patches;
+        if settings.sources == 0 {
+            return Ok(());
+        }
+        let db = &self.ctx.db;
+        let version = app_versions::Entity::find_by_id(args.version_id)
+            .one(db)
+            .await?
+            .ok_or(Error::NotFound)?;
+        let Some(target) = main_file(db, &version).await? else {
+            return Ok(());
+        };
+
+        // only devices on an older release that reached everyone patch up
+        let sources = app_versions::Entity::find()
+            .filter(app_versions::Column::AppId.eq(version.app_id))
+            .filter(app_versions::Column::VersionCode.lt(version.version_code))
+            .filter(app_versions::Column::PublishedAt.is_not_null())
+            .filter(app_versions::Column::RolloutState.eq(RolloutState::Completed.as_str()))
+            .order_by_desc(app_versions::Column::PublishedAt)
+            .limit(settings.sources)
+            .all(db)
+            .await?;
+        let mut target_bytes = None;
+        for source in sources {
+            if VersionPatches::find_patch(db, source.id, version.id)
+                .await?
+                .is_some()
+            {
+                continue;
+            }
+            let Some(source_file) = main_file(db, &source).await? else {
+                continue;
+            };
+            if source_file.checksum_sha256 == target.checksum_sha256 {
+                continue;
+            }
+
+            let target_bytes = match &target_bytes {
+                Some(bytes) => Arc::clone(bytes),
+                None => {
+                    let bytes: Vec<u8> = self.ctx.storage.download(Path::new(&target.path)).await?;
+                    Arc::clone(target_bytes.insert(Arc::new(bytes)))
+                }
+            };
+            let source_bytes: Vec<u8> = self
+                .ctx
+                .storage
+                .download(Path::new(&source_file.path))
+                .await?;
+            let patch = diff(source_bytes, target_bytes).await?;
+            #[allow(clippy::cast_precision_loss)]
+            if patch.len() as f64 > target.size_bytes as f64 * settings.max_ratio {
+                tracing::info!(
+                    from = source.id,
+                    to = version.id,
+                    size = patch.len(),
+                    "patch is not worth it, dropped"
+                );
+                continue;
+            }
+            self.insert_patch(&source, &version, patch).await?;
+        }
+        Ok(())
+    }
+}
+
+impl PatchWorker {
+    /// Queues patch generation for a version that was just published.
+    pub async fn enqueue(ctx: &AppContext, version_id: i32) -> Result<()> {
+        Self::perform_later(ctx, PatchWorkerArgs { version_id }).await
+    }
+
+    async fn insert_patch(
+        &self,
+        source: &app_versions::Model,
+        version: &app_versions::Model,
+        patch: Vec<u8>,
+    ) -> Result<()> {
+        let mut file = files::ActiveModel {
+            ..Default::default()
+        };
+        let key = file
+            .store(
+                &self.ctx.storage,
+                format!("{}-{}.patch", source.version_code, version.version_code),
+                "application/octet-stream".to_string(),
+                &patch.into(),
+            )
+            .await?;
+
+        let inserted = async {
+            let file = file.insert(&self.ctx.db).await?;
+            version_patches::ActiveModel {
+                app_version_id: Set(version.id),
+                from_version_id: Set(source.id),
+                file_id: Set(file.id),
+                algorithm: Set(BSDIFF.to_string()),
+                ..Default::default()
+            }
+            .insert(&self.ctx.db)
+            .await
+        };
+        if let Err(err) = inserted.await {
+            remove_stored(&self.ctx.storage, &[key]).await;
+            return Err(err.into());
+        }
+        Ok(())
+    }
+}
+
+/// The file devices without split artifacts install.
+async fn main_file<C>(db: &C, version: &app_versions::Model) -> Result<Option<files::Model>>
+where
+    C: ConnectionTrait,
+{
+    let Some(file_id) = version.apk_file_id else {
+        return Ok(None);
+    };
+    Ok(files::Entity::find_by_id(file_id).one(db).await?)
+}
+
+/// Computes a `BSDIFF40` patch on the blocking pool, diffing is CPU bound.
+pub async fn diff(source: Vec<u8>, target: Arc<Vec<u8>>) -> Result<Vec<u8>> {
+    let patch = tokio::task::spawn_blocking(move || {
+        let mut patch = vec![];
+        Bsdiff::new(&source, &target).compare(&mut patch)?;
+        Ok::<_, std::io::Error>(patch)
+    })
+    .await??;
+    Ok(patch)
+}
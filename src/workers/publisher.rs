@@ -0,0 +1,51 @@
+use loco_rs::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::models::app_versions::{self, AppVersions, PublishPayload};
+
+/// Publishes the versions whose scheduled publish time has come.
+pub struct PublishWorker {
+    pub ctx: AppContext,
+}
+
+#[derive(Deserialize, Debug, Serialize)]
+pub struct PublishWorkerArgs {}
+
+#[async_trait]
+impl BackgroundWorker<PublishWorkerArgs> for PublishWorker {
+    fn build(ctx: &AppContext) -> Self {
+        Self { ctx: ctx.clone() }
+    }
+
+    async fn perform(&self, _args: PublishWorkerArgs) -> Result<()> {
+        let due = AppVersions::find_due(&self.ctx.db, chrono::Utc::now().fixed_offset()).await?;
+        for version in due {
+            // one version failing to go live must not hold back the others,
+            // its schedule was cleared by the claim so it is not retried
+            if let Err(err) = self.publish(&version).await {
+                tracing::error!(
+                    version = version.id,
+                    err = err.to_string(),
+                    "failed to publish scheduled version"
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+impl PublishWorker {
+    async fn publish(&self, version: &app_versions::Model) -> Result<()> {
+        // overlapping runs see the same due versions, only one publishes each
+        if !app_versions::ActiveModel::claim_scheduled(&self.ctx.db, version).await? {
+            return Ok(());
+        }
+        let payload = PublishPayload {
+            publish: true,
+            rollout_percentage: version.scheduled_rollout_percentage,
+        };
+        app_versions::ActiveModel::publish(&self.ctx.db, version.id, &payload).await?;
+        tracing::info!(version = version.id, "published scheduled version");
+        super::after_publish(&self.ctx, version.id, None).await
+    }
+}
@@ -0,0 +1,165 @@
+//! Webhook delivery.
+//!
+//! Every attempt at a delivery is a job on the background queue. A failed
+//! attempt is queued again once its backoff has passed: the queue cannot
+//! delay a job, so the wait happens in a detached task rather than in a
+//! worker, or in the request itself in `ForegroundBlocking` mode. Retries
+//! waiting when the process stops are lost with it; their deliveries stay
+//! pending and are queued with the next events dispatched.
+use std::time::Duration;
+
+use chrono::{DateTime, FixedOffset, TimeDelta, Utc};
+use loco_rs::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    models::{
+        webhook_deliveries::{self, Attempt, DeliveryStatus, WebhookDeliveries},
+        webhooks::{self, Webhooks},
+    },
+    settings::{Settings, WebhookSettings},
+};
+
+/// Longest part of a receiver's answer kept with a failed delivery.
+const MAX_ERROR_LEN: usize = 1024;
+
+/// Makes one attempt at a webhook delivery, queueing the next one when it
+/// fails.
+pub struct WebhookWorker {
+    pub ctx: AppContext,
+}
+
+#[derive(Deserialize, Debug, Serialize)]
+pub struct WebhookWorkerArgs {
+    pub delivery_id: i32,
+}
+
+#[async_trait]
+impl BackgroundWorker<WebhookWorkerArgs> for WebhookWorker {
+    fn build(ctx: &AppContext) -> Self {
+        Self { ctx: ctx.clone() }
+    }
+
+    async fn perform(&self, args: WebhookWorkerArgs) -> Result<()> {
+        let settings = Settings::from_config(&self.ctx.config)?.webhooks;
+        let Some(delivery) = WebhookDeliveries::find_by_id(args.delivery_id)
+            .one(&self.ctx.db)
+            .await?
+        else {
+            return Ok(());
+        };
+        // a delivery queued twice is attempted by the job that gets to it
+        // first, the other one finds it done or not due yet
+        let now = Utc::now().fixed_offset();
+        let due = delivery.status == DeliveryStatus::Pending.as_str()
+            && delivery.next_attempt_at.is_some_and(|at| at <= now);
+        // an attempt in flight is not retried before it had time to time out
+        let lease = TimeDelta::seconds(i64::try_from(settings.timeout_secs * 2).unwrap_or(60));
+        if !due || !webhook_deliveries::ActiveModel::claim(&self.ctx.db, &delivery, lease).await? {
+            return Ok(());
+        }
+        let delivery = self.deliver(delivery, &settings).await?;
+        if let Some(at) = delivery.next_attempt_at {
+            self.retry_at(delivery.id, at);
+        }
+        Ok(())
+    }
+}
+
+impl WebhookWorker {
+    /// Queues an attempt at every delivery that is due, the events just
+    /// dispatched among them.
+    pub async fn enqueue(ctx: &AppContext) -> Result<()> {
+        let due = WebhookDeliveries::find_due(&ctx.db, Utc::now().fixed_offset()).await?;
+        for delivery in due {
+            Self::perform_later(
+                ctx,
+                WebhookWorkerArgs {
+                    delivery_id: delivery.id,
+                },
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Queues the next attempt at a delivery once `at` has come.
+    fn retry_at(&self, delivery_id: i32, at: DateTime<FixedOffset>) {
+        let ctx = self.ctx.clone();
+        let wait = (at - Utc::now().fixed_offset())
+            .to_std()
+            .unwrap_or_default();
+        tokio::spawn(async move {
+            tokio::time::sleep(wait).await;
+            if let Err(err) = Self::perform_later(&ctx, WebhookWorkerArgs { delivery_id }).await {
+                tracing::error!(
+                    delivery = delivery_id,
+                    err = err.to_string(),
+                    "failed to queue webhook retry"
+                );
+            }
+        });
+    }
+
+    async fn deliver(
+        &self,
+        delivery: webhook_deliveries::Model,
+        settings: &WebhookSettings,
+    ) -> Result<webhook_deliveries::Model> {
+        let webhook = Webhooks::find_by_id(delivery.webhook_id)
+            .one(&self.ctx.db)
+            .await?
+            .ok_or_else(|| Error::NotFound)?;
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(settings.timeout_secs))
+            .build()
+            .map_err(Error::wrap)?;
+        let attempt = send(&client, &webhook, &delivery).await;
+        if let Attempt::Failed(err) = &attempt {
+            tracing::warn!(
+                delivery = delivery.id,
+                webhook = webhook.id,
+                err,
+                "webhook delivery failed"
+            );
+        }
+        webhook_deliveries::ActiveModel::record_attempt(&self.ctx.db, delivery, &attempt, settings)
+            .await
+    }
+}
+
+/// Posts the payload, signed with the webhook's secret.
+async fn send(
+    client: &reqwest::Client,
+    webhook: &webhooks::Model,
+    delivery: &webhook_deliveries::Model,
+) -> Attempt {
+    let body = delivery.payload.to_string();
+    let res = client
+        .post(&webhook.url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header("X-Apkraft-Event", &delivery.event)
+        .header("X-Apkraft-Delivery", delivery.id)
+        .header(
+            "X-Apkraft-Signature-256",
+            webhooks::sign(&webhook.secret, body.as_bytes()),
+        )
+        .body(body)
+        .send()
+        .await;
+    match res {
+        Ok(res) => {
+            let status = res.status().as_u16();
+            let mut body = res.text().await.unwrap_or_default();
+            if body.len() > MAX_ERROR_LEN {
+                let end = (0..=MAX_ERROR_LEN)
+                    .rev()
+                    .find(|&i| body.is_char_boundary(i))
+                    .unwrap_or(0);
+                body.truncate(end);
+            }
+            Attempt::Responded { status, body }
+        }
+        Err(err) => Attempt::Failed(err.to_string()),
+    }
+}
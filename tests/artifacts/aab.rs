@@ -0,0 +1,80 @@
+use apkraft::artifacts::{aab, apk, proto, ArtifactType};
+
+use super::fixtures;
+
+fn bundle() -> Vec<u8> {
+    let manifest = fixtures::proto_xml(&fixtures::manifest("com.example.app", 42, "1.2.0"));
+    fixtures::zip(&[
+        (aab::BUNDLE_CONFIG_PATH, &fixtures::bundle_config("1.17.2")),
+        (aab::MANIFEST_PATH, &manifest),
+        ("base/dex/classes.dex", b"dex\n035\0"),
+    ])
+}
+
+#[test]
+fn can_decode_proto_manifest() {
+    let bytes = fixtures::proto_xml(&fixtures::manifest("com.example.app", 42, "1.2.0"));
+    let root = proto::parse_xml(&bytes).unwrap();
+
+    assert_eq!(root.name, "manifest");
+    assert_eq!(
+        root.attr("package").and_then(|v| v.as_str()),
+        Some("com.example.app")
+    );
+    assert_eq!(
+        root.android_attr("versionCode").and_then(|v| v.as_i64()),
+        Some(42)
+    );
+    assert_eq!(root.children.len(), 3);
+}
+
+#[test]
+fn can_inspect_bundle() {
+    let meta = aab::inspect(&bundle()).unwrap();
+
+    assert_eq!(meta.artifact_type, ArtifactType::Aab);
+    assert_eq!(meta.package_name, "com.example.app");
+    assert_eq!(meta.version_code, 42);
+    assert_eq!(meta.version_name.as_deref(), Some("1.2.0"));
+    assert_eq!(meta.min_sdk_version, Some(21));
+    assert_eq!(meta.permissions, vec!["android.permission.INTERNET"]);
+    assert!(meta.signing.is_none());
+}
+
+#[test]
+fn reads_bundle_compatibility_requirements() {
+    use fixtures::{android, element, Value};
+
+    let mut manifest = fixtures::manifest("com.example.app", 42, "1.2.0");
+    manifest.children.push(element(
+        "uses-feature",
+        vec![
+            android("name", Value::Str("android.software.leanback")),
+            android("required", Value::Bool(false)),
+        ],
+        vec![],
+    ));
+    let bytes = fixtures::zip(&[
+        (aab::BUNDLE_CONFIG_PATH, &fixtures::bundle_config("1.17.2")),
+        (aab::MANIFEST_PATH, &fixtures::proto_xml(&manifest)),
+        ("base/lib/armeabi-v7a/libnative.so", b""),
+    ]);
+
+    let meta = aab::inspect(&bytes).unwrap();
+    assert_eq!(meta.native_abis, ["armeabi-v7a"]);
+    assert!(meta.required_features.is_empty());
+}
+
+#[test]
+fn detects_artifact_type_by_content() {
+    let manifest = fixtures::axml(&fixtures::manifest("com.example.app", 42, "1.2.0"));
+    let apk = fixtures::zip(&[(apk::MANIFEST_PATH, &manifest)]);
+
+    assert_eq!(ArtifactType::detect(&bundle()), Some(ArtifactType::Aab));
+    assert_eq!(ArtifactType::detect(&apk), Some(ArtifactType::Apk));
+    assert_eq!(ArtifactType::detect(b"not a zip"), None);
+    assert_eq!(
+        ArtifactType::detect(&fixtures::zip(&[("readme.txt", b"hello")])),
+        None
+    );
+}
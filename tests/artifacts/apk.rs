@@ -0,0 +1,195 @@
+use apkraft::artifacts::{apk, axml, signing, ArtifactError};
+
+use super::fixtures;
+
+fn unsigned_apk() -> Vec<u8> {
+    let manifest = fixtures::axml(&fixtures::manifest("com.example.app", 42, "1.2.0"));
+    fixtures::zip(&[
+        (apk::MANIFEST_PATH, &manifest),
+        ("classes.dex", b"dex\n035\0"),
+    ])
+}
+
+#[test]
+fn can_decode_binary_manifest() {
+    let bytes = fixtures::axml(&fixtures::manifest("com.example.app", 42, "1.2.0"));
+    let root = axml::parse(&bytes).unwrap();
+
+    assert_eq!(root.name, "manifest");
+    assert_eq!(
+        root.attr("package").and_then(|v| v.as_str()),
+        Some("com.example.app")
+    );
+    assert_eq!(
+        root.android_attr("versionCode").and_then(|v| v.as_i64()),
+        Some(42)
+    );
+    assert_eq!(root.children.len(), 3);
+}
+
+#[test]
+fn can_inspect_apk() {
+    let meta = apk::inspect(&fixtures::sign_v2(&unsigned_apk())).unwrap();
+
+    assert_eq!(meta.package_name, "com.example.app");
+    assert_eq!(meta.version_code, 42);
+    assert_eq!(meta.version_name.as_deref(), Some("1.2.0"));
+    assert_eq!(meta.min_sdk_version, Some(21));
+    assert_eq!(meta.target_sdk_version, Some(34));
+    assert_eq!(meta.permissions, vec!["android.permission.INTERNET"]);
+}
+
+#[test]
+fn can_extract_label_and_icon() {
+    use fixtures::{android, Resource, Value};
+
+    let mut manifest = fixtures::manifest("com.example.app", 42, "1.2.0");
+    manifest.children.last_mut().unwrap().attrs = vec![
+        android("label", Value::Ref(0x7f01_0000)),
+        android("icon", Value::Ref(0x7f02_0000)),
+    ];
+    let icon = |density, value| Resource {
+        id: 0x7f02_0000,
+        language: "",
+        density,
+        value,
+    };
+    let resources = fixtures::resource_table(&[
+        Resource {
+            id: 0x7f01_0000,
+            language: "de",
+            density: 0,
+            value: "Beispiel",
+        },
+        Resource {
+            id: 0x7f01_0000,
+            language: "",
+            density: 0,
+            value: "Example",
+        },
+        icon(0, "res/mipmap/ic_launcher.png"),
+        icon(640, "res/mipmap-xxxhdpi-v4/ic_launcher.webp"),
+        icon(0xfffe, "res/mipmap-anydpi-v26/ic_launcher.xml"),
+    ]);
+    let apk = fixtures::sign_v2(&fixtures::zip(&[
+        (apk::MANIFEST_PATH, &fixtures::axml(&manifest)),
+        (apk::RESOURCES_PATH, &resources),
+        ("res/mipmap/ic_launcher.png", b"mdpi"),
+        ("res/mipmap-xxxhdpi-v4/ic_launcher.webp", b"xxxhdpi"),
+    ]));
+
+    let meta = apk::inspect(&apk).unwrap();
+    assert_eq!(meta.label.as_deref(), Some("Example"));
+    let icon = meta.icon.unwrap();
+    assert_eq!(icon.name, "ic_launcher.webp");
+    assert_eq!(icon.mime, "image/webp");
+    assert_eq!(icon.bytes, b"xxxhdpi");
+}
+
+#[test]
+fn reads_compatibility_requirements() {
+    use fixtures::{android, element, Value};
+
+    let mut manifest = fixtures::manifest("com.example.app", 42, "1.2.0");
+    manifest.children[0]
+        .attrs
+        .push(android("maxSdkVersion", Value::Int(33)));
+    manifest.children.extend([
+        element(
+            "uses-feature",
+            vec![android("name", Value::Str("android.hardware.type.watch"))],
+            vec![],
+        ),
+        element(
+            "uses-feature",
+            vec![
+                android("name", Value::Str("android.hardware.camera")),
+                android("required", Value::Bool(false)),
+            ],
+            vec![],
+        ),
+        element(
+            "uses-feature",
+            vec![android("glEsVersion", Value::Int(0x30000))],
+            vec![],
+        ),
+    ]);
+    let apk = fixtures::sign_v2(&fixtures::zip(&[
+        (apk::MANIFEST_PATH, &fixtures::axml(&manifest)),
+        ("lib/x86_64/libnative.so", b""),
+        ("lib/arm64-v8a/libnative.so", b""),
+        ("lib/arm64-v8a/libother.so", b""),
+        ("assets/lib/armeabi/readme.txt", b""),
+    ]));
+
+    let meta = apk::inspect(&apk).unwrap();
+    assert_eq!(meta.min_sdk_version, Some(21));
+    assert_eq!(meta.max_sdk_version, Some(33));
+    assert_eq!(meta.native_abis, ["arm64-v8a", "x86_64"]);
+    assert_eq!(meta.required_features, ["android.hardware.type.watch"]);
+}
+
+#[test]
+fn rejects_apk_without_manifest() {
+    let apk = fixtures::zip(&[("classes.dex", b"dex\n035\0")]);
+    assert!(apk::inspect(&apk).is_err());
+}
+
+#[test]
+fn rejects_unsigned_apk() {
+    assert!(signing::verify(&unsigned_apk()).is_err());
+}
+
+#[test]
+fn can_verify_v1_signature() {
+    let info = signing::verify(fixtures::V1_SIGNED_APK).unwrap();
+    assert_eq!(info.schemes, vec![1]);
+    assert_eq!(info.signers, vec![fixtures::CERT_FINGERPRINT]);
+    assert!(info.lineage.is_empty());
+}
+
+#[test]
+fn can_verify_v2_signature() {
+    let info = signing::verify(&fixtures::sign_v2(&unsigned_apk())).unwrap();
+    assert_eq!(info.schemes, vec![2]);
+    assert_eq!(info.signers, vec![fixtures::CERT_FINGERPRINT]);
+    assert_eq!(
+        signing::fingerprint(fixtures::CERT),
+        fixtures::CERT_FINGERPRINT
+    );
+}
+
+#[test]
+fn rejects_apk_modified_after_v2_signing() {
+    let mut apk = fixtures::sign_v2(&unsigned_apk());
+    // flip a byte inside the first local file entry
+    apk[40] ^= 0xff;
+    assert!(signing::verify(&apk).is_err());
+}
+
+#[test]
+fn rejects_signing_block_with_invalid_size() {
+    let unsigned = unsigned_apk();
+    let eocd_offset = unsigned.len() - 22;
+    let cd_offset = u32::from_le_bytes(
+        unsigned[eocd_offset + 16..eocd_offset + 20]
+            .try_into()
+            .unwrap(),
+    ) as usize;
+
+    // a size of 16 makes both size fields the same 8 bytes
+    let mut block = 16u64.to_le_bytes().to_vec();
+    block.extend(b"APK Sig Block 42");
+    let mut eocd = unsigned[eocd_offset..].to_vec();
+    eocd[16..20].copy_from_slice(&((cd_offset + block.len()) as u32).to_le_bytes());
+
+    let mut apk = unsigned[..cd_offset].to_vec();
+    apk.extend(block);
+    apk.extend_from_slice(&unsigned[cd_offset..eocd_offset]);
+    apk.extend(eocd);
+
+    assert!(matches!(
+        signing::verify(&apk),
+        Err(ArtifactError::Malformed(_))
+    ));
+}
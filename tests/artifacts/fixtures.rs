@@ -0,0 +1,439 @@
+//! Builders for the binary formats the inspectors read, so tests do not need
+//! to ship real packages.
+use std::io::{Cursor, Write};
+
+use zip::{write::SimpleFileOptions, ZipWriter};
+
+pub const ANDROID_NS: &str = "http://schemas.android.com/apk/res/android";
+
+const TYPE_REFERENCE: u8 = 0x01;
+const TYPE_STRING: u8 = 0x03;
+const TYPE_INT_DEC: u8 = 0x10;
+const TYPE_INT_BOOLEAN: u8 = 0x12;
+
+pub enum Value {
+    Str(&'static str),
+    Int(u32),
+    Ref(u32),
+    Bool(bool),
+}
+
+pub struct Attr {
+    pub android: bool,
+    pub name: &'static str,
+    pub value: Value,
+}
+
+pub fn android(name: &'static str, value: Value) -> Attr {
+    Attr {
+        android: true,
+        name,
+        value,
+    }
+}
+
+pub fn plain(name: &'static str, value: Value) -> Attr {
+    Attr {
+        android: false,
+        name,
+        value,
+    }
+}
+
+pub struct Element {
+    pub name: &'static str,
+    pub attrs: Vec<Attr>,
+    pub children: Vec<Element>,
+}
+
+pub fn element(name: &'static str, attrs: Vec<Attr>, children: Vec<Element>) -> Element {
+    Element {
+        name,
+        attrs,
+        children,
+    }
+}
+
+#[derive(Default)]
+struct Strings(Vec<String>);
+
+impl Strings {
+    fn index(&mut self, s: &str) -> u32 {
+        if let Some(i) = self.0.iter().position(|x| x == s) {
+            return i as u32;
+        }
+        self.0.push(s.to_owned());
+        (self.0.len() - 1) as u32
+    }
+}
+
+fn chunk(kind: u16, header: &[u8], body: &[u8]) -> Vec<u8> {
+    let mut out = vec![];
+    out.extend_from_slice(&kind.to_le_bytes());
+    out.extend_from_slice(&((header.len() + 8) as u16).to_le_bytes());
+    out.extend_from_slice(&((header.len() + body.len() + 8) as u32).to_le_bytes());
+    out.extend_from_slice(header);
+    out.extend_from_slice(body);
+    out
+}
+
+/// Encodes a UTF-16 `ResStringPool` chunk.
+pub fn string_pool(strings: &[String]) -> Vec<u8> {
+    let mut data = vec![];
+    let mut offsets = vec![];
+    for s in strings {
+        offsets.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        let units: Vec<u16> = s.encode_utf16().collect();
+        data.extend_from_slice(&(units.len() as u16).to_le_bytes());
+        for unit in units {
+            data.extend_from_slice(&unit.to_le_bytes());
+        }
+        data.extend_from_slice(&[0, 0]);
+    }
+    while data.len() % 4 != 0 {
+        data.push(0);
+    }
+
+    let mut header = vec![];
+    header.extend_from_slice(&(strings.len() as u32).to_le_bytes());
+    header.extend_from_slice(&0u32.to_le_bytes()); // style count
+    header.extend_from_slice(&0u32.to_le_bytes()); // utf-16
+    header.extend_from_slice(&(28 + offsets.len() as u32).to_le_bytes());
+    header.extend_from_slice(&0u32.to_le_bytes());
+
+    let mut body = offsets;
+    body.extend_from_slice(&data);
+    chunk(0x0001, &header, &body)
+}
+
+fn write_element(element: &Element, strings: &mut Strings, out: &mut Vec<u8>) {
+    let ns = strings.index(ANDROID_NS);
+    let name = strings.index(element.name);
+
+    let mut ext = vec![];
+    ext.extend_from_slice(&u32::MAX.to_le_bytes());
+    ext.extend_from_slice(&name.to_le_bytes());
+    ext.extend_from_slice(&20u16.to_le_bytes());
+    ext.extend_from_slice(&20u16.to_le_bytes());
+    ext.extend_from_slice(&(element.attrs.len() as u16).to_le_bytes());
+    ext.extend_from_slice(&[0; 6]);
+    for attr in &element.attrs {
+        let attr_ns = if attr.android { ns } else { u32::MAX };
+        let attr_name = strings.index(attr.name);
+        let (raw, data_type, data) = match attr.value {
+            Value::Str(s) => {
+                let i = strings.index(s);
+                (i, TYPE_STRING, i)
+            }
+            Value::Int(i) => (u32::MAX, TYPE_INT_DEC, i),
+            Value::Ref(id) => (u32::MAX, TYPE_REFERENCE, id),
+            Value::Bool(b) => (u32::MAX, TYPE_INT_BOOLEAN, if b { u32::MAX } else { 0 }),
+        };
+        ext.extend_from_slice(&attr_ns.to_le_bytes());
+        ext.extend_from_slice(&attr_name.to_le_bytes());
+        ext.extend_from_slice(&raw.to_le_bytes());
+        ext.extend_from_slice(&8u16.to_le_bytes());
+        ext.push(0);
+        ext.push(data_type);
+        ext.extend_from_slice(&data.to_le_bytes());
+    }
+    out.extend(chunk(0x0102, &[1, 0, 0, 0, 0xff, 0xff, 0xff, 0xff], &ext));
+
+    for child in &element.children {
+        write_element(child, strings, out);
+    }
+
+    let mut end = vec![];
+    end.extend_from_slice(&u32::MAX.to_le_bytes());
+    end.extend_from_slice(&name.to_le_bytes());
+    out.extend(chunk(0x0103, &[1, 0, 0, 0, 0xff, 0xff, 0xff, 0xff], &end));
+}
+
+/// Compiles an element tree into binary XML.
+pub fn axml(root: &Element) -> Vec<u8> {
+    let mut strings = Strings::default();
+    let mut nodes = vec![];
+    write_element(root, &mut strings, &mut nodes);
+
+    let mut body = string_pool(&strings.0);
+    body.extend(nodes);
+    chunk(0x0003, &[], &body)
+}
+
+fn pb_varint(mut value: u64, out: &mut Vec<u8>) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn pb_bytes(number: u64, bytes: &[u8], out: &mut Vec<u8>) {
+    pb_varint(number << 3 | 2, out);
+    pb_varint(bytes.len() as u64, out);
+    out.extend_from_slice(bytes);
+}
+
+fn pb_uint(number: u64, value: u64, out: &mut Vec<u8>) {
+    pb_varint(number << 3, out);
+    pb_varint(value, out);
+}
+
+fn pb_element(element: &Element) -> Vec<u8> {
+    let mut out = vec![];
+    pb_bytes(3, element.name.as_bytes(), &mut out);
+    for attr in &element.attrs {
+        let mut attribute = vec![];
+        if attr.android {
+            pb_bytes(1, ANDROID_NS.as_bytes(), &mut attribute);
+        }
+        pb_bytes(2, attr.name.as_bytes(), &mut attribute);
+        let mut item = vec![];
+        match attr.value {
+            Value::Str(s) => {
+                pb_bytes(3, s.as_bytes(), &mut attribute);
+                let mut string = vec![];
+                pb_bytes(1, s.as_bytes(), &mut string);
+                pb_bytes(2, &string, &mut item);
+            }
+            Value::Int(i) => {
+                let mut primitive = vec![];
+                pb_uint(6, u64::from(i), &mut primitive);
+                pb_bytes(7, &primitive, &mut item);
+            }
+            Value::Ref(id) => {
+                let mut reference = vec![];
+                pb_uint(2, u64::from(id), &mut reference);
+                pb_bytes(1, &reference, &mut item);
+            }
+            Value::Bool(b) => {
+                let mut primitive = vec![];
+                pb_uint(8, u64::from(b), &mut primitive);
+                pb_bytes(7, &primitive, &mut item);
+            }
+        }
+        pb_bytes(6, &item, &mut attribute);
+        pb_bytes(4, &attribute, &mut out);
+    }
+    for child in &element.children {
+        let mut node = vec![];
+        pb_bytes(1, &pb_element(child), &mut node);
+        pb_bytes(5, &node, &mut out);
+    }
+    out
+}
+
+/// Encodes an element tree as an `aapt.pb.XmlNode`, the manifest format of
+/// app bundles.
+pub fn proto_xml(root: &Element) -> Vec<u8> {
+    let mut out = vec![];
+    pb_bytes(1, &pb_element(root), &mut out);
+    out
+}
+
+/// Encodes a `BundleConfig` naming the bundletool version.
+pub fn bundle_config(bundletool_version: &str) -> Vec<u8> {
+    let mut bundletool = vec![];
+    pb_bytes(2, bundletool_version.as_bytes(), &mut bundletool);
+    let mut out = vec![];
+    pb_bytes(1, &bundletool, &mut out);
+    out
+}
+
+/// A string value of a resource table, `density` 0 meaning unqualified.
+pub struct Resource {
+    pub id: u32,
+    pub language: &'static str,
+    pub density: u16,
+    pub value: &'static str,
+}
+
+/// Compiles `resources.arsc` with a single package holding string values.
+pub fn resource_table(resources: &[Resource]) -> Vec<u8> {
+    let mut strings = Strings::default();
+    let mut types = vec![];
+
+    let mut configs: Vec<(u8, &str, u16)> = resources
+        .iter()
+        .map(|r| ((r.id >> 16) as u8, r.language, r.density))
+        .collect();
+    configs.sort_unstable();
+    configs.dedup();
+    for (type_id, language, density) in configs {
+        let entries: Vec<&Resource> = resources
+            .iter()
+            .filter(|r| (r.id >> 16) as u8 == type_id && r.language == language)
+            .filter(|r| r.density == density)
+            .collect();
+        let count = entries
+            .iter()
+            .map(|r| (r.id & 0xffff) + 1)
+            .max()
+            .unwrap_or(0);
+
+        let mut offsets = vec![u32::MAX; count as usize];
+        let mut values = vec![];
+        for resource in entries {
+            offsets[(resource.id & 0xffff) as usize] = values.len() as u32;
+            values.extend_from_slice(&8u16.to_le_bytes());
+            values.extend_from_slice(&0u16.to_le_bytes());
+            values.extend_from_slice(&0u32.to_le_bytes());
+            values.extend_from_slice(&8u16.to_le_bytes());
+            values.push(0);
+            values.push(TYPE_STRING);
+            values.extend_from_slice(&strings.index(resource.value).to_le_bytes());
+        }
+
+        let mut config = [0u8; 64];
+        config[0] = 64;
+        config[8..8 + language.len()].copy_from_slice(language.as_bytes());
+        config[14..16].copy_from_slice(&density.to_le_bytes());
+
+        let mut header = vec![type_id, 0, 0, 0];
+        header.extend_from_slice(&count.to_le_bytes());
+        header.extend_from_slice(&(84 + count * 4).to_le_bytes());
+        header.extend_from_slice(&config);
+        let mut body: Vec<u8> = offsets.iter().flat_map(|o| o.to_le_bytes()).collect();
+        body.extend(values);
+        types.extend(chunk(0x0201, &header, &body));
+    }
+
+    let mut package = 0x7fu32.to_le_bytes().to_vec();
+    package.extend_from_slice(&[0; 256]);
+    package.extend_from_slice(&[0; 20]);
+    let mut body = string_pool(&strings.0);
+    body.extend(chunk(0x0200, &package, &types));
+    chunk(0x0002, &1u32.to_le_bytes(), &body)
+}
+
+/// Zips the given entries into an in-memory archive.
+pub fn zip(entries: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut writer = ZipWriter::new(Cursor::new(vec![]));
+    for (name, bytes) in entries {
+        writer
+            .start_file(*name, SimpleFileOptions::default())
+            .unwrap();
+        writer.write_all(bytes).unwrap();
+    }
+    writer.finish().unwrap().into_inner()
+}
+
+/// A manifest with the usual version and sdk attributes.
+pub fn manifest(package: &'static str, version_code: u32, version_name: &'static str) -> Element {
+    element(
+        "manifest",
+        vec![
+            android("versionCode", Value::Int(version_code)),
+            android("versionName", Value::Str(version_name)),
+            plain("package", Value::Str(package)),
+        ],
+        vec![
+            element(
+                "uses-sdk",
+                vec![
+                    android("minSdkVersion", Value::Int(21)),
+                    android("targetSdkVersion", Value::Int(34)),
+                ],
+                vec![],
+            ),
+            element(
+                "uses-permission",
+                vec![android("name", Value::Str("android.permission.INTERNET"))],
+                vec![],
+            ),
+            element("application", vec![], vec![]),
+        ],
+    )
+}
+
+/// Test signing key, a self-signed RSA 2048 certificate made with openssl.
+const KEY: &[u8] = include_bytes!("../fixtures/signing/key.pk8");
+pub const CERT: &[u8] = include_bytes!("../fixtures/signing/cert.der");
+/// `sha256sum tests/fixtures/signing/cert.der`
+pub const CERT_FINGERPRINT: &str =
+    "db7d00d6c840a11ccb64722b5df15cce2715332a3d93a1fb50783d5649460273";
+/// [`manifest`] signed with `jarsigner -digestalg SHA-256 -sigalg SHA256withRSA`
+pub const V1_SIGNED_APK: &[u8] = include_bytes!("../fixtures/signing/v1-signed.apk");
+
+fn prefixed(data: &[u8]) -> Vec<u8> {
+    let mut out = (data.len() as u32).to_le_bytes().to_vec();
+    out.extend_from_slice(data);
+    out
+}
+
+/// Adds an APK signature scheme v2 block (RSASSA-PKCS1-v1_5 with SHA-256) to
+/// an unsigned zip.
+pub fn sign_v2(unsigned: &[u8]) -> Vec<u8> {
+    use rsa::{
+        pkcs1v15::SigningKey,
+        pkcs8::{DecodePrivateKey, EncodePublicKey},
+        signature::{SignatureEncoding, Signer},
+        RsaPrivateKey,
+    };
+    use sha2::{Digest, Sha256};
+
+    let key = RsaPrivateKey::from_pkcs8_der(KEY).unwrap();
+    let public_key = key.to_public_key().to_public_key_der().unwrap();
+
+    // our zips carry no comment, so the record is the last 22 bytes
+    let eocd_offset = unsigned.len() - 22;
+    let cd_offset = u32::from_le_bytes(
+        unsigned[eocd_offset + 16..eocd_offset + 20]
+            .try_into()
+            .unwrap(),
+    ) as usize;
+
+    let sections = [
+        &unsigned[..cd_offset],
+        &unsigned[cd_offset..eocd_offset],
+        &unsigned[eocd_offset..],
+    ];
+    let chunks: Vec<&[u8]> = sections
+        .iter()
+        .flat_map(|s| s.chunks(1024 * 1024))
+        .collect();
+    let mut top = Sha256::new();
+    top.update([0x5a]);
+    top.update((chunks.len() as u32).to_le_bytes());
+    for chunk in chunks {
+        let mut hasher = Sha256::new();
+        hasher.update([0xa5]);
+        hasher.update((chunk.len() as u32).to_le_bytes());
+        hasher.update(chunk);
+        top.update(hasher.finalize());
+    }
+    let digest = top.finalize();
+
+    let mut digest_record = 0x0103u32.to_le_bytes().to_vec();
+    digest_record.extend(prefixed(&digest));
+    let mut signed_data = prefixed(&prefixed(&digest_record));
+    signed_data.extend(prefixed(&prefixed(CERT)));
+    signed_data.extend(prefixed(&[]));
+
+    let signature = SigningKey::<Sha256>::new(key).sign(&signed_data).to_vec();
+    let mut signature_record = 0x0103u32.to_le_bytes().to_vec();
+    signature_record.extend(prefixed(&signature));
+
+    let mut signer = prefixed(&signed_data);
+    signer.extend(prefixed(&prefixed(&signature_record)));
+    signer.extend(prefixed(public_key.as_bytes()));
+    let value = prefixed(&prefixed(&signer));
+
+    let mut pairs = ((value.len() + 4) as u64).to_le_bytes().to_vec();
+    pairs.extend(0x7109_871au32.to_le_bytes());
+    pairs.extend(value);
+    let size = (pairs.len() + 8 + 16) as u64;
+    let mut block = size.to_le_bytes().to_vec();
+    block.extend(pairs);
+    block.extend(size.to_le_bytes());
+    block.extend(b"APK Sig Block 42");
+
+    let mut eocd = unsigned[eocd_offset..].to_vec();
+    eocd[16..20].copy_from_slice(&((cd_offset + block.len()) as u32).to_le_bytes());
+
+    let mut signed = unsigned[..cd_offset].to_vec();
+    signed.extend(block);
+    signed.extend_from_slice(&unsigned[cd_offset..eocd_offset]);
+    signed.extend(eocd);
+    signed
+}
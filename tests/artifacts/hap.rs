@@ -0,0 +1,79 @@
+use apkraft::artifacts::{hap, ArtifactType};
+use serde_json::json;
+
+use super::fixtures;
+
+fn module_json() -> Vec<u8> {
+    serde_json::to_vec(&json!({
+        "app": {
+            "bundleName": "com.example.app",
+            "vendor": "example",
+            "versionCode": 1_000_002,
+            "versionName": "1.0.2",
+            "icon": "$media:app_icon",
+            "label": "$string:app_name",
+            "minAPIVersion": 50_000_012,
+            "targetAPIVersion": 50_005_017,
+        },
+        "module": {
+            "name": "entry",
+            "type": "entry",
+            "requestPermissions": [{ "name": "ohos.permission.INTERNET" }],
+        },
+    }))
+    .unwrap()
+}
+
+fn pack_info() -> Vec<u8> {
+    serde_json::to_vec(&json!({
+        "summary": {
+            "app": {
+                "bundleName": "com.example.app",
+                "version": { "code": 1_000_002, "name": "1.0.2" },
+            },
+            "modules": [
+                { "apiVersion": { "compatible": 12, "target": 14 } },
+                { "apiVersion": { "compatible": 11, "target": 14 } },
+            ],
+        },
+        "packages": [{ "name": "entry-default", "moduleType": "entry" }],
+    }))
+    .unwrap()
+}
+
+#[test]
+fn can_inspect_hap() {
+    let bytes = fixtures::zip(&[
+        (hap::MODULE_PATH, &module_json()),
+        (hap::PACK_INFO_PATH, &pack_info()),
+        ("ets/modules.abc", b""),
+    ]);
+    let meta = hap::inspect(&bytes).unwrap();
+
+    assert_eq!(meta.artifact_type, ArtifactType::Hap);
+    assert_eq!(meta.package_name, "com.example.app");
+    assert_eq!(meta.version_code, 1_000_002);
+    assert_eq!(meta.version_name.as_deref(), Some("1.0.2"));
+    assert_eq!(meta.min_sdk_version, Some(12));
+    assert_eq!(meta.target_sdk_version, Some(17));
+    assert_eq!(meta.permissions, vec!["ohos.permission.INTERNET"]);
+    assert_eq!(meta.label, None);
+}
+
+#[test]
+fn can_inspect_app_pack() {
+    let entry = fixtures::zip(&[(hap::MODULE_PATH, &module_json())]);
+    let bytes = fixtures::zip(&[
+        (hap::PACK_INFO_PATH, &pack_info()),
+        ("entry-default.hap", &entry),
+    ]);
+    let meta = hap::inspect_pack(&bytes).unwrap();
+
+    assert_eq!(meta.artifact_type, ArtifactType::HarmonyApp);
+    assert_eq!(meta.package_name, "com.example.app");
+    assert_eq!(meta.version_code, 1_000_002);
+    assert_eq!(meta.min_sdk_version, Some(12));
+    assert_eq!(meta.target_sdk_version, Some(14));
+    assert_eq!(ArtifactType::detect(&bytes), Some(ArtifactType::HarmonyApp));
+    assert_eq!(ArtifactType::detect(&entry), Some(ArtifactType::Hap));
+}
@@ -0,0 +1,68 @@
+use apkraft::{
+    artifacts::{inspector, ArtifactType},
+    models::platforms::PlatformCode,
+};
+
+use super::fixtures;
+
+#[test]
+fn registers_every_artifact_type() {
+    for artifact_type in ArtifactType::ALL {
+        let inspector = inspector::for_type(artifact_type).unwrap();
+
+        assert!(inspector.artifact_types().contains(&artifact_type));
+        assert_eq!(
+            inspector::for_platform(inspector.platform() as i16)
+                .unwrap()
+                .platform(),
+            inspector.platform()
+        );
+    }
+    assert!(inspector::for_platform(0).is_none());
+}
+
+#[test]
+fn bundles_are_not_installed() {
+    assert_eq!(
+        inspector::installed_on(ArtifactType::Apk),
+        Some(PlatformCode::Android)
+    );
+    assert_eq!(
+        inspector::installed_on(ArtifactType::Ipa),
+        Some(PlatformCode::Ios)
+    );
+    assert_eq!(
+        inspector::installed_on(ArtifactType::HarmonyApp),
+        Some(PlatformCode::HarmonyOs)
+    );
+    assert_eq!(inspector::installed_on(ArtifactType::Aab), None);
+}
+
+#[test]
+fn stores_unknown_archives_as_plain_files() {
+    let bytes = fixtures::zip(&[("readme.txt", b"hello")]);
+
+    assert!(inspector::inspect(&bytes).unwrap().is_none());
+    assert!(inspector::inspect(b"plain text").unwrap().is_none());
+}
+
+#[test]
+fn recognises_claimed_artifacts_by_name() {
+    assert_eq!(
+        inspector::claimed_type("app.APK", "application/octet-stream"),
+        Some(ArtifactType::Apk)
+    );
+    assert_eq!(
+        inspector::claimed_type("upload", "application/vnd.android.package-archive"),
+        Some(ArtifactType::Apk)
+    );
+    assert_eq!(
+        inspector::claimed_type("app.ipa", "application/octet-stream"),
+        Some(ArtifactType::Ipa)
+    );
+    assert_eq!(
+        inspector::claimed_type("entry.hap", "application/octet-stream"),
+        Some(ArtifactType::Hap)
+    );
+    assert_eq!(inspector::claimed_type("notes.txt", "text/plain"), None);
+}
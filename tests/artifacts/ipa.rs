@@ -0,0 +1,65 @@
+use apkraft::artifacts::{ipa, ArtifactType};
+use plist::{Dictionary, Value};
+
+use super::fixtures;
+
+fn info_plist(build: &str) -> Dictionary {
+    let mut info = Dictionary::new();
+    info.insert("CFBundleIdentifier".into(), "com.example.app".into());
+    info.insert("CFBundleShortVersionString".into(), "1.2.0".into());
+    info.insert("CFBundleVersion".into(), build.into());
+    info.insert("CFBundleName".into(), "Example".into());
+    info
+}
+
+fn ipa(info: &Dictionary, binary: bool) -> Vec<u8> {
+    let mut plist = vec![];
+    if binary {
+        Value::from(info.clone())
+            .to_writer_binary(&mut plist)
+            .unwrap();
+    } else {
+        Value::from(info.clone()).to_writer_xml(&mut plist).unwrap();
+    }
+    fixtures::zip(&[
+        ("Payload/Example.app/Info.plist", &plist),
+        ("Payload/Example.app/Example", b"\xcf\xfa\xed\xfe"),
+        (
+            "Payload/Example.app/PlugIns/Widget.appex/Info.plist",
+            b"<plist/>",
+        ),
+    ])
+}
+
+#[test]
+fn can_inspect_ipa() {
+    for binary in [false, true] {
+        let meta = ipa::inspect(&ipa(&info_plist("42"), binary)).unwrap();
+
+        assert_eq!(meta.artifact_type, ArtifactType::Ipa);
+        assert_eq!(meta.package_name, "com.example.app");
+        assert_eq!(meta.version_code, 42);
+        assert_eq!(meta.version_name.as_deref(), Some("1.2.0"));
+        assert_eq!(meta.label.as_deref(), Some("Example"));
+        assert!(meta.signing.is_none());
+    }
+}
+
+#[test]
+fn detects_ipa_by_content() {
+    let bytes = ipa(&info_plist("42"), true);
+
+    assert_eq!(ArtifactType::detect(&bytes), Some(ArtifactType::Ipa));
+    assert_eq!(
+        ArtifactType::detect(&fixtures::zip(&[("Payload/Example.app/Example", b"")])),
+        None
+    );
+}
+
+#[test]
+fn rejects_non_integer_build_number() {
+    let err = ipa::inspect(&ipa(&info_plist("1.2.0"), false)).unwrap_err();
+
+    assert!(err.to_string().contains("CFBundleVersion"), "{err}");
+    assert!(err.to_string().contains("not supported"), "{err}");
+}
@@ -0,0 +1,6 @@
+mod aab;
+mod apk;
+pub mod fixtures;
+mod hap;
+mod inspector;
+mod ipa;
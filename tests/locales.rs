@@ -0,0 +1,26 @@
+use apkraft::locales::Locales;
+use serde_json::json;
+
+#[test]
+fn orders_accept_language_by_quality() {
+    assert_eq!(
+        Locales::parse_accept_language("ja;q=0.5, zh-CN, en;q=0.8, *;q=0.1, fr;q=0"),
+        ["zh-CN", "en", "ja"]
+    );
+}
+
+#[test]
+fn falls_back_from_region_to_language_to_chain() {
+    let texts = json!({ "zh": "中文", "en_US": "English", "ja": "日本語" });
+    let fallback = ["en-us".to_string()];
+    let pick = |requested: &[&str]| {
+        Locales::new(requested, &fallback)
+            .pick(Some(&texts))
+            .map(ToOwned::to_owned)
+    };
+    assert_eq!(pick(&["zh-CN"]).as_deref(), Some("中文"));
+    assert_eq!(pick(&["JA"]).as_deref(), Some("日本語"));
+    assert_eq!(pick(&["fr", "de"]).as_deref(), Some("English"));
+    assert_eq!(Locales::new(["fr"], &[]).pick(Some(&texts)), None);
+    assert_eq!(Locales::new(["fr"], &fallback).pick(None), None);
+}
@@ -1,4 +1,7 @@
+mod artifacts;
+mod locales;
 mod models;
 mod requests;
+mod targeting;
 mod tasks;
 mod workers;
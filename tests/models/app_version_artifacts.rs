@@ -0,0 +1,104 @@
+use apkraft::{
+    app::App,
+    models::{
+        _entities::files,
+        app_version_artifacts::{ActiveModel, AddArtifact, ArtifactKind, Model},
+    },
+};
+use chrono::Utc;
+use loco_rs::testing::prelude::*;
+use sea_orm::{ActiveModelTrait, ActiveValue::Set};
+use serial_test::serial;
+
+use super::prepare_data::{create_app, create_file, create_version};
+
+fn artifact(id: i32, kind: &str, abi: Option<&str>, density: Option<i32>) -> Model {
+    let now = Utc::now().fixed_offset();
+    Model {
+        created_at: now,
+        updated_at: now,
+        id,
+        kind: kind.to_string(),
+        abi: abi.map(ToOwned::to_owned),
+        density,
+        app_version_id: 1,
+        file_id: id,
+    }
+}
+
+fn selected(artifacts: &[Model], abis: &[&str], density: Option<i32>) -> Option<i32> {
+    Model::select(artifacts, abis, density).map(|a| a.id)
+}
+
+#[test]
+fn prefers_primary_abi_over_universal() {
+    let artifacts = [
+        artifact(1, "bundle", None, None),
+        artifact(2, "universal", None, None),
+        artifact(3, "split", Some("armeabi-v7a"), None),
+        artifact(4, "split", Some("arm64-v8a"), None),
+    ];
+
+    assert_eq!(
+        selected(&artifacts, &["arm64-v8a", "armeabi-v7a"], None),
+        Some(4)
+    );
+    assert_eq!(selected(&artifacts, &["armeabi-v7a"], None), Some(3));
+    assert_eq!(selected(&artifacts, &["x86_64"], None), Some(2));
+    assert_eq!(selected(&artifacts, &[], None), Some(2));
+}
+
+#[test]
+fn prefers_nearest_higher_density() {
+    let artifacts = [
+        artifact(1, "split", Some("arm64-v8a"), Some(320)),
+        artifact(2, "split", Some("arm64-v8a"), Some(480)),
+        artifact(3, "split", Some("arm64-v8a"), Some(640)),
+    ];
+
+    assert_eq!(selected(&artifacts, &["arm64-v8a"], Some(480)), Some(2));
+    assert_eq!(selected(&artifacts, &["arm64-v8a"], Some(400)), Some(2));
+    assert_eq!(selected(&artifacts, &["arm64-v8a"], Some(720)), Some(3));
+}
+
+#[test]
+fn skips_incompatible_artifacts() {
+    let artifacts = [
+        artifact(1, "bundle", None, None),
+        artifact(2, "split", Some("x86_64"), None),
+    ];
+
+    assert_eq!(selected(&artifacts, &["arm64-v8a"], None), None);
+}
+
+#[tokio::test]
+#[serial]
+async fn rejects_foreign_and_unqualified_artifacts() {
+    let boot = boot_test::<App>().await.unwrap();
+    let db = &boot.app_context.db;
+    let app = create_app(db).await;
+    let version = create_version(db, app.id, 1).await;
+    let add = |file_id, kind, abi: Option<&str>| AddArtifact {
+        file_id,
+        kind,
+        abi: abi.map(ToOwned::to_owned),
+        density: None,
+    };
+
+    // an ipa does not install on android
+    let mut ipa: files::ActiveModel = create_file(db, 2).await.into();
+    ipa.artifact_type = Set(Some("ipa".to_string()));
+    let ipa = ipa.update(db).await.unwrap();
+    let data = add(ipa.id, ArtifactKind::Universal, None);
+    assert!(ActiveModel::create(db, version.id, &data).await.is_err());
+
+    // a split names what it is split by
+    let mut apk: files::ActiveModel = create_file(db, 3).await.into();
+    apk.artifact_type = Set(Some("apk".to_string()));
+    let apk = apk.update(db).await.unwrap();
+    let data = add(apk.id, ArtifactKind::Split, None);
+    assert!(ActiveModel::create(db, version.id, &data).await.is_err());
+    let data = add(apk.id, ArtifactKind::Split, Some("arm64-v8a"));
+    let artifact = ActiveModel::create(db, version.id, &data).await.unwrap();
+    assert_eq!(artifact.abi.as_deref(), Some("arm64-v8a"));
+}
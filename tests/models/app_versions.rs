@@ -1,7 +1,23 @@
-use apkraft::app::App;
+use apkraft::{
+    app::App,
+    locales::Locales,
+    models::{
+        _entities::{app_versions, files},
+        app_versions::{
+            rollout_bucket, ActiveModel, CreateAppVersion, PublishPayload, RollbackPayload,
+            YankPayload,
+        },
+        apps::{DryRunPayload, Model as AppModel, Revision, UpdateInfo},
+    },
+    targeting::Attributes,
+};
 use loco_rs::testing::prelude::*;
+use sea_orm::{ActiveModelTrait, ActiveValue::Set, DatabaseConnection, EntityTrait};
+use serde_json::json;
 use serial_test::serial;
 
+use super::prepare_data::{create_app, create_file, revision};
+
 macro_rules! configure_insta {
     ($($expr:expr),*) => {
         let mut settings = insta::Settings::clone_current();
@@ -29,3 +45,359 @@ async fn test_model() {
     // snapshot the result:
     // assert_debug_snapshot!(item);
 }
+
+#[test]
+fn buckets_devices_deterministically() {
+    let buckets: Vec<i16> = (0..1000)
+        .map(|i| rollout_bucket(7, &format!("device-{i}")))
+        .collect();
+    assert!(buckets.iter().all(|b| (0..100).contains(b)));
+    assert_eq!(buckets[42], rollout_bucket(7, "device-42"));
+
+    // roughly a tenth of the devices land in a 10% rollout
+    let inside = buckets.iter().filter(|&&b| b < 10).count();
+    assert!((50..150).contains(&inside), "{inside} devices inside");
+}
+
+#[test]
+fn rollouts_reach_different_devices_first() {
+    let moved = (0..1000)
+        .map(|i| format!("device-{i}"))
+        .filter(|device| rollout_bucket(7, device) != rollout_bucket(8, device))
+        .count();
+    assert!(moved > 900);
+}
+
+#[tokio::test]
+#[serial]
+async fn version_codes_only_go_up() {
+    let boot = boot_test::<App>().await.unwrap();
+    let db = &boot.app_context.db;
+    let app = create_app(db).await;
+
+    let mut created = vec![];
+    for code in [5, 5, 4, 6] {
+        created.push(release(db, app.id, code).await.is_ok());
+    }
+    assert_eq!(created, [true, false, false, true]);
+
+    let offered = |build_number| async move {
+        check_update(db, app.id, build_number)
+            .await
+            .latest_version
+            .map(|v| v.build_number)
+    };
+    assert_eq!(offered(5).await, Some(6));
+    assert_eq!(offered(6).await, None);
+    // a newer internal build is not moved back
+    assert_eq!(offered(7).await, None);
+}
+
+#[tokio::test]
+#[serial]
+async fn rolls_back_to_previous_release() {
+    let boot = boot_test::<App>().await.unwrap();
+    let db = &boot.app_context.db;
+    let app = create_app(db).await;
+    let mut versions = vec![];
+    for code in [1, 2, 3] {
+        versions.push(release(db, app.id, code).await.unwrap());
+    }
+
+    let payload = RollbackPayload {
+        reason: "crashes on start".to_string(),
+    };
+    let target = ActiveModel::rollback(db, versions[2].id, &payload)
+        .await
+        .unwrap();
+    assert_eq!(target.map(|v| v.version_code), Some(2));
+    // it is no longer published
+    assert!(ActiveModel::rollback(db, versions[2].id, &payload)
+        .await
+        .is_err());
+
+    let pulled = check_update(db, app.id, 3).await;
+    assert!(pulled.update_available && pulled.reinstall);
+    assert_eq!(pulled.latest_version.map(|v| v.build_number), Some(2));
+    let behind = check_update(db, app.id, 1).await;
+    assert!(behind.update_available && !behind.reinstall);
+    assert!(!check_update(db, app.id, 2).await.update_available);
+
+    let target = ActiveModel::rollback(db, versions[1].id, &payload)
+        .await
+        .unwrap();
+    assert_eq!(target.map(|v| v.version_code), Some(1));
+}
+
+#[tokio::test]
+#[serial]
+async fn pushes_devices_off_yanked_versions() {
+    let boot = boot_test::<App>().await.unwrap();
+    let db = &boot.app_context.db;
+    let app = create_app(db).await;
+    let mut versions = vec![];
+    for code in [1, 2, 3] {
+        versions.push(release(db, app.id, code).await.unwrap());
+    }
+
+    let yank = |reason: &str, force| YankPayload {
+        reason: reason.to_string(),
+        force,
+    };
+    // an older build, the current version stays
+    let target = ActiveModel::yank(db, versions[1].id, &yank("loses data", true))
+        .await
+        .unwrap();
+    assert_eq!(target.map(|v| v.version_code), Some(1));
+    let info = check_update(db, app.id, 2).await;
+    assert!(info.force_update && !info.reinstall);
+    assert_eq!(info.yank_reason.as_deref(), Some("loses data"));
+    assert_eq!(info.latest_version.map(|v| v.build_number), Some(3));
+    assert!(!check_update(db, app.id, 1).await.force_update);
+
+    // the current version, devices go back to the last good one
+    ActiveModel::yank(db, versions[2].id, &yank("crashes", false))
+        .await
+        .unwrap();
+    let info = check_update(db, app.id, 3).await;
+    assert!(info.reinstall && !info.force_update);
+    assert_eq!(info.latest_version.map(|v| v.build_number), Some(1));
+
+    let publish = PublishPayload {
+        publish: true,
+        rollout_percentage: None,
+    };
+    assert!(ActiveModel::publish(db, versions[2].id, &publish)
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+#[serial]
+async fn returns_changelog_since_running_build() {
+    let boot = boot_test::<App>().await.unwrap();
+    let db = &boot.app_context.db;
+    let app = create_app(db).await;
+    for code in [1, 2, 3, 4] {
+        release(db, app.id, code).await.unwrap();
+    }
+
+    let changelog = |revision| async move {
+        AppModel::check_update(
+            db,
+            app.id,
+            &revision,
+            &Attributes::default(),
+            &Locales::default(),
+        )
+        .await
+        .unwrap()
+        .latest_version
+        .unwrap()
+        .changelog
+        .into_iter()
+        .map(|entry| {
+            assert!(entry.published_at.is_some());
+            (entry.name, entry.release_notes.unwrap())
+        })
+        .collect::<Vec<_>>()
+    };
+    let entry = |code| (format!("1.{code}"), format!("notes of {code}"));
+    assert_eq!(changelog(revision(1)).await, [entry(4), entry(3), entry(2)]);
+    let capped = Revision {
+        changelog_limit: Some(2),
+        ..revision(1)
+    };
+    assert_eq!(changelog(capped).await, [entry(4), entry(3)]);
+}
+
+#[tokio::test]
+#[serial]
+async fn localizes_changelog() {
+    let boot = boot_test::<App>().await.unwrap();
+    let db = &boot.app_context.db;
+    let app = create_app(db).await;
+    release(db, app.id, 1).await.unwrap();
+    let version = release(db, app.id, 2).await.unwrap();
+    let mut version: app_versions::ActiveModel = version.into();
+    version.localized_release_notes = Set(Some(
+        serde_json::json!({ "zh-CN": "修复崩溃", "ja": "クラッシュを修正" }),
+    ));
+    version.update(db).await.unwrap();
+
+    let notes = |locales| async move {
+        AppModel::check_update(db, app.id, &revision(1), &Attributes::default(), &locales)
+            .await
+            .unwrap()
+            .latest_version
+            .unwrap()
+            .changelog
+            .remove(0)
+            .release_notes
+    };
+    let fallback = ["en".to_string()];
+    assert_eq!(
+        notes(Locales::new(["zh-CN"], &fallback)).await.as_deref(),
+        Some("修复崩溃")
+    );
+    assert_eq!(
+        notes(Locales::new(["ja-JP"], &fallback)).await.as_deref(),
+        Some("クラッシュを修正")
+    );
+    // untranslated notes are the last resort
+    assert_eq!(
+        notes(Locales::new(["fr"], &fallback)).await.as_deref(),
+        Some("notes of 2")
+    );
+}
+
+#[tokio::test]
+#[serial]
+async fn offers_devices_the_newest_version_targeting_them() {
+    let boot = boot_test::<App>().await.unwrap();
+    let db = &boot.app_context.db;
+    let app = create_app(db).await;
+    let mut versions = vec![];
+    for code in [1, 2, 3] {
+        versions.push(release(db, app.id, code).await.unwrap());
+    }
+    for (version, rule) in [
+        (
+            &versions[1],
+            json!({ "in": { "attribute": "tenant_id", "values": ["acme"] } }),
+        ),
+        (
+            &versions[2],
+            json!({ "in": { "attribute": "country", "values": ["CN"] } }),
+        ),
+    ] {
+        let mut version: app_versions::ActiveModel = version.clone().into();
+        version.targeting = Set(Some(rule));
+        version.update(db).await.unwrap();
+    }
+
+    let latest = |attributes: Vec<(&'static str, &'static str)>| async move {
+        let attributes = attributes.into_iter().collect::<Attributes>();
+        AppModel::check_update(db, app.id, &revision(0), &attributes, &Locales::default())
+            .await
+            .unwrap()
+            .latest_version
+            .map(|v| v.build_number)
+    };
+    assert_eq!(
+        latest(vec![("country", "CN"), ("tenant_id", "acme")]).await,
+        Some(3)
+    );
+    assert_eq!(
+        latest(vec![("country", "US"), ("tenant_id", "acme")]).await,
+        Some(2)
+    );
+    assert_eq!(latest(vec![("country", "US")]).await, Some(1));
+
+    let payload = DryRunPayload {
+        attributes: [("country", "US")].into_iter().collect(),
+        revision: Some(revision(0)),
+    };
+    let dry_run = AppModel::dry_run(db, app.id, &payload).await.unwrap();
+    let targeted: Vec<_> = dry_run
+        .versions
+        .iter()
+        .map(|v| (v.name.as_str(), v.targeted, v.trace.is_some()))
+        .collect();
+    assert_eq!(
+        targeted,
+        [
+            ("1.3", false, true),
+            ("1.2", false, true),
+            ("1.1", true, false)
+        ]
+    );
+    let update = dry_run.update.unwrap().latest_version.unwrap();
+    assert_eq!(update.build_number, 1);
+}
+
+#[tokio::test]
+#[serial]
+async fn offers_devices_the_newest_version_they_can_install() {
+    let boot = boot_test::<App>().await.unwrap();
+    let db = &boot.app_context.db;
+    let app = create_app(db).await;
+    for (code, min_sdk, abis, features) in [
+        (1, 21, json!([]), json!([])),
+        (2, 29, json!(["arm64-v8a", "x86_64"]), json!([])),
+        (3, 29, json!(["x86_64"]), json!([])),
+        (4, 29, json!([]), json!(["android.hardware.type.watch"])),
+    ] {
+        let version = release(db, app.id, code).await.unwrap();
+        let file = files::Entity::find_by_id(version.apk_file_id.unwrap())
+            .one(db)
+            .await
+            .unwrap()
+            .unwrap();
+        let mut file: files::ActiveModel = file.into();
+        file.min_sdk_version = Set(Some(min_sdk));
+        file.native_abis = Set(Some(abis));
+        file.required_features = Set(Some(features));
+        file.update(db).await.unwrap();
+    }
+
+    let latest = |sdk_int, abis: &str, features: &str| {
+        let revision = Revision {
+            sdk_int: Some(sdk_int),
+            abis: Some(abis.to_string()),
+            features: Some(features.to_string()),
+            ..revision(0)
+        };
+        async move {
+            AppModel::check_update(
+                db,
+                app.id,
+                &revision,
+                &Attributes::default(),
+                &Locales::default(),
+            )
+            .await
+            .unwrap()
+            .latest_version
+            .map(|v| v.build_number)
+        }
+    };
+    assert_eq!(latest(26, "arm64-v8a", "").await, Some(1));
+    assert_eq!(latest(30, "arm64-v8a", "").await, Some(2));
+    assert_eq!(latest(30, "x86_64", "").await, Some(3));
+    let watch = "android.hardware.type.watch, android.hardware.wifi";
+    assert_eq!(latest(30, "arm64-v8a", watch).await, Some(4));
+    assert_eq!(latest(19, "arm64-v8a", watch).await, None);
+}
+
+async fn release(
+    db: &DatabaseConnection,
+    app_id: i32,
+    code: i64,
+) -> loco_rs::Result<app_versions::Model> {
+    let data = CreateAppVersion {
+        app_id,
+        version_code: Some(code),
+        version_name: Some(format!("1.{code}")),
+        release_notes: Some(format!("notes of {code}")),
+        localized_release_notes: None,
+        apk_file_id: create_file(db, code).await.id,
+        publish_immediately: Some(true),
+        mandatory: None,
+        min_supported_version_code: None,
+        targeting: None,
+    };
+    ActiveModel::create(db, &data).await
+}
+
+async fn check_update(db: &DatabaseConnection, app_id: i32, build_number: i64) -> UpdateInfo {
+    AppModel::check_update(
+        db,
+        app_id,
+        &revision(build_number),
+        &Attributes::default(),
+        &Locales::default(),
+    )
+    .await
+    .unwrap()
+}
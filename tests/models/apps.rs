@@ -2,6 +2,8 @@ use apkraft::app::App;
 use loco_rs::testing::prelude::*;
 use serial_test::serial;
 
+use super::prepare_data::revision;
+
 macro_rules! configure_insta {
     ($($expr:expr),*) => {
         let mut settings = insta::Settings::clone_current();
@@ -29,3 +31,20 @@ async fn test_model() {
     // snapshot the result:
     // assert_debug_snapshot!(item);
 }
+
+#[test]
+fn forces_update_below_minimum() {
+    assert!(revision(9).must_update(Some(10), 12, &[]));
+    assert!(!revision(10).must_update(Some(10), 12, &[]));
+    assert!(!revision(9).must_update(None, 12, &[]));
+}
+
+#[test]
+fn forces_update_across_mandatory_versions() {
+    assert!(revision(9).must_update(None, 12, &[11]));
+    assert!(revision(9).must_update(None, 12, &[12]));
+    // already past the mandatory version
+    assert!(!revision(11).must_update(None, 12, &[11]));
+    // mandatory versions newer than the latest one do not count
+    assert!(!revision(9).must_update(None, 12, &[13]));
+}
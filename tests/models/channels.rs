@@ -0,0 +1,103 @@
+use apkraft::{
+    app::App,
+    locales::Locales,
+    models::{
+        apps::{Model as AppModel, Revision},
+        channels::{self, CreateChannel, PromotePayload, STABLE},
+    },
+    targeting::Attributes,
+};
+use loco_rs::testing::prelude::*;
+use sea_orm::DatabaseConnection;
+use serial_test::serial;
+
+use super::prepare_data::{create_app, create_version, revision};
+
+async fn latest(db: &DatabaseConnection, app_id: i32, channel: Option<&str>) -> Option<i64> {
+    AppModel::check_update(
+        db,
+        app_id,
+        &Revision {
+            channel: channel.map(ToOwned::to_owned),
+            ..revision(1)
+        },
+        &Attributes::default(),
+        &Locales::default(),
+    )
+    .await
+    .unwrap()
+    .latest_version
+    .map(|v| v.build_number)
+}
+
+#[tokio::test]
+#[serial]
+async fn promotes_through_channels() {
+    let boot = boot_test::<App>().await.unwrap();
+    let db = &boot.app_context.db;
+    let app = create_app(db).await;
+    for (name, position) in [("alpha", 0), ("beta", 1)] {
+        let data = CreateChannel {
+            name: name.to_string(),
+            position,
+            targeting: None,
+        };
+        channels::ActiveModel::create(db, app.id, &data)
+            .await
+            .unwrap();
+    }
+    let v2 = create_version(db, app.id, 2).await;
+    let v3 = create_version(db, app.id, 3).await;
+
+    let to = |to: Option<&str>| PromotePayload {
+        to: to.map(ToOwned::to_owned),
+        rollout_percentage: None,
+    };
+    assert_eq!(
+        channels::ActiveModel::promote(db, v2.id, &to(Some("alpha")))
+            .await
+            .unwrap(),
+        "alpha"
+    );
+    assert_eq!(latest(db, app.id, Some("alpha")).await, Some(2));
+    assert_eq!(latest(db, app.id, Some("beta")).await, None);
+    assert_eq!(latest(db, app.id, None).await, None);
+
+    // the next channel is taken when none is named
+    assert_eq!(
+        channels::ActiveModel::promote(db, v2.id, &to(None))
+            .await
+            .unwrap(),
+        "beta"
+    );
+    assert_eq!(
+        channels::ActiveModel::promote(db, v2.id, &to(None))
+            .await
+            .unwrap(),
+        STABLE
+    );
+    assert_eq!(latest(db, app.id, None).await, Some(2));
+
+    channels::ActiveModel::promote(db, v3.id, &to(Some("alpha")))
+        .await
+        .unwrap();
+    assert_eq!(latest(db, app.id, Some("alpha")).await, Some(3));
+    assert_eq!(latest(db, app.id, Some("beta")).await, Some(2));
+    assert_eq!(latest(db, app.id, Some(STABLE)).await, Some(2));
+
+    assert!(
+        channels::ActiveModel::promote(db, v3.id, &to(Some("nightly")))
+            .await
+            .is_err()
+    );
+    // devices asking for a channel the app does not have stay on stable
+    assert_eq!(latest(db, app.id, Some("nightly")).await, Some(2));
+
+    // channels do not go back to older versions
+    assert!(
+        channels::ActiveModel::promote(db, v2.id, &to(Some("alpha")))
+            .await
+            .is_err()
+    );
+    assert_eq!(latest(db, app.id, Some("alpha")).await, Some(3));
+}
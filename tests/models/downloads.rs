@@ -0,0 +1,82 @@
+use std::net::IpAddr;
+
+use apkraft::{
+    app::App,
+    models::{
+        _entities::files,
+        downloads::{self, anonymize, Client, DownloadStats, Downloads},
+        update_checks::StatsQuery,
+    },
+};
+use loco_rs::testing::prelude::*;
+use sea_orm::EntityTrait;
+use serial_test::serial;
+
+use super::prepare_data::{create_app, create_version};
+
+#[test]
+fn anonymizes_addresses_to_their_network() {
+    let ip = |s: &str| s.parse::<IpAddr>().unwrap();
+    assert_eq!(anonymize(ip("203.0.113.57")), ip("203.0.113.0"));
+    assert_eq!(
+        anonymize(ip("2001:db8:85a3:8d3:1319:8a2e:370:7348")),
+        ip("2001:db8:85a3::")
+    );
+}
+
+#[tokio::test]
+#[serial]
+async fn counts_downloads_and_bandwidth_by_version() {
+    let boot = boot_test::<App>().await.unwrap();
+    let db = &boot.app_context.db;
+    let app = create_app(db).await;
+    let first = create_version(db, app.id, 1).await;
+    let second = create_version(db, app.id, 2).await;
+    let client = Client {
+        ip: Some("203.0.113.0".parse().unwrap()),
+        user_agent: None,
+    };
+
+    // files made by `create_version` are a single byte
+    for (version, sent, completed) in [(&first, 1, true), (&second, 1, true), (&second, 0, false)] {
+        let file = files::Entity::find_by_id(version.apk_file_id.unwrap())
+            .one(db)
+            .await
+            .unwrap()
+            .unwrap();
+        let download = downloads::ActiveModel::start(db, &file, &client)
+            .await
+            .unwrap();
+        assert_eq!(download.app_id, Some(app.id));
+        assert_eq!(download.app_version_id, Some(version.id));
+        assert_eq!(download.client_ip.as_deref(), Some("203.0.113.0"));
+        downloads::ActiveModel::finish(db, download.id, sent, completed)
+            .await
+            .unwrap();
+    }
+
+    let since = StatsQuery { days: Some(7) }.since();
+    assert_eq!(
+        Downloads::version_stats(db, second.id, since)
+            .await
+            .unwrap(),
+        DownloadStats {
+            downloads: 2,
+            completed: 1,
+            bytes_sent: 1,
+        }
+    );
+    let by_version: Vec<_> = Downloads::app_stats(db, app.id, since)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|v| (v.app_version_id, v.downloads, v.completed))
+        .collect();
+    assert_eq!(
+        by_version,
+        [(Some(second.id), 2, 1), (Some(first.id), 1, 1)]
+    );
+
+    let unknown = Downloads::version_stats(db, 0, since).await.unwrap();
+    assert_eq!(unknown, DownloadStats::default());
+}
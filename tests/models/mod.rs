@@ -5,4 +5,10 @@ mod platforms;
 mod files;
 
 mod apps;
-mod app_versions;
\ No newline at end of file
+mod app_versions;
+mod app_version_artifacts;
+mod channels;
+mod update_checks;
+mod downloads;
+mod webhooks;
+pub mod prepare_data;
@@ -0,0 +1,76 @@
+use apkraft::models::{
+    _entities::{app_versions, apps, files, platforms},
+    apps::Revision,
+    platforms::PlatformCode,
+};
+use sea_orm::{ActiveModelTrait, ActiveValue::Set, DatabaseConnection};
+
+pub async fn create_app(db: &DatabaseConnection) -> apps::Model {
+    let platform = platforms::ActiveModel {
+        name: Set("android".to_string()),
+        code: Set(PlatformCode::Android as i16),
+        ..Default::default()
+    }
+    .insert(db)
+    .await
+    .unwrap();
+    apps::ActiveModel {
+        name: Set("demo".to_string()),
+        bundle_id: Set("com.example.demo".to_string()),
+        platform_id: Set(platform.id),
+        ..Default::default()
+    }
+    .insert(db)
+    .await
+    .unwrap()
+}
+
+/// A stored file without manifest, named after `code`.
+pub async fn create_file(db: &DatabaseConnection, code: i64) -> files::Model {
+    files::ActiveModel {
+        name: Set(format!("{code}.apk")),
+        mime: Set("application/vnd.android.package-archive".to_string()),
+        size_bytes: Set(1),
+        path: Set(format!("{code}.apk")),
+        checksum_sha256: Set(code.to_string()),
+        ..Default::default()
+    }
+    .insert(db)
+    .await
+    .unwrap()
+}
+
+/// An unpublished version inserted as is, bypassing the checks of
+/// `ActiveModel::create`.
+pub async fn create_version(
+    db: &DatabaseConnection,
+    app_id: i32,
+    code: i64,
+) -> app_versions::Model {
+    let file = create_file(db, code).await;
+    app_versions::ActiveModel {
+        app_id: Set(app_id),
+        version_code: Set(code),
+        version_name: Set(format!("1.{code}")),
+        apk_file_id: Set(Some(file.id)),
+        ..Default::default()
+    }
+    .insert(db)
+    .await
+    .unwrap()
+}
+
+/// A device running `build_number` that sends nothing else.
+pub fn revision(build_number: i64) -> Revision {
+    Revision {
+        version_name: String::new(),
+        build_number,
+        abis: None,
+        density: None,
+        channel: None,
+        changelog_limit: None,
+        device_id: None,
+        sdk_int: None,
+        features: None,
+    }
+}
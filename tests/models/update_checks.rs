@@ -0,0 +1,59 @@
+use apkraft::{
+    app::App,
+    models::{
+        apps::{Revision, UpdateInfo},
+        update_checks::{self, StatsQuery, UpdateChecks},
+    },
+};
+use loco_rs::testing::prelude::*;
+use serial_test::serial;
+
+use super::prepare_data::{create_app, revision};
+
+#[tokio::test]
+#[serial]
+async fn counts_devices_by_the_version_they_run() {
+    let boot = boot_test::<App>().await.unwrap();
+    let db = &boot.app_context.db;
+    let app = create_app(db).await;
+    let mut checks = vec![];
+    for (build_number, device_id) in [
+        (1, Some("a")),
+        (1, Some("b")),
+        (1, None),
+        (2, Some("a")),
+        (2, Some("a")),
+    ] {
+        let revision = Revision {
+            abis: Some("arm64-v8a, armeabi-v7a".to_string()),
+            device_id: device_id.map(ToOwned::to_owned),
+            sdk_int: Some(34),
+            ..revision(build_number)
+        };
+        checks.push(
+            update_checks::ActiveModel::record(db, app.id, &revision, &UpdateInfo::default())
+                .await
+                .unwrap(),
+        );
+    }
+    let check = checks.pop().unwrap();
+    assert_eq!(check.abi.as_deref(), Some("arm64-v8a"));
+    assert_eq!(check.device_hash, Some(update_checks::device_hash("a")));
+
+    let since = StatsQuery { days: Some(7) }.since();
+    let active: Vec<_> = UpdateChecks::active_devices(db, app.id, since)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|v| (v.version_code, v.devices))
+        .collect();
+    assert_eq!(active, [(2, 1), (1, 1)]);
+
+    let adoption = UpdateChecks::adoption(db, app.id, since).await.unwrap();
+    let points: Vec<_> = adoption
+        .iter()
+        .map(|p| (p.version_code, p.devices))
+        .collect();
+    assert_eq!(points, [(2, 1), (1, 2)]);
+    assert!((adoption[1].share - 2.0 / 3.0).abs() < 1e-9);
+}
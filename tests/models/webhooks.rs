@@ -0,0 +1,116 @@
+use apkraft::{
+    app::App,
+    models::{
+        _entities::{apps, webhooks},
+        webhook_deliveries::{self, Attempt, DeliveryStatus},
+        webhooks::{sign, Event, Webhooks},
+    },
+    settings::WebhookSettings,
+};
+use chrono::TimeDelta;
+use loco_rs::testing::prelude::*;
+use sea_orm::{ActiveModelTrait, ActiveValue::Set, DatabaseConnection};
+use serial_test::serial;
+
+use super::prepare_data::create_app;
+
+async fn create_webhook(
+    db: &DatabaseConnection,
+    app_id: Option<i32>,
+    events: &[Event],
+) -> webhooks::Model {
+    webhooks::ActiveModel {
+        url: Set("http://127.0.0.1:9/hook".to_string()),
+        secret: Set("secret".to_string()),
+        events: Set(serde_json::json!(events)),
+        app_id: Set(app_id),
+        ..Default::default()
+    }
+    .insert(db)
+    .await
+    .unwrap()
+}
+
+#[test]
+fn signs_payloads_with_hmac_sha256() {
+    // RFC 4231 test case 2
+    assert_eq!(
+        sign("Jefe", b"what do ya want for nothing?"),
+        "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+    );
+}
+
+#[test]
+fn backs_off_exponentially() {
+    let settings = WebhookSettings::default();
+    assert_eq!(settings.backoff(1), TimeDelta::seconds(30));
+    assert_eq!(settings.backoff(3), TimeDelta::seconds(120));
+}
+
+#[tokio::test]
+#[serial]
+async fn dispatches_to_subscribed_webhooks_and_gives_up_after_max_attempts() {
+    let boot = boot_test::<App>().await.unwrap();
+    let db = &boot.app_context.db;
+    let app = create_app(db).await;
+    let other = apps::ActiveModel {
+        name: Set("other".to_string()),
+        bundle_id: Set("com.example.other".to_string()),
+        platform_id: Set(app.platform_id),
+        ..Default::default()
+    }
+    .insert(db)
+    .await
+    .unwrap();
+    let global = create_webhook(db, None, &[]).await;
+    let published = create_webhook(db, Some(app.id), &[Event::VersionPublished]).await;
+    create_webhook(db, Some(other.id), &[]).await;
+    create_webhook(db, Some(app.id), &[Event::VersionDeleted]).await;
+
+    let subscribed: Vec<_> = Webhooks::find_subscribed(db, app.id, Event::VersionPublished)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|webhook| webhook.id)
+        .collect();
+    assert_eq!(subscribed, [global.id, published.id]);
+
+    let deliveries = webhook_deliveries::ActiveModel::dispatch(
+        db,
+        Event::VersionPublished,
+        app.id,
+        serde_json::json!({ "version": { "id": 1 } }),
+    )
+    .await
+    .unwrap();
+    assert_eq!(deliveries.len(), 2);
+    let delivery = deliveries.into_iter().next().unwrap();
+    assert_eq!(delivery.payload["event"], "version.published");
+    assert_eq!(delivery.payload["app_id"], app.id);
+
+    let settings = WebhookSettings {
+        max_attempts: 2,
+        ..Default::default()
+    };
+    let failed = Attempt::Responded {
+        status: 503,
+        body: "unavailable".to_string(),
+    };
+    let delivery =
+        webhook_deliveries::ActiveModel::record_attempt(db, delivery, &failed, &settings)
+            .await
+            .unwrap();
+    assert_eq!(delivery.status, DeliveryStatus::Pending.as_str());
+    assert_eq!(delivery.response_status, Some(503));
+    assert!(delivery.next_attempt_at > Some(delivery.updated_at));
+
+    let timeout = Attempt::Failed("timed out".to_string());
+    let delivery =
+        webhook_deliveries::ActiveModel::record_attempt(db, delivery, &timeout, &settings)
+            .await
+            .unwrap();
+    assert_eq!(delivery.status, DeliveryStatus::Failed.as_str());
+    assert_eq!(delivery.attempts, 2);
+    assert_eq!(delivery.next_attempt_at, None);
+    assert_eq!(delivery.error.as_deref(), Some("timed out"));
+}
@@ -2,6 +2,8 @@ use apkraft::app::App;
 use loco_rs::testing::prelude::*;
 use serial_test::serial;
 
+use crate::models::prepare_data::{create_app, create_version};
+
 #[tokio::test]
 #[serial]
 async fn can_get_app_versions() {
@@ -14,3 +16,29 @@ async fn can_get_app_versions() {
     })
     .await;
 }
+
+#[tokio::test]
+#[serial]
+async fn cannot_change_version_codes() {
+    request::<App, _, _>(|request, ctx| async move {
+        let app = create_app(&ctx.db).await;
+        create_version(&ctx.db, app.id, 1).await;
+        let version = create_version(&ctx.db, app.id, 2).await;
+
+        let res = request
+            .patch(&format!("/api/app-versions/{}", version.id))
+            .json(&serde_json::json!({ "version_code": 0, "release_notes": "moved" }))
+            .await;
+        assert_eq!(res.status_code(), 400);
+
+        let res = request
+            .patch(&format!("/api/app-versions/{}", version.id))
+            .json(&serde_json::json!({ "version_code": 2, "release_notes": "kept" }))
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let version = res.json::<serde_json::Value>();
+        assert_eq!(version["version_code"], 2);
+        assert_eq!(version["release_notes"], "kept");
+    })
+    .await;
+}
@@ -0,0 +1,153 @@
+use std::collections::BTreeSet;
+
+use apkraft::{
+    app::App,
+    artifacts::apk,
+    models::{
+        _entities::{apps, files, platforms},
+        files::remove_stored,
+        platforms::PlatformCode,
+    },
+};
+use axum_test::multipart::{MultipartForm, Part};
+use loco_rs::{app::AppContext, testing::prelude::*};
+use sea_orm::{ActiveModelTrait, ActiveValue::Set, EntityTrait};
+use serial_test::serial;
+
+use crate::artifacts::fixtures;
+
+fn signed_apk(version_code: u32) -> Vec<u8> {
+    let manifest = fixtures::axml(&fixtures::manifest(
+        "com.example.app",
+        version_code,
+        "1.2.0",
+    ));
+    fixtures::sign_v2(&fixtures::zip(&[
+        (apk::MANIFEST_PATH, &manifest),
+        ("classes.dex", b"dex\n035\0"),
+    ]))
+}
+
+fn build_form(apk: Vec<u8>) -> MultipartForm {
+    MultipartForm::new()
+        .add_part(
+            "file",
+            Part::bytes(apk)
+                .file_name("app.apk")
+                .mime_type("application/vnd.android.package-archive"),
+        )
+        .add_text("publish", "true")
+}
+
+async fn create_android(ctx: &AppContext) {
+    platforms::ActiveModel {
+        name: Set("android".to_string()),
+        code: Set(PlatformCode::Android as i16),
+        ..Default::default()
+    }
+    .insert(&ctx.db)
+    .await
+    .unwrap();
+}
+
+/// Names of the objects in the local upload storage.
+fn stored_objects() -> BTreeSet<String> {
+    std::fs::read_dir("static/uploads")
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .map(|entry| entry.file_name().to_string_lossy().into_owned())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+async fn remove_files(ctx: &AppContext) {
+    let keys: Vec<String> = files::Entity::find()
+        .all(&ctx.db)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|file| file.path)
+        .collect();
+    remove_stored(&ctx.storage, &keys).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn can_upload_builds() {
+    request::<App, _, _>(|request, ctx| async move {
+        create_android(&ctx).await;
+
+        let res = request
+            .post("/api/builds/")
+            .multipart(build_form(signed_apk(42)))
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let build = &res.json::<serde_json::Value>()["data"];
+        assert_eq!(build["app"]["bundle_id"], "com.example.app");
+        assert_eq!(build["version"]["version_code"], 42);
+        assert_eq!(build["app"]["current_version_id"], build["version"]["id"]);
+        assert_eq!(build["version"]["apk_file_id"], build["file"]["id"]);
+
+        // the next build of the package lands on the same app
+        let res = request
+            .post("/api/builds/")
+            .multipart(build_form(signed_apk(43)))
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let next = &res.json::<serde_json::Value>()["data"];
+        assert_eq!(next["app"]["id"], build["app"]["id"]);
+        assert_eq!(next["version"]["version_code"], 43);
+        assert_eq!(next["app"]["current_version_id"], next["version"]["id"]);
+        assert_eq!(apps::Entity::find().all(&ctx.db).await.unwrap().len(), 1);
+
+        remove_files(&ctx).await;
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn failed_builds_leave_nothing_behind() {
+    request::<App, _, _>(|request, ctx| async move {
+        create_android(&ctx).await;
+        let res = request
+            .post("/api/builds/")
+            .multipart(build_form(signed_apk(42)))
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let rows = files::Entity::find().all(&ctx.db).await.unwrap().len();
+        let objects = stored_objects();
+
+        // the artifact is stored before its version is refused as not newer
+        let res = request
+            .post("/api/builds/")
+            .multipart(build_form(signed_apk(42)))
+            .await;
+        assert_eq!(res.status_code(), 400);
+        assert_eq!(
+            files::Entity::find().all(&ctx.db).await.unwrap().len(),
+            rows
+        );
+        assert_eq!(stored_objects(), objects);
+
+        // only an aab can accompany an apk
+        let form = build_form(signed_apk(43)).add_part(
+            "bundle",
+            Part::bytes(signed_apk(43))
+                .file_name("app.aab")
+                .mime_type("application/octet-stream"),
+        );
+        let res = request.post("/api/builds/").multipart(form).await;
+        assert_eq!(res.status_code(), 400);
+        assert_eq!(
+            files::Entity::find().all(&ctx.db).await.unwrap().len(),
+            rows
+        );
+        assert_eq!(stored_objects(), objects);
+
+        remove_files(&ctx).await;
+    })
+    .await;
+}
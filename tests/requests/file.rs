@@ -1,7 +1,20 @@
-use apkraft::app::App;
+use std::{net::SocketAddr, time::Duration};
+
+use apkraft::{
+    app::App,
+    models::{
+        _entities::{downloads, files},
+        downloads::Downloads,
+        files::remove_stored,
+    },
+};
+use axum::{body::Bytes, http::header, Router};
 use loco_rs::testing::prelude::*;
+use sea_orm::{ActiveModelTrait, DatabaseConnection, EntityTrait, IntoActiveModel, QueryOrder};
 use serial_test::serial;
 
+use crate::models::prepare_data::{create_app, create_version};
+
 #[tokio::test]
 #[serial]
 async fn can_get_files() {
@@ -14,3 +27,110 @@ async fn can_get_files() {
     })
     .await;
 }
+
+/// Serves the app on a free local port, returning its url. Unlike the mock
+/// transport of `request` the body goes through hyper like in production.
+async fn serve(router: Router) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let service = router.into_make_service_with_connect_info::<SocketAddr>();
+    tokio::spawn(async move { axum::serve(listener, service).await });
+    format!("http://{addr}")
+}
+
+/// The download log once `count` downloads were finished, which happens
+/// off the request when the body is dropped.
+async fn finished(db: &DatabaseConnection, count: usize) -> Vec<downloads::Model> {
+    for _ in 0..100 {
+        let logged = Downloads::find()
+            .order_by_asc(downloads::Column::Id)
+            .all(db)
+            .await
+            .unwrap();
+        if logged.len() == count && logged.iter().all(|d| d.bytes_sent > 0) {
+            return logged;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("{count} downloads were not finished");
+}
+
+#[tokio::test]
+#[serial]
+async fn logs_downloads() {
+    let boot = boot_test::<App>().await.unwrap();
+    let ctx = &boot.app_context;
+    let db = &ctx.db;
+    let url = serve(boot.router.clone().unwrap()).await;
+    let client = reqwest::Client::new();
+
+    let app = create_app(db).await;
+    let version = create_version(db, app.id, 1).await;
+    // large enough for a client hanging up to stop the server midway rather
+    // than after it buffered everything
+    let bytes = Bytes::from(vec![7u8; 16 * 1024 * 1024]);
+    let len = i64::try_from(bytes.len()).unwrap();
+    let mut file = files::Entity::find_by_id(version.apk_file_id.unwrap())
+        .one(db)
+        .await
+        .unwrap()
+        .unwrap()
+        .into_active_model();
+    let key = file
+        .store(
+            &ctx.storage,
+            "demo.apk".to_string(),
+            "application/vnd.android.package-archive".to_string(),
+            &bytes,
+        )
+        .await
+        .unwrap();
+    file.update(db).await.unwrap();
+    let download = || {
+        client
+            .get(format!("{url}/api/files/static/{key}"))
+            .header(header::USER_AGENT, "apkraft-test")
+            .send()
+    };
+
+    let res = download().await.unwrap();
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.bytes().await.unwrap(), bytes);
+    let logged = finished(db, 1).await;
+    assert!(logged[0].completed);
+    assert_eq!(logged[0].bytes_sent, len);
+    assert_eq!(logged[0].app_id, Some(app.id));
+    assert_eq!(logged[0].app_version_id, Some(version.id));
+    assert_eq!(logged[0].user_agent.as_deref(), Some("apkraft-test"));
+
+    // a client hanging up after the first chunk
+    let mut res = download().await.unwrap();
+    assert!(res.chunk().await.unwrap().is_some());
+    drop(res);
+    let logged = finished(db, 2).await;
+    assert!(!logged[1].completed);
+    assert!(logged[1].bytes_sent < len);
+
+    let stats = |path: String| {
+        let request = client.get(format!("{url}{path}"));
+        async move {
+            let res = request.send().await.unwrap();
+            assert_eq!(res.status(), 200);
+            serde_json::from_str::<serde_json::Value>(&res.text().await.unwrap()).unwrap()
+        }
+    };
+    assert_eq!(
+        stats(format!("/api/app-versions/{}/downloads", version.id)).await,
+        serde_json::json!({
+            "downloads": 2,
+            "completed": 1,
+            "bytes_sent": len + logged[1].bytes_sent,
+        })
+    );
+    assert_eq!(
+        stats(format!("/api/apps/{}/downloads", app.id)).await[0]["app_version_id"],
+        version.id
+    );
+
+    remove_stored(&ctx.storage, &[key]).await;
+}
@@ -6,4 +6,7 @@ pub mod platform;
 pub mod file;
 
 pub mod app;
-pub mod app_version;
\ No newline at end of file
+pub mod app_version;
+pub mod build;
+pub mod subscription;
+pub mod webhook;
@@ -0,0 +1,81 @@
+use apkraft::{
+    app::App,
+    models::{_entities::app_versions, subscriptions::Subscriptions},
+};
+use loco_rs::testing::prelude::*;
+use sea_orm::{ActiveModelTrait, ActiveValue::Set, EntityTrait, IntoActiveModel};
+use serial_test::serial;
+
+use super::prepare_data::{auth_header, init_user_login};
+use crate::models::prepare_data::{create_app, create_version};
+
+#[tokio::test]
+#[serial]
+async fn announces_published_versions_to_subscribers() {
+    request::<App, _, _>(|request, ctx| async move {
+        let user = init_user_login(&request, &ctx).await;
+        let (auth_key, auth_value) = auth_header(&user.token);
+        let db = &ctx.db;
+        let app = create_app(db).await;
+        let version = create_version(db, app.id, 1).await;
+        let mut notes = version.into_active_model();
+        notes.release_notes = Set(Some("Faster startup".to_string()));
+        let version: app_versions::Model = notes.update(db).await.unwrap();
+
+        let res = request
+            .post("/api/subscriptions")
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&serde_json::json!({ "app_id": app.id }))
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let res = request
+            .post("/api/subscriptions")
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&serde_json::json!({ "app_id": app.id, "channel": "nightly" }))
+            .await;
+        assert_eq!(res.status_code(), 400);
+        let subscriptions = Subscriptions::find().all(db).await.unwrap();
+        assert_eq!(subscriptions.len(), 1);
+
+        let sent = ctx.mailer.as_ref().unwrap().deliveries().count;
+        let res = request
+            .post(&format!("/api/app-versions/{}/publish", version.id))
+            .json(&serde_json::json!({ "publish": true, "rollout_percentage": 20 }))
+            .await;
+        assert_eq!(res.status_code(), 200);
+        assert_eq!(
+            ctx.mailer.as_ref().unwrap().deliveries().count,
+            sent,
+            "a partial rollout is not announced"
+        );
+        let res = request
+            .post(&format!("/api/app-versions/{}/rollout", version.id))
+            .json(&serde_json::json!({ "percentage": 100 }))
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let deliveries = ctx.mailer.as_ref().unwrap().deliveries();
+        assert_eq!(
+            deliveries.count,
+            sent + 1,
+            "the subscriber should be mailed"
+        );
+        let message = deliveries.messages.last().unwrap();
+        assert!(message.contains("To: test@loco.com"));
+        assert!(message.contains("Faster startup"));
+        assert!(message.contains("/api/subscriptions/unsubscribe/"));
+
+        // opening the link only asks for confirmation
+        let res = request.get(&subscriptions[0].unsubscribe_path()).await;
+        assert_eq!(res.status_code(), 200);
+        assert!(res.text().contains("method=\"post\""));
+        assert_eq!(Subscriptions::find().all(db).await.unwrap().len(), 1);
+        let res = request.post(&subscriptions[0].unsubscribe_path()).await;
+        assert_eq!(res.status_code(), 200);
+        assert!(Subscriptions::find().all(db).await.unwrap().is_empty());
+        let res = request.get(&subscriptions[0].unsubscribe_path()).await;
+        assert_eq!(res.status_code(), 404);
+        let res = request.post(&subscriptions[0].unsubscribe_path()).await;
+        assert_eq!(res.status_code(), 404);
+    })
+    .await;
+}
@@ -0,0 +1,30 @@
+use apkraft::app::App;
+use loco_rs::testing::prelude::*;
+use serial_test::serial;
+
+#[tokio::test]
+#[serial]
+async fn only_returns_secrets_on_create() {
+    request::<App, _, _>(|request, _ctx| async move {
+        let res = request
+            .post("/api/webhooks/")
+            .json(&serde_json::json!({ "url": "http://127.0.0.1:9/hook", "events": [] }))
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let created = res.json::<serde_json::Value>();
+        assert!(created["secret"].as_str().is_some_and(|s| !s.is_empty()));
+
+        let res = request
+            .get(&format!("/api/webhooks/{}", created["id"]))
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let webhook = res.json::<serde_json::Value>();
+        assert_eq!(webhook["url"], "http://127.0.0.1:9/hook");
+        assert!(webhook.get("secret").is_none());
+
+        let res = request.get("/api/webhooks/").await;
+        assert_eq!(res.status_code(), 200);
+        assert!(res.json::<serde_json::Value>()[0].get("secret").is_none());
+    })
+    .await;
+}
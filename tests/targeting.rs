@@ -0,0 +1,80 @@
+use apkraft::targeting::{Attributes, Rule};
+use serde_json::json;
+
+fn rule(value: serde_json::Value) -> Rule {
+    serde_json::from_value(value).unwrap()
+}
+
+#[test]
+fn matches_devices_by_attributes() {
+    let rule = rule(json!({ "all": [
+        { "in": { "attribute": "country", "values": ["CN", "HK"] } },
+        { "gte": { "attribute": "os_version", "value": 13 } },
+        { "not": { "in": { "attribute": "model", "values": ["SM-G960F"] } } }
+    ] }));
+    let device = |country: &str, os_version: &str, model: &str| {
+        [
+            ("country", country),
+            ("os_version", os_version),
+            ("model", model),
+        ]
+        .into_iter()
+        .collect::<Attributes>()
+    };
+    assert!(rule.matches(&device("cn", "13", "Pixel 8")));
+    assert!(rule.matches(&device("HK", "13.10", "Pixel 8")));
+    assert!(!rule.matches(&device("US", "14", "Pixel 8")));
+    assert!(!rule.matches(&device("CN", "12.9", "Pixel 8")));
+    assert!(!rule.matches(&device("CN", "14", "SM-G960F")));
+    assert!(!rule.matches(&Attributes::default()));
+}
+
+#[test]
+fn compares_versions_numerically() {
+    let below = rule(json!({ "lt": { "attribute": "os_version", "value": "13.10" } }));
+    let os = |version| {
+        [("os_version", version)]
+            .into_iter()
+            .collect::<Attributes>()
+    };
+    assert!(below.matches(&os("13.4")));
+    assert!(below.matches(&os("9")));
+    assert!(!below.matches(&os("13.10.0")));
+    assert!(!below.matches(&os("14")));
+}
+
+#[test]
+fn traces_every_rule() {
+    let rule = rule(json!({ "any": [
+        { "in": { "attribute": "tenant_id", "values": ["acme"] } },
+        { "gte": { "attribute": "os_version", "value": "14" } }
+    ] }));
+    let attributes = [("tenant_id", "acme"), ("os_version", "13")]
+        .into_iter()
+        .collect::<Attributes>();
+    let trace = rule.trace(&attributes);
+    assert!(trace.matched);
+    assert_eq!(
+        serde_json::to_value(&trace).unwrap(),
+        json!({
+            "rule": "any",
+            "matched": true,
+            "children": [
+                { "rule": "tenant_id in [acme]", "value": "acme", "matched": true },
+                { "rule": "os_version >= 14", "value": "13", "matched": false }
+            ]
+        })
+    );
+}
+
+#[test]
+fn admits_everyone_without_a_rule() {
+    let attributes = Attributes::default();
+    assert!(Rule::admits(None, &attributes));
+    assert!(Rule::admits(Some(&json!({ "all": [] })), &attributes));
+    assert!(!Rule::admits(Some(&json!({ "unknown": [] })), &attributes));
+    assert!(
+        serde_json::from_value::<Rule>(json!({ "in": { "attribute": "a", "values": [[1]] } }))
+            .is_err()
+    );
+}
@@ -1 +1 @@
-
+mod publish_scheduled;
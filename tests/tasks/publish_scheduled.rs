@@ -0,0 +1,119 @@
+use apkraft::{
+    app::App,
+    models::{
+        _entities::{app_versions, apps},
+        app_versions::{ActiveModel, RolloutState, SchedulePayload, YankPayload},
+    },
+};
+use chrono::{Duration, Timelike, Utc};
+use loco_rs::{boot::run_task, task, testing::prelude::*};
+use sea_orm::{ActiveModelTrait, ActiveValue::Set, EntityTrait};
+use serial_test::serial;
+
+use crate::models::prepare_data::{create_app, create_version};
+
+#[tokio::test]
+#[serial]
+async fn publishes_versions_once_due() {
+    let boot = boot_test::<App>().await.unwrap();
+    let db = &boot.app_context.db;
+    let app = create_app(db).await;
+    let due = create_version(db, app.id, 1).await;
+    let later = create_version(db, app.id, 2).await;
+
+    let schedule = |publish_at| SchedulePayload {
+        publish_at,
+        rollout_percentage: None,
+    };
+    // whole seconds, timestamps are stored in microseconds
+    let now = Utc::now().with_nanosecond(0).unwrap().fixed_offset();
+    let past = now - Duration::minutes(1);
+    let tomorrow = now + Duration::days(1);
+    assert!(ActiveModel::schedule(db, due.id, &schedule(past))
+        .await
+        .is_err());
+    let scheduled = ActiveModel::schedule(db, later.id, &schedule(tomorrow))
+        .await
+        .unwrap();
+    assert_eq!(scheduled.scheduled_publish_at, Some(tomorrow));
+    ActiveModel::unschedule(db, later.id).await.unwrap();
+    assert!(ActiveModel::unschedule(db, later.id).await.is_err());
+    ActiveModel::schedule(db, later.id, &schedule(tomorrow))
+        .await
+        .unwrap();
+
+    // a schedule that has come since it was set
+    let mut version: app_versions::ActiveModel = due.into();
+    version.scheduled_publish_at = Set(Some(past));
+    let due = version.update(db).await.unwrap();
+
+    run_task::<App>(
+        &boot.app_context,
+        Some(&"publish_scheduled".to_string()),
+        &task::Vars::default(),
+    )
+    .await
+    .unwrap();
+
+    let find = |id| async move {
+        app_versions::Entity::find_by_id(id)
+            .one(db)
+            .await
+            .unwrap()
+            .unwrap()
+    };
+    let published = find(due.id).await;
+    assert!(published.published_at.is_some());
+    assert_eq!(published.scheduled_publish_at, None);
+    assert_eq!(
+        published.rollout_state.as_deref(),
+        Some(RolloutState::Completed.as_str())
+    );
+    let app = apps::Entity::find_by_id(app.id)
+        .one(db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(app.current_version_id, Some(due.id));
+    // a run that read the version before it was published cannot take it again
+    assert!(!ActiveModel::claim_scheduled(db, &due).await.unwrap());
+
+    let pending = find(later.id).await;
+    assert!(pending.published_at.is_none());
+    assert_eq!(pending.scheduled_publish_at, Some(tomorrow));
+}
+
+#[tokio::test]
+#[serial]
+async fn yanked_versions_are_never_due() {
+    let boot = boot_test::<App>().await.unwrap();
+    let db = &boot.app_context.db;
+    let app = create_app(db).await;
+    let version = create_version(db, app.id, 1).await;
+
+    let tomorrow = Utc::now().fixed_offset() + Duration::days(1);
+    let payload = SchedulePayload {
+        publish_at: tomorrow,
+        rollout_percentage: Some(10),
+    };
+    ActiveModel::schedule(db, version.id, &payload)
+        .await
+        .unwrap();
+    let payload = YankPayload {
+        reason: "crashes on start".to_string(),
+        force: false,
+    };
+    ActiveModel::yank(db, version.id, &payload).await.unwrap();
+
+    let yanked = app_versions::Entity::find_by_id(version.id)
+        .one(db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(yanked.scheduled_publish_at, None);
+    assert_eq!(yanked.scheduled_rollout_percentage, None);
+    assert!(app_versions::Entity::find_due(db, tomorrow)
+        .await
+        .unwrap()
+        .is_empty());
+}
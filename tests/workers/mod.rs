@@ -1 +1,2 @@
-
+mod patcher;
+mod webhook;
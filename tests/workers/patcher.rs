@@ -0,0 +1,23 @@
+use std::sync::Arc;
+
+use apkraft::workers::patcher::diff;
+use qbsdiff::Bspatch;
+
+#[tokio::test]
+async fn patch_restores_target() {
+    let source: Vec<u8> = (0..64 * 1024).map(|i| (i % 251) as u8).collect();
+    let mut target = source.clone();
+    target[1000..1100].fill(7);
+    target.extend_from_slice(b"appended in the new version");
+
+    let patch = diff(source.clone(), Arc::new(target.clone())).await.unwrap();
+    assert!(patch.starts_with(b"BSDIFF40"));
+    assert!(patch.len() < target.len() / 10);
+
+    let mut restored = vec![];
+    Bspatch::new(&patch)
+        .unwrap()
+        .apply(&source, &mut restored)
+        .unwrap();
+    assert_eq!(restored, target);
+}
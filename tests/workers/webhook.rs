@@ -0,0 +1,125 @@
+use std::sync::{
+    atomic::{AtomicU16, Ordering},
+    Arc, Mutex,
+};
+
+use apkraft::{
+    app::App,
+    models::{
+        _entities::{webhook_deliveries, webhooks},
+        app_versions::{ActiveModel, PublishPayload},
+        webhook_deliveries::{DeliveryStatus, WebhookDeliveries},
+        webhooks::{sign, Event},
+    },
+    workers::webhook::{WebhookWorker, WebhookWorkerArgs},
+};
+use axum::{extract::State, http::HeaderMap, http::StatusCode, routing::post, Router};
+use loco_rs::{bgworker::BackgroundWorker, model::query::PaginationQuery, testing::prelude::*};
+use sea_orm::{ActiveModelTrait, ActiveValue::Set, EntityTrait, IntoActiveModel};
+use serial_test::serial;
+
+use crate::models::prepare_data::{create_app, create_version};
+
+#[derive(Clone, Default)]
+struct Receiver {
+    /// Status the next requests are answered with
+    status: Arc<AtomicU16>,
+    received: Arc<Mutex<Vec<(HeaderMap, String)>>>,
+}
+
+async fn receive(State(receiver): State<Receiver>, headers: HeaderMap, body: String) -> StatusCode {
+    receiver.received.lock().unwrap().push((headers, body));
+    StatusCode::from_u16(receiver.status.load(Ordering::SeqCst)).unwrap()
+}
+
+/// Serves a receiver on a free local port, returning its url.
+async fn serve(receiver: Receiver) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let router = Router::new()
+        .route("/hook", post(receive))
+        .with_state(receiver);
+    tokio::spawn(async move { axum::serve(listener, router).await });
+    format!("http://{addr}/hook")
+}
+
+#[tokio::test]
+#[serial]
+async fn delivers_signed_payloads_and_retries_failures() {
+    let boot = boot_test::<App>().await.unwrap();
+    let ctx = &boot.app_context;
+    let db = &ctx.db;
+    let receiver = Receiver::default();
+    receiver.status.store(500, Ordering::SeqCst);
+    let url = serve(receiver.clone()).await;
+
+    let app = create_app(db).await;
+    let webhook = webhooks::ActiveModel {
+        url: Set(url),
+        secret: Set("s3cret".to_string()),
+        events: Set(serde_json::json!([Event::VersionPublished])),
+        app_id: Set(Some(app.id)),
+        ..Default::default()
+    }
+    .insert(db)
+    .await
+    .unwrap();
+    let version = create_version(db, app.id, 1).await;
+    let payload = PublishPayload {
+        publish: true,
+        rollout_percentage: None,
+    };
+    ActiveModel::publish(db, version.id, &payload)
+        .await
+        .unwrap();
+
+    // the test queue runs jobs as they are queued
+    WebhookWorker::enqueue(ctx).await.unwrap();
+    let page = PaginationQuery {
+        page_size: 10,
+        page: 1,
+    };
+    let delivery = WebhookDeliveries::find_by_webhook(db, webhook.id, &page)
+        .await
+        .unwrap()
+        .page
+        .remove(0);
+    assert_eq!(delivery.status, DeliveryStatus::Pending.as_str());
+    assert_eq!(delivery.attempts, 1);
+    assert_eq!(delivery.response_status, Some(500));
+    assert!(delivery.next_attempt_at.unwrap() > chrono::Utc::now().fixed_offset());
+
+    // not attempted again before the backoff has passed
+    let args = || WebhookWorkerArgs {
+        delivery_id: delivery.id,
+    };
+    let worker = WebhookWorker::build(ctx);
+    worker.perform(args()).await.unwrap();
+    WebhookWorker::enqueue(ctx).await.unwrap();
+    assert_eq!(receiver.received.lock().unwrap().len(), 1);
+
+    receiver.status.store(204, Ordering::SeqCst);
+    let mut due = delivery.into_active_model();
+    due.next_attempt_at = Set(Some(chrono::Utc::now().fixed_offset()));
+    let delivery = due.update(db).await.unwrap();
+    worker.perform(args()).await.unwrap();
+    let delivery = webhook_deliveries::Entity::find_by_id(delivery.id)
+        .one(db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(delivery.status, DeliveryStatus::Delivered.as_str());
+    assert_eq!(delivery.attempts, 2);
+    assert!(delivery.delivered_at.is_some());
+
+    let received = receiver.received.lock().unwrap();
+    assert_eq!(received.len(), 2);
+    let (headers, body) = &received[1];
+    assert_eq!(headers["x-apkraft-event"], "version.published");
+    assert_eq!(
+        headers["x-apkraft-signature-256"],
+        sign("s3cret", body.as_bytes()).as_str()
+    );
+    let body: serde_json::Value = serde_json::from_str(body).unwrap();
+    assert_eq!(body["data"]["version"]["id"], version.id);
+}